@@ -1,6 +1,7 @@
 use crate::{error::{Error,
                     Result},
-            hab_http::ApiClient,
+            hab_http::{ApiClient,
+                       ProxyAuth},
             response,
             BuildOnUpload,
             DisplayProgress,
@@ -40,6 +41,7 @@ use reqwest::{header::CONTENT_LENGTH,
               Body,
               IntoUrl,
               RequestBuilder,
+              Response,
               StatusCode};
 use serde::{Deserialize,
             Serialize};
@@ -52,7 +54,9 @@ use std::{fs::{self,
           path::{Path,
                  PathBuf},
           string::ToString,
-          time::Duration};
+          sync::Mutex,
+          time::{Duration,
+                 Instant}};
 use tee::TeeReader;
 use tokio::task;
 use tokio_util::{codec::{BytesCodec,
@@ -141,7 +145,36 @@ pub struct OriginChannelWithPromotion {
     pub promoted_at: String,
 }
 
-pub struct BuilderAPIClient(ApiClient);
+/// The amount of data downloaded by a `BuilderAPIClient`, and how long
+/// it took.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransferStats {
+    pub bytes:   u64,
+    pub elapsed: Duration,
+}
+
+impl TransferStats {
+    /// The effective download rate in bytes per second.
+    pub fn bytes_per_sec(&self) -> u64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            (self.bytes as f64 / secs) as u64
+        } else {
+            self.bytes
+        }
+    }
+}
+
+/// How a client's downloads are limited, and what they have added up to.
+#[derive(Default)]
+struct Downloads {
+    /// The most bytes per second to download at, if limited.
+    bandwidth_limit: Option<u64>,
+    /// Totals over every download made by this client.
+    transferred:     Mutex<TransferStats>,
+}
+
+pub struct BuilderAPIClient(ApiClient, Downloads);
 
 impl BuilderAPIClient {
     pub fn new<U>(endpoint: U,
@@ -150,16 +183,50 @@ impl BuilderAPIClient {
                   fs_root_path: Option<&Path>)
                   -> Result<Self>
         where U: IntoUrl
+    {
+        let proxy_auth = ProxyAuth::from_env().map_err(Error::HabitatHttpClient)?;
+        Self::new_with_proxy_auth(endpoint,
+                                  product,
+                                  version,
+                                  fs_root_path,
+                                  proxy_auth.as_ref())
+    }
+
+    /// A client that authenticates with any proxy using `proxy_auth`,
+    /// rather than the authentication configured in the environment.
+    pub fn new_with_proxy_auth<U>(endpoint: U,
+                                  product: &str,
+                                  version: &str,
+                                  fs_root_path: Option<&Path>,
+                                  proxy_auth: Option<&ProxyAuth>)
+                                  -> Result<Self>
+        where U: IntoUrl
     {
         let mut endpoint = endpoint.into_url().map_err(Error::ReqwestError)?;
         if !endpoint.cannot_be_a_base() && endpoint.path() == "/" {
             endpoint.set_path(DEFAULT_API_PATH);
         }
-        let client = BuilderAPIClient(
-            ApiClient::new(endpoint, product, version, fs_root_path)
-                .map_err(Error::HabitatHttpClient)?,
-        );
-        Ok(client)
+        let client = ApiClient::new_with_proxy_auth(endpoint,
+                                                    product,
+                                                    version,
+                                                    fs_root_path,
+                                                    proxy_auth).map_err(Error::HabitatHttpClient)?;
+        Ok(BuilderAPIClient(client, Downloads::default()))
+    }
+
+    /// Limit downloads to `bytes_per_sec`. `None` or zero leaves them
+    /// unlimited.
+    pub fn with_bandwidth_limit(mut self, bytes_per_sec: Option<u64>) -> Self {
+        self.1.bandwidth_limit = bytes_per_sec.filter(|limit| *limit > 0);
+        self
+    }
+
+    /// The totals over every download this client has made.
+    pub fn transfer_stats(&self) -> TransferStats {
+        *self.1
+             .transferred
+             .lock()
+             .expect("Transfer stats lock poisoned")
     }
 
     pub fn create<U>(endpoint: U,
@@ -195,7 +262,7 @@ impl BuilderAPIClient {
         let dst_file_path = dst_path.join(file_name);
         let w = AtomicWriter::new_with_permissions(&dst_file_path, permissions)?;
         let content_length = response::get_header(&resp, CONTENT_LENGTH);
-        let mut body = Cursor::new(resp.bytes().await?);
+        let mut body = Cursor::new(self.read_body(resp).await?);
         // Blocking IO is used because of `DisplayProgress` which relies on the `Write` trait.
         task::spawn_blocking(move || {
             w.with_writer(|mut f| {
@@ -215,6 +282,30 @@ impl BuilderAPIClient {
         }).await?
     }
 
+    /// Read the whole body of `resp`, no faster than the bandwidth
+    /// limit, adding it to the transfer stats.
+    async fn read_body(&self, mut resp: Response) -> Result<Vec<u8>> {
+        let start = Instant::now();
+        let mut body = Vec::new();
+        while let Some(chunk) = resp.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if let Some(limit) = self.1.bandwidth_limit {
+                // Wait until the bytes read so far are within the limit
+                let due = Duration::from_secs_f64(body.len() as f64 / limit as f64);
+                if let Some(ahead) = due.checked_sub(start.elapsed()) {
+                    tokio::time::sleep(ahead).await;
+                }
+            }
+        }
+        let mut transferred = self.1
+                                  .transferred
+                                  .lock()
+                                  .expect("Transfer stats lock poisoned");
+        transferred.bytes += body.len() as u64;
+        transferred.elapsed += start.elapsed();
+        Ok(body)
+    }
+
     async fn upload_body(src_path: &Path,
                          progress: Option<Box<dyn DisplayProgress>>)
                          -> Result<Body> {
@@ -1116,6 +1207,45 @@ impl BuilderAPIClient {
         Ok(package)
     }
 
+    /// Returns every release of a package in a channel. Only the origin
+    /// and name of `package` are used.
+    ///
+    /// # Failures
+    ///
+    /// * Remote Builder is not available
+    pub async fn list_channel_packages(&self,
+                                       (package, target): (&PackageIdent, PackageTarget),
+                                       channel: &ChannelIdent,
+                                       token: Option<&str>)
+                                       -> Result<Vec<PackageIdent>> {
+        debug!("Listing releases of {} in {}, target {}",
+               package, channel, target);
+
+        let path = channel_packages_path(channel, package);
+        let mut packages = Vec::new();
+        loop {
+            let range = packages.len();
+            let req = self.0.get_with_custom_url(&path, |url| {
+                                url.set_query(Some(&format!("range={}&target={}", range, target)))
+                            });
+            let resp = self.maybe_add_authz(req, token).send().await?;
+            let status = resp.status();
+            debug!("Response Status: {:?}", status);
+            if status != StatusCode::OK && status != StatusCode::PARTIAL_CONTENT {
+                return Err(response::err_from_response(resp).await);
+            }
+
+            let encoded = resp.text().await.map_err(Error::BadResponseBody)?;
+            trace!(target: "habitat_http_client::api_client::list_channel_packages", "{:?}", encoded);
+            let mut results: PackageResults<PackageIdent> = serde_json::from_str(&encoded)?;
+            let more_to_come = status == StatusCode::PARTIAL_CONTENT && !results.data.is_empty();
+            packages.append(&mut results.data);
+            if !more_to_come {
+                return Ok(packages);
+            }
+        }
+    }
+
     /// Upload a package to a remote Builder.
     ///
     /// # Failures
@@ -1512,6 +1642,13 @@ fn channel_package_path(channel: &ChannelIdent, package: &PackageIdent) -> Strin
     path
 }
 
+fn channel_packages_path(channel: &ChannelIdent, package: &PackageIdent) -> String {
+    format!("depot/channels/{}/{}/pkgs/{}",
+            package.origin(),
+            channel,
+            package.name())
+}
+
 fn package_channels_path(package: &PackageIdent) -> String {
     format!("depot/pkgs/{}/{}/{}/{}/channels",
             package.origin(),
@@ -1574,6 +1711,14 @@ mod tests {
         assert_eq!(pre.owner_id, post.owner_id);
     }
 
+    #[test]
+    fn transfer_stats_report_throughput() {
+        let stats = TransferStats { bytes:   10_000,
+                                    elapsed: Duration::from_secs(4), };
+        assert_eq!(stats.bytes_per_sec(), 2_500);
+        assert_eq!(TransferStats::default().bytes_per_sec(), 0);
+    }
+
     fn get_test_ident(name: &str) -> PackageIdent {
         PackageIdent { origin:  String::from("test"),
                        name:    String::from(name),
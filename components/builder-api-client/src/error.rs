@@ -7,7 +7,8 @@ use std::{error,
           io,
           num,
           path::PathBuf,
-          result};
+          result,
+          time::Duration};
 use tokio::task::JoinError;
 
 pub type Result<T> = result::Result<T, Error>;
@@ -61,6 +62,9 @@ pub enum Error {
     APIError(reqwest::StatusCode, String),
     APIClientError(APIFailure),
     BadResponseBody(reqwest::Error),
+    /// The depot is down for maintenance, optionally saying how long
+    /// clients should wait before trying again.
+    DepotMaintenance(Option<Duration>),
     DownloadWrite(PathBuf, io::Error),
     HabitatCore(hab_core::Error),
     HabitatHttpClient(hab_http::Error),
@@ -73,6 +77,7 @@ pub enum Error {
     NoFilePart,
     PackageReadError(PathBuf, io::Error),
     ParseIntError(num::ParseIntError),
+    ProxyAuthFailed(String),
     IdentNotFullyQualified,
     UploadFailed(String),
     UrlParseError(url::ParseError),
@@ -88,6 +93,10 @@ impl fmt::Display for Error {
             Error::APIError(ref c, _) => format!("[{}]", c),
             Error::APIClientError(ref e) => format!("{}", e),
             Error::BadResponseBody(ref e) => format!("Failed to read response body, {}", e),
+            Error::DepotMaintenance(Some(ref d)) => {
+                format!("Depot in maintenance, retry in {}s", d.as_secs())
+            }
+            Error::DepotMaintenance(None) => "Depot in maintenance".to_string(),
             Error::DownloadWrite(ref p, ref e) => {
                 format!("Failed to write contents of builder response, {}, {}",
                         p.display(),
@@ -110,6 +119,7 @@ impl fmt::Display for Error {
                 format!("Failed to read package artifact, {}, {}", p.display(), e)
             }
             Error::ParseIntError(ref err) => format!("{}", err),
+            Error::ProxyAuthFailed(ref e) => format!("Proxy authentication failed: {}", e),
             Error::IdentNotFullyQualified => {
                 "Cannot perform the specified operation. Specify a fully qualifed package \
                  identifier (ex: core/busybox-static/1.42.2/20170513215502)"
@@ -129,6 +139,41 @@ impl fmt::Display for Error {
 
 impl error::Error for Error {}
 
+impl Error {
+    /// Whether this error, or the error behind a failed retry, is a
+    /// proxy refusing our credentials rather than a general failure.
+    pub fn is_proxy_auth_failed(&self) -> bool {
+        match self.last_attempt() {
+            Error::ProxyAuthFailed(_) => true,
+            Error::HabitatHttpClient(hab_http::Error::ReqwestError(e)) => {
+                hab_http::is_proxy_auth_error(e)
+            }
+            _ => false,
+        }
+    }
+
+    /// If this error, or the error behind a failed retry, is the depot
+    /// reporting that it is down for maintenance, the window it asked
+    /// clients to wait, if it gave one.
+    pub fn maintenance_window(&self) -> Option<Option<Duration>> {
+        match self.last_attempt() {
+            Error::DepotMaintenance(window) => Some(*window),
+            _ => None,
+        }
+    }
+
+    /// The error of the final attempt, when retries were exhausted.
+    fn last_attempt(&self) -> &Error {
+        match self {
+            Error::APIClientError(APIFailure::DownloadPackageFailed(_, _, _, e))
+            | Error::APIClientError(APIFailure::DownloadLatestKeyFailed(_, _, e))
+            | Error::APIClientError(APIFailure::DownloadKeyFailed(_, _, e))
+            | Error::APIClientError(APIFailure::UploadKeyFailed(_, _, e)) => e.last_attempt(),
+            e => e,
+        }
+    }
+}
+
 impl From<hab_core::Error> for Error {
     fn from(err: hab_core::Error) -> Error { Error::HabitatCore(err) }
 }
@@ -138,7 +183,13 @@ impl From<hab_http::Error> for Error {
 }
 
 impl From<reqwest::Error> for Error {
-    fn from(err: reqwest::Error) -> Error { Error::ReqwestError(err) }
+    fn from(err: reqwest::Error) -> Error {
+        if hab_http::is_proxy_auth_error(&err) {
+            Error::ProxyAuthFailed(err.to_string())
+        } else {
+            Error::ReqwestError(err)
+        }
+    }
 }
 
 impl From<io::Error> for Error {
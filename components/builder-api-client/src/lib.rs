@@ -23,8 +23,10 @@ use reqwest::IntoUrl;
 use serde::{Deserialize,
             Serialize};
 
-use crate::hab_core::package::PackageIdent;
+use crate::hab_core::package::{PackageIdent,
+                               PackageTarget};
 pub use crate::{builder::{BuilderAPIClient,
+                          TransferStats,
                           API_RETRY_COUNT,
                           API_RETRY_DELAY},
                 error::{APIFailure,
@@ -143,13 +145,27 @@ mod json {
 
 #[derive(Clone, Deserialize)]
 pub struct Package {
-    pub ident:    PackageIdent,
-    pub checksum: String,
-    pub manifest: String,
-    pub deps:     Vec<PackageIdent>,
-    pub tdeps:    Vec<PackageIdent>,
-    pub exposes:  Vec<u32>,
-    pub config:   String,
+    pub ident:         PackageIdent,
+    pub checksum:      String,
+    pub manifest:      String,
+    pub deps:          Vec<PackageIdent>,
+    pub tdeps:         Vec<PackageIdent>,
+    pub exposes:       Vec<u32>,
+    pub config:        String,
+    /// When a channel embargoes a newly published package, the RFC 3339
+    /// time before which it shouldn't be consumed. Depots that don't
+    /// embargo packages leave this out.
+    #[serde(default)]
+    pub embargo_until: Option<String>,
+    /// Whether the package has been yanked from the channel, so that it
+    /// should no longer be consumed. Depots that don't yank packages
+    /// leave this out.
+    #[serde(default)]
+    pub yanked:        bool,
+    /// The target the package was built for. Depots that don't report
+    /// it leave this out.
+    #[serde(default)]
+    pub target:        Option<PackageTarget>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -1,11 +1,18 @@
 use crate::error::{Error,
                    Result};
 use log::debug;
-use reqwest::{header::AsHeaderName,
+use reqwest::{header::{AsHeaderName,
+                       HeaderMap,
+                       RETRY_AFTER},
               Response,
               StatusCode};
 use serde::Deserialize;
-use std::fmt;
+use std::{fmt,
+          time::Duration};
+
+/// The header the depot sets on its responses while it is down for
+/// maintenance.
+const MAINTENANCE_HEADER: &str = "x-maintenance";
 
 #[derive(Clone, Deserialize)]
 #[serde(rename = "error")]
@@ -57,14 +64,48 @@ pub async fn err_from_response(response: Response) -> Error {
                                 Token."
                                        .to_string());
     }
+    if status == StatusCode::PROXY_AUTHENTICATION_REQUIRED {
+        return Error::ProxyAuthFailed(String::from("the proxy rejected the request; check the \
+                                                    proxy credentials"));
+    }
+
+    let headers = response.headers().clone();
+    let buff = response.text().await.unwrap_or_default();
+    // Any other 503 is an outage or overload, and an ordinary error
+    if status == StatusCode::SERVICE_UNAVAILABLE && is_maintenance(&headers, &buff) {
+        // Only the delay-seconds form of Retry-After is honored.
+        let retry_after = headers.get(RETRY_AFTER)
+                                 .and_then(|value| value.to_str().ok())
+                                 .and_then(|value| value.trim().parse().ok())
+                                 .map(Duration::from_secs);
+        return Error::DepotMaintenance(retry_after);
+    }
+    match serde_json::from_str::<NetError>(&buff) {
+        Ok(err) => Error::APIError(status, err.to_string()),
+        Err(_) => Error::APIError(status, buff),
+    }
+}
+
+/// Whether an unavailable depot says it is down for maintenance, in its
+/// headers or the body of its response.
+fn is_maintenance(headers: &HeaderMap, body: &str) -> bool {
+    headers.contains_key(MAINTENANCE_HEADER) || body.to_lowercase().contains("maintenance")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn only_a_depot_saying_so_is_in_maintenance() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_maintenance(&headers, ""));
+        assert!(!is_maintenance(&headers, "upstream connect error"));
+        assert!(is_maintenance(&headers,
+                               r#"{"code": 503, "msg": "Builder is down for Maintenance"}"#));
 
-    match response.text().await {
-        Ok(buff) => {
-            match serde_json::from_str::<NetError>(&buff) {
-                Ok(err) => Error::APIError(status, err.to_string()),
-                Err(_) => Error::APIError(status, buff),
-            }
-        }
-        Err(_) => Error::APIError(status, String::new()),
+        headers.insert(MAINTENANCE_HEADER, HeaderValue::from_static("true"));
+        assert!(is_maintenance(&headers, ""));
     }
 }
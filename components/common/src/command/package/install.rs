@@ -307,12 +307,40 @@ pub async fn start<U>(ui: &mut U,
                       install_hook_mode: InstallHookMode)
                       -> Result<PackageInstall>
     where U: UIWriter
+{
+    let api_client = Client::new(url, product, version, Some(fs_root_path))?;
+    start_with_client(ui,
+                      &api_client,
+                      channel,
+                      install_source,
+                      fs_root_path,
+                      artifact_cache_path,
+                      token,
+                      install_mode,
+                      local_package_usage,
+                      install_hook_mode).await
+}
+
+/// `start`, but retrieving packages through an already configured
+/// `api_client`.
+#[allow(clippy::too_many_arguments)]
+pub async fn start_with_client<U>(ui: &mut U,
+                                  api_client: &BuilderAPIClient,
+                                  channel: &ChannelIdent,
+                                  install_source: &InstallSource,
+                                  fs_root_path: &Path,
+                                  artifact_cache_path: &Path,
+                                  token: Option<&str>,
+                                  install_mode: &InstallMode,
+                                  local_package_usage: &LocalPackageUsage,
+                                  install_hook_mode: InstallHookMode)
+                                  -> Result<PackageInstall>
+    where U: UIWriter
 {
     let key_cache = KeyCache::new(cache_key_path(fs_root_path));
     key_cache.setup()?;
     debug!("install key cache: {}", key_cache.as_ref().display());
 
-    let api_client = Client::new(url, product, version, Some(fs_root_path))?;
     let task = InstallTask { install_mode,
                              local_package_usage,
                              api_client,
@@ -417,7 +445,7 @@ fn read_install_hook_status(path: PathBuf) -> Result<Option<i32>> {
 struct InstallTask<'a> {
     install_mode:        &'a InstallMode,
     local_package_usage: &'a LocalPackageUsage,
-    api_client:          BuilderAPIClient,
+    api_client:          &'a BuilderAPIClient,
     channel:             &'a ChannelIdent,
     fs_root_path:        &'a Path,
     /// The path to the local artifact cache (e.g., /hab/cache/artifacts)
@@ -76,6 +76,20 @@ pub enum HabSup {
         #[structopt(flatten)]
         remote_sup: RemoteSup,
     },
+    /// Check for Supervisor updates more often for a while, then go back to the configured
+    /// --auto-update-period
+    #[structopt(no_version)]
+    UpdatePeriod {
+        /// The period of time in seconds between Supervisor update checks, at most the
+        /// configured one
+        #[structopt(name = "PERIOD")]
+        period:     DurationProxy,
+        /// How long in seconds to use PERIOD for, at most an hour
+        #[structopt(name = "DURATION")]
+        duration:   DurationProxy,
+        #[structopt(flatten)]
+        remote_sup: RemoteSup,
+    },
     #[cfg(not(target_os = "macos"))]
     #[structopt(flatten)]
     Sup(Sup),
@@ -220,6 +234,10 @@ pub struct SupRun {
     /// The period of time in seconds between Supervisor update checks
     #[structopt(long = "auto-update-period", default_value = "60")]
     pub auto_update_period: DurationProxy,
+    /// The most bytes per second to download Supervisor updates at. If this argument is not
+    /// specified, or is 0, downloads are unlimited.
+    #[structopt(long = "auto-update-bandwidth-limit")]
+    pub auto_update_bandwidth_limit: Option<u64>,
     /// The period of time in seconds between service update checks
     #[structopt(long = "service-update-period", default_value = "60")]
     pub service_update_period: DurationProxy,
@@ -260,6 +260,13 @@ async fn start(ui: &mut UI, feature_flags: FeatureFlag) -> Result<()> {
                         HabSup::Restart { remote_sup } => {
                             return sub_sup_restart(remote_sup.inner()).await;
                         }
+                        HabSup::UpdatePeriod { period,
+                                               duration,
+                                               remote_sup, } => {
+                            return sub_sup_update_period(period.into(),
+                                                         duration.into(),
+                                                         remote_sup.inner()).await;
+                        }
                     }
                 }
                 Hab::Svc(svc) => {
@@ -1616,6 +1623,15 @@ async fn sub_sup_restart(remote_sup: Option<&ResolvedListenCtlAddr>) -> Result<(
     Ok(())
 }
 
+async fn sub_sup_update_period(period: u64,
+                               duration: u64,
+                               remote_sup: Option<&ResolvedListenCtlAddr>)
+                               -> Result<()> {
+    let msg = sup_proto::ctl::SupUpdatePeriod { period_secs:   Some(period),
+                                                duration_secs: Some(duration), };
+    gateway_util::send(remote_sup, msg).await
+}
+
 #[cfg(not(target_os = "macos"))]
 fn sub_sup_secret_generate() -> Result<()> {
     let mut ui = ui::ui();
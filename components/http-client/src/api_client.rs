@@ -13,8 +13,10 @@ use reqwest::{header::{HeaderValue,
               IntoUrl,
               Proxy,
               RequestBuilder,
+              StatusCode,
               Url};
-use std::{path::Path,
+use std::{fmt,
+          path::Path,
           time::Duration};
 
 // Read and write TCP socket timeout for Hyper/HTTP client calls.
@@ -51,6 +53,29 @@ impl ApiClient {
                   fs_root_path: Option<&Path>)
                   -> Result<Self>
         where T: IntoUrl
+    {
+        let proxy_auth = ProxyAuth::from_env()?;
+        Self::new_with_proxy_auth(endpoint,
+                                  product,
+                                  version,
+                                  fs_root_path,
+                                  proxy_auth.as_ref())
+    }
+
+    /// Creates and returns a new `ApiClient` instance that
+    /// authenticates with any HTTP/HTTPS proxy using `proxy_auth`,
+    /// rather than the authentication configured in the environment.
+    ///
+    /// # Errors
+    ///
+    /// * As for `new`
+    pub fn new_with_proxy_auth<T>(endpoint: T,
+                                  product: &str,
+                                  version: &str,
+                                  fs_root_path: Option<&Path>,
+                                  proxy_auth: Option<&ProxyAuth>)
+                                  -> Result<Self>
+        where T: IntoUrl
     {
         let endpoint = endpoint.into_url().map_err(Error::ReqwestError)?;
 
@@ -84,7 +109,7 @@ impl ApiClient {
             ),
         ].into_iter().collect();
 
-        let mut client = ReqwestClient::builder().proxy(proxy_for(&endpoint)?)
+        let mut client = ReqwestClient::builder().proxy(proxy_for(&endpoint, proxy_auth)?)
                                                  .default_headers(headers)
                                                  .timeout(Duration::from_secs(timeout_in_secs))
                                                  .danger_accept_invalid_certs(skip_cert_verify);
@@ -198,20 +223,117 @@ impl ApiClient {
     }
 }
 
-fn proxy_for(url: &Url) -> reqwest::Result<Proxy> {
+/// Credentials presented to an HTTP proxy that requires
+/// authentication, selected with `HAB_PROXY_AUTH_SCHEME`.
+///
+/// Only the Basic scheme is supported. NTLM and Negotiate (Kerberos)
+/// are challenge/response exchanges that have to happen on a single
+/// proxy connection, which the underlying client cannot hold, so they
+/// are rejected rather than attempted. Point the proxy environment
+/// variables at a local authenticating proxy (such as cntlm) instead.
+#[derive(Clone)]
+pub enum ProxyAuth {
+    /// A username and password, sent to the proxy with the Basic
+    /// scheme.
+    Basic { username: String, password: String },
+}
+
+impl ProxyAuth {
+    pub const PASSWORD_ENVVAR: &'static str = "HAB_PROXY_PASSWORD";
+    pub const SCHEME_ENVVAR: &'static str = "HAB_PROXY_AUTH_SCHEME";
+    pub const USERNAME_ENVVAR: &'static str = "HAB_PROXY_USERNAME";
+
+    /// Read the proxy authentication configured in the environment,
+    /// if any. The credentials are read from `HAB_PROXY_USERNAME` and
+    /// `HAB_PROXY_PASSWORD`.
+    ///
+    /// # Errors
+    ///
+    /// * As for `for_scheme`
+    pub fn from_env() -> Result<Option<Self>> {
+        match env::var(Self::SCHEME_ENVVAR) {
+            Ok(scheme) => {
+                Self::for_scheme(&scheme,
+                                 env::var(Self::USERNAME_ENVVAR).ok(),
+                                 env::var(Self::PASSWORD_ENVVAR).ok())
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// The proxy authentication for `scheme` with the given
+    /// credentials, or `None` for the scheme `none`.
+    ///
+    /// # Errors
+    ///
+    /// * If the scheme is unknown or unsupported
+    /// * If the credentials the scheme needs are not given
+    pub fn for_scheme(scheme: &str,
+                      username: Option<String>,
+                      password: Option<String>)
+                      -> Result<Option<Self>> {
+        let missing = |credential| {
+            Error::ProxyAuthConfig(format!("a {} is needed for basic proxy authentication",
+                                           credential))
+        };
+        match scheme.trim().to_lowercase().as_str() {
+            "" | "none" => Ok(None),
+            "basic" => {
+                let username = username.ok_or_else(|| missing("username"))?;
+                let password = password.ok_or_else(|| missing("password"))?;
+                Ok(Some(ProxyAuth::Basic { username, password }))
+            }
+            unsupported @ ("ntlm" | "negotiate" | "kerberos") => {
+                let msg = format!("the {} scheme is not supported; use a local authenticating \
+                                   proxy instead",
+                                  unsupported);
+                Err(Error::ProxyAuthConfig(msg))
+            }
+            other => Err(Error::ProxyAuthConfig(format!("unknown scheme '{}'", other))),
+        }
+    }
+
+    fn scheme(&self) -> &'static str {
+        match self {
+            ProxyAuth::Basic { .. } => "basic",
+        }
+    }
+
+    fn apply(&self, proxy: Proxy) -> Proxy {
+        match self {
+            ProxyAuth::Basic { username, password } => proxy.basic_auth(username, password),
+        }
+    }
+}
+
+// Credentials must never end up in logs.
+impl fmt::Debug for ProxyAuth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ProxyAuth({}, <redacted>)", self.scheme())
+    }
+}
+
+fn proxy_for(url: &Url, auth: Option<&ProxyAuth>) -> Result<Proxy> {
     trace!("Checking proxy for url: {:?}", url);
 
     if let Some(proxy_url) = env_proxy::for_url(url).to_string() {
-        match url.scheme() {
+        let proxy = match url.scheme() {
             "http" => {
-                debug!("Setting http_proxy to {}", proxy_url);
-                Proxy::http(&proxy_url)
+                debug!("Setting http_proxy to {}", redacted(&proxy_url));
+                Proxy::http(&proxy_url)?
             }
             "https" => {
-                debug!("Setting https proxy to {}", proxy_url);
-                Proxy::https(&proxy_url)
+                debug!("Setting https proxy to {}", redacted(&proxy_url));
+                Proxy::https(&proxy_url)?
             }
             _ => unimplemented!(),
+        };
+        match auth {
+            Some(auth) => {
+                debug!("Using {} proxy authentication", auth.scheme());
+                Ok(auth.apply(proxy))
+            }
+            None => Ok(proxy),
         }
     } else {
         debug!("No proxy configured for url: {:?}", url);
@@ -219,6 +341,25 @@ fn proxy_for(url: &Url) -> reqwest::Result<Proxy> {
     }
 }
 
+/// Strip any credentials embedded in a proxy URL so it can be logged.
+fn redacted(proxy_url: &str) -> String {
+    match Url::parse(proxy_url) {
+        Ok(mut url) => {
+            if url.password().is_some() {
+                let _ = url.set_password(Some("<redacted>"));
+            }
+            url.to_string()
+        }
+        Err(_) => "<unparseable proxy url>".to_string(),
+    }
+}
+
+/// Whether `err` is a proxy refusing our credentials (or asking for
+/// some) rather than a general connection failure.
+pub fn is_proxy_auth_error(err: &reqwest::Error) -> bool {
+    err.status() == Some(StatusCode::PROXY_AUTHENTICATION_REQUIRED)
+}
+
 /// Returns an HTTP User-Agent string type for use by Reqwest when making HTTP requests.
 ///
 /// The general form for Habitat-related clients are of the following form:
@@ -253,3 +394,31 @@ fn user_agent(product: &str, version: &str) -> Result<HeaderValue> {
     debug!("User-Agent: {}", &ua);
     Ok(HeaderValue::from_str(&ua).expect("Valid User Agent header"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_basic_proxy_authentication_is_supported() {
+        let credential = |value: &str| Some(value.to_string());
+        assert!(ProxyAuth::for_scheme("none", None, None).unwrap().is_none());
+        assert_eq!(ProxyAuth::for_scheme(" Basic ", credential("user"), credential("secret"))
+                   .unwrap()
+                   .map(|auth| auth.scheme()),
+                   Some("basic"));
+        assert!(ProxyAuth::for_scheme("basic", credential("user"), None).is_err());
+        for scheme in &["ntlm", "negotiate", "kerberos", "digest"] {
+            assert!(ProxyAuth::for_scheme(scheme, credential("user"), credential("secret")).is_err(),
+                    "the {} scheme should be rejected",
+                    scheme);
+        }
+    }
+
+    #[test]
+    fn credentials_are_never_shown() {
+        let auth = ProxyAuth::Basic { username: String::from("user"),
+                                      password: String::from("secret"), };
+        assert_eq!(format!("{:?}", auth), "ProxyAuth(basic, <redacted>)");
+    }
+}
@@ -12,6 +12,7 @@ pub enum Error {
     ReqwestError(reqwest::Error),
     IO(io::Error),
     Json(serde_json::Error),
+    ProxyAuthConfig(String),
     UrlParseError(url::ParseError),
 }
 
@@ -22,6 +23,7 @@ impl fmt::Display for Error {
             Error::ReqwestError(ref err) => format!("{}", err),
             Error::IO(ref e) => format!("{}", e),
             Error::Json(ref e) => format!("{}", e),
+            Error::ProxyAuthConfig(ref e) => format!("Invalid proxy authentication: {}", e),
             Error::UrlParseError(ref e) => format!("{}", e),
         };
         write!(f, "{}", msg)
@@ -1,6 +1,8 @@
 mod api_client;
 mod error;
 
-pub use crate::{api_client::ApiClient,
+pub use crate::{api_client::{is_proxy_auth_error,
+                             ApiClient,
+                             ProxyAuth},
                 error::{Error,
                         Result}};
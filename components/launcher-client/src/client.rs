@@ -1,13 +1,17 @@
-use crate::error::{ConnectError,
-                   IPCCommandError,
-                   IPCReadError,
-                   ReceiveError,
-                   SendError,
-                   TryIPCCommandError,
-                   TryReceiveError};
+use crate::{error::{ConnectError,
+                    IPCCommandError,
+                    IPCReadError,
+                    ReadinessError,
+                    ReceiveError,
+                    SendError,
+                    SpawnError,
+                    TryIPCCommandError,
+                    TryReceiveError},
+            trace as ipc_trace};
 use habitat_common::types::UserInfo;
 use habitat_core::os::process::Pid;
-use habitat_launcher_protocol as protocol;
+use habitat_launcher_protocol::{self as protocol,
+                                LauncherMessage};
 use ipc_channel::ipc::{IpcError,
                        IpcOneShotServer,
                        IpcReceiver,
@@ -17,14 +21,45 @@ use log::{debug,
           error,
           trace,
           warn};
-use std::{collections::BTreeMap,
-          path::Path,
+use serde::{Serialize,
+            Serializer};
+use std::{collections::{BTreeMap,
+                        BTreeSet,
+                        VecDeque},
+          convert::TryFrom,
+          path::{Path,
+                 PathBuf},
+          sync::{atomic::{AtomicU32,
+                          Ordering},
+                 Mutex,
+                 MutexGuard},
           thread,
           time::{Duration,
                  Instant}};
+use tokio::sync::{mpsc,
+                  watch};
 
 type Env = BTreeMap<String, String>;
 type IpcServer = IpcOneShotServer<Vec<u8>>;
+/// Where exit notifications read off the connection are forwarded,
+/// once they have been subscribed to.
+type ExitSubscriber = Mutex<Option<mpsc::UnboundedSender<protocol::ProcessExited>>>;
+
+/// The exit notifications subscribed to with `LauncherCli::subscribe_exits`.
+pub type ExitNotifications = mpsc::UnboundedReceiver<protocol::ProcessExited>;
+
+/// How often `wait_until_ready` asks the Launcher whether a process is
+/// ready.
+const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How many of the most recent `ping` round trips `average_latency` is
+/// taken over.
+const LATENCY_SAMPLES: usize = 20;
+
+/// How many commands in a row may go unanswered within the interaction
+/// timeout before the connection is considered degraded. A single slow
+/// reply says little about the Launcher.
+const DEGRADED_AFTER_TIMEOUTS: u32 = 3;
 
 // Defines how long to wait to receive a reply from the Launcher.
 //
@@ -44,11 +79,268 @@ pub enum LauncherStatus {
     Unknown,
 }
 
+/// The Supervisor's view of its connection to the Launcher, updated
+/// as commands are exchanged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ConnectionState {
+    /// Commands are being sent and answered normally.
+    Connected,
+    /// The last exchange with the Launcher failed unexpectedly. Further
+    /// commands may still succeed.
+    Degraded,
+    /// The Launcher has announced that it is shutting down. It will not
+    /// process any further commands, so none are sent.
+    ShuttingDown,
+    /// The IPC channel to the Launcher has closed.
+    Disconnected,
+}
+
+impl ConnectionState {
+    /// Whether a connection in this state may move to `next`. A
+    /// Launcher that is shutting down never recovers on the same
+    /// connection, and a closed channel is final.
+    fn can_become(self, next: ConnectionState) -> bool {
+        match self {
+            ConnectionState::Disconnected => false,
+            ConnectionState::ShuttingDown => next == ConnectionState::Disconnected,
+            _ => true,
+        }
+    }
+}
+
+impl From<&IPCReadError> for ConnectionState {
+    fn from(err: &IPCReadError) -> Self {
+        match err {
+            // The Launcher answered; the command itself failed.
+            IPCReadError::LauncherCommand(_) => ConnectionState::Connected,
+            IPCReadError::LauncherShuttingDown => ConnectionState::ShuttingDown,
+            IPCReadError::ProtocolDeserialize(_)
+            | IPCReadError::PayloadDeserialize(_)
+            | IPCReadError::UnexpectedReply { .. } => ConnectionState::Degraded,
+        }
+    }
+}
+
+impl From<&IpcError> for ConnectionState {
+    fn from(err: &IpcError) -> Self {
+        match err {
+            IpcError::Disconnected => ConnectionState::Disconnected,
+            _ => ConnectionState::Degraded,
+        }
+    }
+}
+
+/// The optional features in use on a connection to the Launcher: those
+/// that both this client and the Launcher support.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    /// `SpawnOptions::cpu_affinity` is applied to spawned processes.
+    pub cpu_affinity:     bool,
+    /// `SpawnOptions::args` and `SpawnOptions::cwd` are applied to
+    /// spawned processes.
+    pub spawn_args:       bool,
+    /// `LauncherCli::restart_with` is available.
+    pub restart_with:     bool,
+    /// Messages are length-prefixed.
+    pub framing:          bool,
+    /// `LauncherCli::diagnostics` is available.
+    pub diagnostics:      bool,
+    /// `SpawnOptions::readiness` is evaluated and
+    /// `LauncherCli::readiness` is available.
+    pub readiness_probe:  bool,
+    /// Spawns are deduplicated by `SpawnOptions::key`.
+    pub spawn_key:        bool,
+    /// `LauncherCli::restart_history` is available.
+    pub restart_history:  bool,
+    /// `SpawnOptions::cgroup` is applied to spawned processes.
+    pub cgroup:           bool,
+    /// `LauncherCli::preload` is available.
+    pub preload:          bool,
+    /// `LauncherCli::validate_pid` is available.
+    pub validate_pid:     bool,
+    /// `LauncherCli::health_summary` is available.
+    pub health_summary:   bool,
+    /// `SpawnOptions::scheduling` is applied to spawned processes, and
+    /// `LauncherCli::scheduling` and `LauncherCli::set_scheduling` are
+    /// available.
+    pub scheduling:       bool,
+    /// `SpawnOptions::early_exit_window` is honored.
+    pub early_exit:       bool,
+    /// `LauncherCli::ping` is available.
+    pub ping:             bool,
+    /// `LauncherCli::subscribe_exits` is available.
+    pub subscribe_exits:  bool,
+    /// `LauncherCli::resource_usage` is available.
+    pub resource_usage:   bool,
+    /// `SpawnOptions::privileges` is applied to spawned processes.
+    pub privileges:       bool,
+    /// `LauncherCli::list_services` is available.
+    pub list_services:    bool,
+    /// `LauncherCli::fd_counts` is available.
+    pub fd_counts:        bool,
+    /// `LauncherCli::uptimes` is available.
+    pub uptimes:          bool,
+    /// `LauncherCli::config` is available.
+    pub config:           bool,
+    /// The largest message, in bytes, the Launcher accepts.
+    pub max_message_size: usize,
+}
+
+/// What the Supervisor knows about its Launcher, gathered in one call
+/// for status reporting.
+#[derive(Clone, Debug, Serialize)]
+pub struct LauncherSummary {
+    pub state:            ConnectionState,
+    pub capabilities:     Capabilities,
+    pub protocol_version: u32,
+    /// See `LauncherCli::average_latency`. Serialized in microseconds,
+    /// as `average_latency_us`.
+    #[serde(rename = "average_latency_us", serialize_with = "as_micros")]
+    pub average_latency:  Option<Duration>,
+}
+
+fn as_micros<S: Serializer>(latency: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+    latency.map(|latency| latency.as_micros() as u64)
+           .serialize(serializer)
+}
+
+/// The Launcher's own use of the host's resources, apart from the
+/// processes it supervises. Each figure is `None` where the Launcher's
+/// platform doesn't expose it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct LauncherResourceUsage {
+    /// CPU time spent in user and kernel mode since the Launcher
+    /// started, in milliseconds.
+    pub cpu_user_ms:    Option<u64>,
+    pub cpu_system_ms:  Option<u64>,
+    /// Resident memory now, and at its peak, in bytes.
+    pub rss_bytes:      Option<u64>,
+    pub peak_rss_bytes: Option<u64>,
+    pub open_fds:       Option<u32>,
+}
+
+impl From<protocol::ResourceUsageReport> for LauncherResourceUsage {
+    fn from(report: protocol::ResourceUsageReport) -> Self {
+        LauncherResourceUsage { cpu_user_ms:    report.cpu_user_ms,
+                                cpu_system_ms:  report.cpu_system_ms,
+                                rss_bytes:      report.rss_bytes,
+                                peak_rss_bytes: report.peak_rss_bytes,
+                                open_fds:       report.open_fds, }
+    }
+}
+
+/// Optional settings for a spawned process. Each requires the Launcher
+/// to report the corresponding capability.
+#[derive(Clone, Debug, Default)]
+pub struct SpawnOptions {
+    /// The CPUs the process may run on. Empty means no restriction.
+    pub cpu_affinity:      Vec<u32>,
+    /// Arguments to pass to the binary.
+    pub args:              Vec<String>,
+    /// The directory to run the binary in, rather than the Launcher's.
+    pub cwd:               Option<PathBuf>,
+    /// How the Launcher decides the process has finished starting.
+    pub readiness:         Option<protocol::ReadinessProbe>,
+    /// Identifies the request, so that repeating it (e.g. after a lost
+    /// reply) returns the process the first one started.
+    pub key:               Option<String>,
+    /// Resource limits to run the process under, in a cgroup of its
+    /// own.
+    pub cgroup:            Option<protocol::CgroupLimits>,
+    /// How the process is scheduled. Real-time scheduling and negative
+    /// nice values are only allowed for services the Launcher trusts.
+    pub scheduling:        Option<protocol::Scheduling>,
+    /// How long the Launcher watches the process after spawning it. If
+    /// it exits in that time, the spawn fails with
+    /// `SpawnError::ExitedEarly`, giving what the process wrote to
+    /// standard error. The Launcher holds the window to 250ms.
+    pub early_exit_window: Option<Duration>,
+    /// Supplementary groups and Linux capabilities for the process. The
+    /// spawn fails with `SpawnError::InvalidPrivileges` if the Launcher
+    /// can't apply them.
+    pub privileges:        Option<protocol::Privileges>,
+}
+
+/// Everything needed to spawn a service process. See `spawn` for the
+/// meaning of each field.
+#[derive(Debug)]
+pub struct SpawnSpec {
+    pub id:        String,
+    pub bin:       PathBuf,
+    pub user_info: UserInfo,
+    pub password:  Option<String>,
+    pub env:       Env,
+    pub options:   SpawnOptions,
+}
+
+/// The outcome of `spawn_spec`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpawnOutcome {
+    /// A new process started with this PID.
+    Spawned(Pid),
+    /// A process with this PID was already running for the spawn's
+    /// key, so no new one was started.
+    DuplicateSpawn(Pid),
+}
+
+impl SpawnOutcome {
+    pub fn pid(self) -> Pid {
+        match self {
+            SpawnOutcome::Spawned(pid) | SpawnOutcome::DuplicateSpawn(pid) => pid,
+        }
+    }
+}
+
+/// The process `spawn_spec` spawned, or found already running.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Spawned {
+    pub outcome: SpawnOutcome,
+    /// The cgroup the Launcher runs the process in, when
+    /// `SpawnOptions::cgroup` was given, so that its resource use can
+    /// be monitored.
+    pub cgroup:  Option<PathBuf>,
+}
+
+/// The outcome of `restart_with` once the old process has stopped.
+#[derive(Debug)]
+pub enum RestartOutcome {
+    /// The new process started with this PID.
+    Restarted(Pid),
+    /// The new process failed to start, so nothing is running for the
+    /// service any more.
+    StartFailed(SpawnError),
+}
+
+/// The two ends of the IPC connection to the Launcher.
+struct Connection {
+    tx: IpcSender<Vec<u8>>,
+    rx: IpcReceiver<Vec<u8>>,
+}
+
 pub struct LauncherCli {
-    tx:      IpcSender<Vec<u8>>,
-    rx:      IpcReceiver<Vec<u8>>,
+    /// Held for the whole of an exchange, so that commands sent from
+    /// different threads never read each other's replies.
+    connection:       Mutex<Connection>,
     /// Maximum wait time for interactions that can timeout.
-    timeout: Duration,
+    timeout:          Duration,
+    state:            watch::Sender<ConnectionState>,
+    /// Optional features the Launcher reported at connection time.
+    capabilities:     BTreeSet<String>,
+    /// How messages are laid out on the connection, as negotiated at
+    /// connection time.
+    framing:          protocol::Framing,
+    /// The largest message the Launcher accepts, as reported at
+    /// connection time.
+    max_message_size: usize,
+    /// The version of the protocol the Launcher speaks, as reported at
+    /// connection time.
+    protocol_version: u32,
+    /// The round trips of the most recent pings, oldest first.
+    latencies:        Mutex<VecDeque<Duration>>,
+    /// Where exit notifications go, once subscribed to.
+    exits:            ExitSubscriber,
+    /// How many commands in a row have timed out.
+    timeouts:         AtomicU32,
 }
 
 impl LauncherCli {
@@ -61,39 +353,317 @@ impl LauncherCli {
         debug!("IpcServer::new() returned pipe_to_sup: {}", pipe_to_sup);
         // Register the supervisor with the launcher by sending a register command
         let cmd = protocol::Register { pipe: pipe_to_sup };
-        Self::send(&tx, &cmd).map_err(ConnectError::LauncherRegisterSend)?;
+        Self::send(&tx,
+                   protocol::Framing::Unframed,
+                   protocol::MAX_FRAME_SIZE,
+                   &cmd).map_err(ConnectError::LauncherRegisterSend)?;
         // Accpet the incoming connection from the launcher and read the response
         let (rx, raw) = ipc_srv.accept()
                                .map_err(ConnectError::IPCIncomingConnection)?;
-        Self::read::<protocol::NetOk>(&raw).map_err(ConnectError::LauncherRegisterReceive)?;
+        Self::read::<protocol::NetOk>(&raw, protocol::Framing::Unframed)
+            .map_err(ConnectError::LauncherRegisterReceive)?;
 
         let timeout = LauncherInteractionTimeout::configured_value().into();
+        let reported = Self::query_capabilities(&tx, &rx, timeout);
+        // Launchers that don't say predate protocol versions, and speak
+        // the first one
+        let protocol_version = reported.protocol_version.unwrap_or(1);
+        if protocol_version != protocol::PROTOCOL_VERSION {
+            return Err(ConnectError::VersionMismatch { launcher: protocol_version,
+                                                       client:   protocol::PROTOCOL_VERSION, });
+        }
+        let capabilities = reported.capabilities.into_iter().collect();
+        // Launchers that don't say are held to the limit of the protocol
+        let max_message_size = reported.max_message_size
+                                       .and_then(|max| usize::try_from(max).ok())
+                                       .unwrap_or(protocol::MAX_FRAME_SIZE);
+        let framing = Self::negotiate_framing(&tx, &rx, &capabilities, timeout);
+
+        Ok(LauncherCli { connection: Mutex::new(Connection { tx, rx }),
+                         timeout,
+                         state: watch::channel(ConnectionState::Connected).0,
+                         capabilities,
+                         framing,
+                         max_message_size,
+                         protocol_version,
+                         latencies: Mutex::default(),
+                         exits: Mutex::default(),
+                         timeouts: AtomicU32::default() })
+    }
+
+    /// Connect to the Launcher again, typically after the previous
+    /// connection was lost. Anything watching the connection state
+    /// keeps its subscription and sees the state become `Connected`.
+    ///
+    /// The Launcher may have been replaced in the meantime, e.g. by a
+    /// newer release, so the handshake is run again in full and what it
+    /// learns replaces what the previous connection knew. A Launcher
+    /// speaking a different protocol version is refused with
+    /// `ConnectError::VersionMismatch`, and the client is left as it
+    /// was.
+    pub fn reconnect(&mut self, pipe_to_launcher: String) -> Result<(), ConnectError> {
+        let LauncherCli { connection,
+                          timeout,
+                          capabilities,
+                          framing,
+                          max_message_size,
+                          protocol_version,
+                          .. } = Self::connect(pipe_to_launcher)?;
+        if capabilities != self.capabilities {
+            debug!("Launcher capabilities changed across reconnect from {:?} to {:?}",
+                   self.capabilities, capabilities);
+        }
+        self.connection = connection;
+        self.timeout = timeout;
+        self.capabilities = capabilities;
+        self.framing = framing;
+        self.max_message_size = max_message_size;
+        self.protocol_version = protocol_version;
+        self.timeouts = AtomicU32::default();
+        // How the previous Launcher responded says nothing about this one
+        self.latencies
+            .lock()
+            .expect("Launcher latency lock poisoned")
+            .clear();
+        // The subscription was to the previous Launcher; dropping it
+        // closes the subscriber's receiver so that it can subscribe again
+        self.exits
+            .lock()
+            .expect("Launcher exit subscriber lock poisoned")
+            .take();
+        // This is a new connection, so the previous one being closed
+        // or shut down doesn't hold it back.
+        let previous = self.state.send_replace(ConnectionState::Connected);
+        debug!("Reconnected to the Launcher, previously {:?}", previous);
+        Ok(())
+    }
 
-        Ok(LauncherCli { tx, rx, timeout })
+    /// Ask the Launcher which optional features it supports. Launchers
+    /// that predate the query either reject it or never answer, and
+    /// are taken to support none.
+    fn query_capabilities(tx: &IpcSender<Vec<u8>>,
+                          rx: &IpcReceiver<Vec<u8>>,
+                          timeout: Duration)
+                          -> protocol::CapabilityList {
+        if let Err(err) = Self::send(tx,
+                                     protocol::Framing::Unframed,
+                                     protocol::MAX_FRAME_SIZE,
+                                     &protocol::Capabilities {})
+        {
+            debug!("Unable to query Launcher capabilities, assuming none: {}",
+                   err);
+            return protocol::CapabilityList::default();
+        }
+        let reply = Self::recv_timeout::<protocol::CapabilityList>(rx,
+                                                                   protocol::Framing::Unframed,
+                                                                   timeout,
+                                                                   None);
+        match reply {
+            Ok(list) => {
+                debug!("Launcher capabilities: {:?}", list.capabilities);
+                list
+            }
+            Err(err) => {
+                debug!("Launcher did not report its capabilities, assuming none: {}",
+                       err);
+                protocol::CapabilityList::default()
+            }
+        }
+    }
+
+    /// Switch the connection to length-prefixed framing if the
+    /// Launcher supports it. Any failure to agree leaves the
+    /// connection unframed, which every Launcher understands.
+    fn negotiate_framing(tx: &IpcSender<Vec<u8>>,
+                         rx: &IpcReceiver<Vec<u8>>,
+                         capabilities: &BTreeSet<String>,
+                         timeout: Duration)
+                         -> protocol::Framing {
+        if !capabilities.contains(protocol::capability::FRAMING) {
+            debug!("Launcher does not support framing, leaving IPC unframed");
+            return protocol::Framing::Unframed;
+        }
+        let msg = protocol::EnableFraming { version: protocol::FRAMING_VERSION, };
+        if let Err(err) = Self::send(tx,
+                                     protocol::Framing::Unframed,
+                                     protocol::MAX_FRAME_SIZE,
+                                     &msg)
+        {
+            debug!("Unable to enable framing, leaving IPC unframed: {}", err);
+            return protocol::Framing::Unframed;
+        }
+        match Self::recv_timeout::<protocol::NetOk>(rx, protocol::Framing::Unframed, timeout, None)
+        {
+            Ok(_) => {
+                debug!("Launcher IPC framing version {} enabled",
+                       protocol::FRAMING_VERSION);
+                protocol::Framing::LengthPrefixed
+            }
+            Err(err) => {
+                debug!("Launcher declined framing, leaving IPC unframed: {}", err);
+                protocol::Framing::Unframed
+            }
+        }
+    }
+
+    /// The largest message, in bytes, the Launcher accepts. Commands
+    /// that would be larger are refused with
+    /// `SendError::MessageTooLarge`.
+    pub fn max_message_size(&self) -> usize { self.max_message_size }
+
+    /// The version of the protocol the Launcher speaks.
+    pub fn protocol_version(&self) -> u32 { self.protocol_version }
+
+    /// Whether the Launcher reported supporting `capability` (see
+    /// `habitat_launcher_protocol::capability`).
+    pub fn supports(&self, capability: &str) -> bool { self.capabilities.contains(capability) }
+
+    /// The optional features in use on this connection.
+    pub fn active_capabilities(&self) -> Capabilities {
+        Capabilities { cpu_affinity:     self.supports(protocol::capability::CPU_AFFINITY),
+                       spawn_args:       self.supports(protocol::capability::SPAWN_ARGS),
+                       restart_with:     self.supports(protocol::capability::RESTART_WITH),
+                       framing:          self.framing == protocol::Framing::LengthPrefixed,
+                       diagnostics:      self.supports(protocol::capability::DIAGNOSTICS),
+                       readiness_probe:  self.supports(protocol::capability::READINESS_PROBE),
+                       spawn_key:        self.supports(protocol::capability::SPAWN_KEY),
+                       restart_history:  self.supports(protocol::capability::RESTART_HISTORY),
+                       cgroup:           self.supports(protocol::capability::CGROUP),
+                       preload:          self.supports(protocol::capability::PRELOAD),
+                       validate_pid:     self.supports(protocol::capability::VALIDATE_PID),
+                       health_summary:   self.supports(protocol::capability::HEALTH_SUMMARY),
+                       scheduling:       self.supports(protocol::capability::SCHEDULING),
+                       early_exit:       self.supports(protocol::capability::EARLY_EXIT),
+                       ping:             self.supports(protocol::capability::PING),
+                       subscribe_exits:  self.supports(protocol::capability::EXIT_NOTIFICATIONS),
+                       resource_usage:   self.supports(protocol::capability::RESOURCE_USAGE),
+                       privileges:       self.supports(protocol::capability::PRIVILEGES),
+                       list_services:    self.supports(protocol::capability::LIST_SERVICES),
+                       fd_counts:        self.supports(protocol::capability::FD_COUNTS),
+                       uptimes:          self.supports(protocol::capability::UPTIMES),
+                       config:           self.supports(protocol::capability::CONFIG),
+                       max_message_size: self.max_message_size, }
+    }
+
+    /// The current state of the connection and the features in use on
+    /// it.
+    pub fn summary(&self) -> LauncherSummary {
+        LauncherSummary { state:            self.state(),
+                          capabilities:     self.active_capabilities(),
+                          protocol_version: self.protocol_version,
+                          average_latency:  self.average_latency(), }
+    }
+
+    /// The current state of the connection to the Launcher.
+    pub fn state(&self) -> ConnectionState { *self.state.borrow() }
+
+    /// Watch the state of the connection to the Launcher. The
+    /// receiver starts out holding the current state and is notified
+    /// of every transition after that.
+    pub fn subscribe_state(&self) -> watch::Receiver<ConnectionState> { self.state.subscribe() }
+
+    fn set_state(&self, next: ConnectionState) {
+        self.state.send_if_modified(|state| {
+                      if *state != next && state.can_become(next) {
+                          debug!("Launcher connection state changed from {:?} to {:?}",
+                                 *state, next);
+                          *state = next;
+                          true
+                      } else {
+                          false
+                      }
+                  });
     }
 
     /// Read a launcher protocol message from a byte array
-    fn read<T>(bytes: &[u8]) -> Result<T, IPCReadError>
+    fn read<T>(bytes: &[u8], framing: protocol::Framing) -> Result<T, IPCReadError>
         where T: protocol::LauncherMessage
     {
-        let txn = protocol::NetTxn::from_bytes(bytes).map_err(IPCReadError::ProtocolDeserialize)?;
+        let txn = protocol::NetTxn::from_framed_bytes(bytes, framing)
+                      .map_err(IPCReadError::ProtocolDeserialize)?;
+        ipc_trace::read(txn.message_id(), bytes.len());
+        // The Launcher announces its own shutdown unprompted, so the
+        // announcement can arrive in place of a command's reply.
+        if txn.message_id() == protocol::Shutdown::MESSAGE_ID
+           && T::MESSAGE_ID != protocol::Shutdown::MESSAGE_ID
+        {
+            return Err(IPCReadError::LauncherShuttingDown);
+        }
         if txn.message_id() == "NetErr" {
             let err = txn.decode::<protocol::NetErr>()
                          .map_err(IPCReadError::PayloadDeserialize)?;
             return Err(IPCReadError::LauncherCommand(err));
         }
+        if txn.message_id() != T::MESSAGE_ID {
+            return Err(IPCReadError::UnexpectedReply { expected: T::MESSAGE_ID,
+                                                       received: txn.message_id().to_string(), });
+        }
         let msg = txn.decode::<T>()
                      .map_err(IPCReadError::PayloadDeserialize)?;
         Ok(msg)
     }
 
+    /// If `bytes` is an exit notification, forward it to the
+    /// subscriber in `exits` and return true. The Launcher pushes these
+    /// unprompted, so one can turn up ahead of a command's reply.
+    fn forward_exit(bytes: &[u8],
+                    framing: protocol::Framing,
+                    exits: Option<&ExitSubscriber>)
+                    -> bool {
+        let exits = match exits {
+            Some(exits) => exits,
+            None => return false,
+        };
+        let txn = match protocol::NetTxn::from_framed_bytes(bytes, framing) {
+            Ok(txn) if txn.message_id() == protocol::ProcessExited::MESSAGE_ID => txn,
+            _ => return false,
+        };
+        ipc_trace::read(txn.message_id(), bytes.len());
+        let exit = match txn.decode::<protocol::ProcessExited>() {
+            Ok(exit) => exit,
+            Err(err) => {
+                warn!("Unable to read an exit notification from the Launcher: {}",
+                      err);
+                return true;
+            }
+        };
+        let mut subscriber = exits.lock()
+                                  .expect("Launcher exit subscriber lock poisoned");
+        if let Some(tx) = subscriber.as_ref() {
+            if tx.send(exit).is_err() {
+                debug!("Exit notifications are no longer wanted, dropping them");
+                *subscriber = None;
+            }
+        }
+        true
+    }
+
+    /// Drop a reply that isn't the one being waited for. This is
+    /// typically the reply to a command that timed out, arriving late;
+    /// left on the connection, it would be read as the reply to the next
+    /// command.
+    fn discard(err: &IPCReadError) {
+        debug!("Discarding a stale reply: {}", err);
+    }
+
     /// Receive and read protocol message from an IpcReceiver
-    fn recv<T>(rx: &IpcReceiver<Vec<u8>>) -> Result<T, ReceiveError>
+    fn recv<T>(rx: &IpcReceiver<Vec<u8>>,
+               framing: protocol::Framing,
+               exits: Option<&ExitSubscriber>)
+               -> Result<T, ReceiveError>
         where T: protocol::LauncherMessage
     {
-        match rx.recv() {
-            Ok(bytes) => Ok(Self::read(&bytes)?),
-            Err(err) => Err(ReceiveError::IPCReceive(err)),
+        loop {
+            match rx.recv() {
+                Ok(bytes) if Self::forward_exit(&bytes, framing, exits) => continue,
+                Ok(bytes) => {
+                    match Self::read(&bytes, framing) {
+                        Err(err @ IPCReadError::UnexpectedReply { .. }) => Self::discard(&err),
+                        reply => return Ok(reply?),
+                    }
+                }
+                Err(err) => return Err(ReceiveError::IPCReceive(err)),
+            }
         }
     }
 
@@ -114,7 +684,11 @@ impl LauncherCli {
     /// *should* be fine, but I can't guarantee that right now).
     ///
     /// As such, use this with caution and intention.
-    fn recv_timeout<T>(rx: &IpcReceiver<Vec<u8>>, timeout: Duration) -> Result<T, TryReceiveError>
+    fn recv_timeout<T>(rx: &IpcReceiver<Vec<u8>>,
+                       framing: protocol::Framing,
+                       timeout: Duration,
+                       exits: Option<&ExitSubscriber>)
+                       -> Result<T, TryReceiveError>
         where T: protocol::LauncherMessage
     {
         // If ipc_channel implemented this directly, we wouldn't have
@@ -122,9 +696,12 @@ impl LauncherCli {
         let start_time = Instant::now();
         loop {
             match rx.try_recv() {
+                Ok(bytes) if Self::forward_exit(&bytes, framing, exits) => {}
                 Ok(bytes) => {
-                    let msg = Self::read(&bytes).map_err(TryReceiveError::IPCRead)?;
-                    return Ok(msg);
+                    match Self::read(&bytes, framing) {
+                        Err(err @ IPCReadError::UnexpectedReply { .. }) => Self::discard(&err),
+                        reply => return reply.map_err(TryReceiveError::IPCRead),
+                    }
                 }
                 Err(TryRecvError::Empty) => {
                     trace!("try_recv would block; waiting 5ms");
@@ -140,43 +717,161 @@ impl LauncherCli {
         }
     }
 
-    /// Send a command to a Launcher
-    fn send<T>(tx: &IpcSender<Vec<u8>>, message: &T) -> Result<(), SendError>
+    /// Send a command to a Launcher, returning the number of bytes sent
+    fn send<T>(tx: &IpcSender<Vec<u8>>,
+               framing: protocol::Framing,
+               max_size: usize,
+               message: &T)
+               -> Result<usize, SendError>
         where T: protocol::LauncherMessage
     {
         let txn = protocol::NetTxn::build(message).map_err(SendError::PayloadSerialize)?;
-        let bytes = txn.to_bytes().map_err(SendError::ProtocolSerialize)?;
+        let bytes = txn.to_framed_bytes(framing)
+                       .map_err(SendError::ProtocolSerialize)?;
+        let size = bytes.len();
+        if size > max_size {
+            return Err(SendError::MessageTooLarge { size,
+                                                    max: max_size });
+        }
         tx.send(bytes).map_err(SendError::IPCSend)?;
-        Ok(())
+        Ok(size)
+    }
+
+    /// Send a command on this connection. A message that is too large
+    /// is refused before it reaches the wire, so it says nothing about
+    /// the connection; any other failure degrades it.
+    fn send_command<T>(&self, tx: &IpcSender<Vec<u8>>, message: &T) -> Result<usize, SendError>
+        where T: protocol::LauncherMessage
+    {
+        let sent = Self::send(tx, self.framing, self.max_message_size, message);
+        if let Err(err) = &sent {
+            if !matches!(err, SendError::MessageTooLarge { .. }) {
+                self.set_state(ConnectionState::Degraded);
+            }
+        }
+        sent
     }
 
     /// Receive and read protocol message from an IpcReceiver
-    fn try_recv<T>(rx: &IpcReceiver<Vec<u8>>) -> Result<Option<T>, ReceiveError>
+    fn try_recv<T>(rx: &IpcReceiver<Vec<u8>>,
+                   framing: protocol::Framing,
+                   exits: Option<&ExitSubscriber>)
+                   -> Result<Option<T>, ReceiveError>
         where T: protocol::LauncherMessage
     {
-        match rx.try_recv() {
-            Ok(bytes) => {
-                let msg = Self::read::<T>(&bytes)?;
-                Ok(Some(msg))
+        loop {
+            match rx.try_recv() {
+                Ok(bytes) if Self::forward_exit(&bytes, framing, exits) => continue,
+                Ok(bytes) => {
+                    match Self::read::<T>(&bytes, framing) {
+                        Err(err @ IPCReadError::UnexpectedReply { .. }) => Self::discard(&err),
+                        reply => return Ok(Some(reply?)),
+                    }
+                }
+                Err(TryRecvError::Empty) => return Ok(None),
+                Err(TryRecvError::IpcError(err)) => return Err(ReceiveError::IPCReceive(err)),
             }
-            Err(TryRecvError::Empty) => Ok(None),
-            Err(TryRecvError::IpcError(err)) => Err(ReceiveError::IPCReceive(err)),
         }
     }
 
+    fn lock_connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .expect("Launcher connection lock poisoned")
+    }
+
+    /// Record that the exchange that just ended, without timing out,
+    /// left the connection in `state`.
+    fn replied(&self, state: ConnectionState) {
+        self.timeouts.store(0, Ordering::Relaxed);
+        self.set_state(state);
+    }
+
+    /// Send a command to the Launcher and block until it replies,
+    /// tracking what the exchange says about the connection.
+    fn command<T, R>(&self, name: &'static str, message: &T) -> Result<R, IPCCommandError>
+        where T: protocol::LauncherMessage + 'static,
+              R: protocol::LauncherMessage
+    {
+        if self.state() == ConnectionState::ShuttingDown {
+            return Err(IPCCommandError::LauncherShuttingDown(name));
+        }
+        let connection = self.lock_connection();
+        let size = self.send_command(&connection.tx, message)
+                       .map_err(|err| IPCCommandError::Send(name, err))?;
+        let id = ipc_trace::sent(name, message, size);
+        let reply = Self::recv::<R>(&connection.rx, self.framing, Some(&self.exits));
+        ipc_trace::received(id, name, &reply);
+        self.replied(match &reply {
+                         Ok(_) => ConnectionState::Connected,
+                         Err(ReceiveError::IPCRead(err)) => err.into(),
+                         Err(ReceiveError::IPCReceive(err)) => err.into(),
+                     });
+        reply.map_err(|err| IPCCommandError::Receive(name, err))
+    }
+
+    /// Like `command`, but gives up waiting for the reply after the
+    /// interaction timeout.
+    fn try_command<T, R>(&self, name: &'static str, message: &T) -> Result<R, TryIPCCommandError>
+        where T: protocol::LauncherMessage + 'static,
+              R: protocol::LauncherMessage
+    {
+        if self.state() == ConnectionState::ShuttingDown {
+            return Err(TryIPCCommandError::LauncherShuttingDown(name));
+        }
+        let connection = self.lock_connection();
+        let size = self.send_command(&connection.tx, message)
+                       .map_err(|err| TryIPCCommandError::Send(name, err))?;
+        let id = ipc_trace::sent(name, message, size);
+        let reply = Self::recv_timeout::<R>(&connection.rx,
+                                            self.framing,
+                                            self.timeout,
+                                            Some(&self.exits));
+        ipc_trace::received(id, name, &reply);
+        match &reply {
+            Ok(_) => self.replied(ConnectionState::Connected),
+            Err(TryReceiveError::IPCRead(err)) => self.replied(err.into()),
+            Err(TryReceiveError::IPCReceive(err)) => self.replied(err.into()),
+            Err(TryReceiveError::Timeout) => {
+                // A slow reply now and then leaves the connection as it
+                // was
+                if self.timeouts.fetch_add(1, Ordering::Relaxed) + 1 >= DEGRADED_AFTER_TIMEOUTS {
+                    self.set_state(ConnectionState::Degraded);
+                }
+            }
+        }
+        reply.map_err(|err| TryIPCCommandError::TryReceive(name, err))
+    }
+
     pub fn launcher_status(&self) -> LauncherStatus {
-        match Self::try_recv::<protocol::Shutdown>(&self.rx) {
+        // The shutdown announcement may already have been consumed
+        // while waiting on a command's reply.
+        if self.state() == ConnectionState::ShuttingDown {
+            return LauncherStatus::GracefullyShutdown;
+        }
+        // Reading the connection also forwards any exit notifications
+        // waiting on it.
+        let connection = self.lock_connection();
+        match Self::try_recv::<protocol::Shutdown>(&connection.rx, self.framing, Some(&self.exits))
+        {
             // We haven't received any command to shutdown
             Ok(None) => LauncherStatus::Running,
             // Received a shutdown command
-            Ok(Some(_)) => LauncherStatus::GracefullyShutdown,
+            Ok(Some(_)) => {
+                self.set_state(ConnectionState::ShuttingDown);
+                LauncherStatus::GracefullyShutdown
+            }
             // Launcher IPC channel was disconnected
-            Err(ReceiveError::IPCReceive(IpcError::Disconnected)) => LauncherStatus::Shutdown,
+            Err(ReceiveError::IPCReceive(IpcError::Disconnected)) => {
+                self.set_state(ConnectionState::Disconnected);
+                LauncherStatus::Shutdown
+            }
             // Received a bad message, or encountered an IO error while communicating via IPC
             Err(err) => {
                 error!("Unexpected IPC communication error while checking for a shutdown \
                         request: {}",
                        err);
+                self.set_state(ConnectionState::Degraded);
                 LauncherStatus::Unknown
             }
         }
@@ -185,11 +880,7 @@ impl LauncherCli {
     /// Restart a running process with the same arguments
     pub fn restart(&self, pid: Pid) -> Result<Pid, IPCCommandError> {
         let msg = protocol::Restart { pid: pid.into() };
-        Self::send(&self.tx, &msg).map_err(|err| IPCCommandError::Send("restart", err))?;
-        let reply = Self::recv::<protocol::SpawnOk>(&self.rx).map_err(|err| {
-                                                                 IPCCommandError::Receive("restart",
-                                                                                          err)
-                                                             })?;
+        let reply = self.command::<_, protocol::SpawnOk>("restart", &msg)?;
         Ok(reply.pid as Pid)
     }
 
@@ -201,51 +892,159 @@ impl LauncherCli {
     pub fn spawn(&self,
                  id: &str,
                  bin: &Path,
-                 UserInfo { username,
-                            uid,
-                            groupname,
-                            gid, }: UserInfo,
+                 user_info: UserInfo,
                  password: Option<&str>,
                  env: Env)
-                 -> Result<Pid, IPCCommandError> {
+                 -> Result<Pid, SpawnError> {
+        self.spawn_with_options(id, bin, user_info, password, env, SpawnOptions::default())
+    }
+
+    /// Like `spawn`, but with optional settings that are refused rather
+    /// than silently ignored if the Launcher doesn't support them.
+    pub fn spawn_with_options(&self,
+                              id: &str,
+                              bin: &Path,
+                              user_info: UserInfo,
+                              password: Option<&str>,
+                              env: Env,
+                              options: SpawnOptions)
+                              -> Result<Pid, SpawnError> {
+        let spec = SpawnSpec { id: id.to_string(),
+                               bin: bin.to_path_buf(),
+                               user_info,
+                               password: password.map(str::to_string),
+                               env,
+                               options };
+        Ok(self.spawn_spec(spec)?.outcome.pid())
+    }
+
+    /// Spawn a process from `spec`, reporting whether the Launcher
+    /// found one already running for `spec.options.key` instead.
+    pub fn spawn_spec(&self, spec: SpawnSpec) -> Result<Spawned, SpawnError> {
+        let id = spec.id.clone();
+        let msg = self.spawn_message("spawn", spec)?;
+        let reply = self.command::<_, protocol::SpawnOk>("spawn", &msg)?;
+        if reply.pid == 0 {
+            warn!(target: "pidfile_tracing", "Spawn operation for {} resulted in a spawned PID of 0, which \
+                   should be impossible! (proceeding anyway)",
+                  id);
+        }
+        let outcome = if reply.duplicate_spawn {
+            debug!("Launcher was already running PID {} for this spawn of {}",
+                   reply.pid, id);
+            SpawnOutcome::DuplicateSpawn(reply.pid as Pid)
+        } else {
+            SpawnOutcome::Spawned(reply.pid as Pid)
+        };
+        Ok(Spawned { outcome,
+                     cgroup: reply.cgroup_path.map(PathBuf::from) })
+    }
+
+    /// Stop the process `pid` and start a new one in its place from
+    /// `spec`, returning the new PID. The Launcher handles both halves
+    /// as one command, so no other command is handled in between. An
+    /// error means the old process may still be running; once it has
+    /// stopped, a failure to start the new one is reported as
+    /// `RestartOutcome::StartFailed`.
+    pub fn restart_with(&self,
+                        pid: Pid,
+                        spec: SpawnSpec)
+                        -> Result<RestartOutcome, IPCCommandError> {
+        if !self.supports(protocol::capability::RESTART_WITH) {
+            return Err(IPCCommandError::Unsupported("restart_with",
+                                                    protocol::capability::RESTART_WITH));
+        }
+        let msg = protocol::RestartWith { pid:   pid.into(),
+                                          spawn: self.spawn_message("restart_with", spec)?, };
+        match self.command::<_, protocol::RestartWithResult>("restart_with", &msg)? {
+            protocol::RestartWithResult::Restarted(pid) => {
+                Ok(RestartOutcome::Restarted(pid as Pid))
+            }
+            protocol::RestartWithResult::StartFailed(err) => {
+                Ok(RestartOutcome::StartFailed(SpawnError::from_launcher("restart_with", err)))
+            }
+        }
+    }
+
+    /// Build the `Spawn` message for `spec`, refusing options the
+    /// Launcher doesn't support rather than having them ignored.
+    fn spawn_message(&self,
+                     command: &'static str,
+                     SpawnSpec { id,
+                                 bin,
+                                 user_info:
+                                     UserInfo { username,
+                                                uid,
+                                                groupname,
+                                                gid, },
+                                 password,
+                                 env,
+                                 options, }: SpawnSpec)
+                     -> Result<protocol::Spawn, IPCCommandError> {
+        if !options.cpu_affinity.is_empty() && !self.supports(protocol::capability::CPU_AFFINITY) {
+            return Err(IPCCommandError::Unsupported(command, protocol::capability::CPU_AFFINITY));
+        }
+        if (!options.args.is_empty() || options.cwd.is_some())
+           && !self.supports(protocol::capability::SPAWN_ARGS)
+        {
+            return Err(IPCCommandError::Unsupported(command, protocol::capability::SPAWN_ARGS));
+        }
+        if options.key.is_some() && !self.supports(protocol::capability::SPAWN_KEY) {
+            return Err(IPCCommandError::Unsupported(command, protocol::capability::SPAWN_KEY));
+        }
+        if options.readiness.is_some() && !self.supports(protocol::capability::READINESS_PROBE) {
+            return Err(IPCCommandError::Unsupported(command,
+                                                    protocol::capability::READINESS_PROBE));
+        }
+        if options.cgroup.is_some() && !self.supports(protocol::capability::CGROUP) {
+            return Err(IPCCommandError::Unsupported(command, protocol::capability::CGROUP));
+        }
+        if options.scheduling.is_some() && !self.supports(protocol::capability::SCHEDULING) {
+            return Err(IPCCommandError::Unsupported(command, protocol::capability::SCHEDULING));
+        }
+        if options.early_exit_window.is_some() && !self.supports(protocol::capability::EARLY_EXIT) {
+            return Err(IPCCommandError::Unsupported(command, protocol::capability::EARLY_EXIT));
+        }
+        if options.privileges.is_some() && !self.supports(protocol::capability::PRIVILEGES) {
+            return Err(IPCCommandError::Unsupported(command, protocol::capability::PRIVILEGES));
+        }
+        let early_exit_window_ms =
+            options.early_exit_window
+                   .map(|window| u32::try_from(window.as_millis()).unwrap_or(u32::MAX));
         // On Windows, we only expect user to be Some.
         //
         // On Linux, we expect uid and gid to be Some, while
         // user and groupname may be either Some or None. Only the IDs are
         // used; names are only for backward compatibility with older
         // Launchers.
-        let msg = protocol::Spawn { binary: bin.to_string_lossy().into_owned(),
-                                    svc_user: username,
-                                    svc_group: groupname,
-                                    svc_user_id: uid,
-                                    svc_group_id: gid,
-                                    svc_password: password.map(str::to_string),
-                                    env,
-                                    id: id.to_string() };
-
-        Self::send(&self.tx, &msg).map_err(|err| IPCCommandError::Send("spawn", err))?;
-        let reply = Self::recv::<protocol::SpawnOk>(&self.rx).map_err(|err| {
-                                                                 IPCCommandError::Receive("spawn",
-                                                                                          err)
-                                                             })?;
-        if reply.pid == 0 {
-            warn!(target: "pidfile_tracing", "Spawn operation for {} resulted in a spawned PID of 0, which \
-                   should be impossible! (proceeding anyway)",
-                  id);
-        }
-        Ok(reply.pid as Pid)
+        Ok(protocol::Spawn { binary: bin.to_string_lossy().into_owned(),
+                             svc_user: username,
+                             svc_group: groupname,
+                             svc_user_id: uid,
+                             svc_group_id: gid,
+                             svc_password: password,
+                             env,
+                             id,
+                             cpu_affinity: options.cpu_affinity,
+                             args: options.args,
+                             cwd: options.cwd.map(|cwd| cwd.to_string_lossy().into_owned()),
+                             readiness: options.readiness,
+                             key: options.key,
+                             cgroup: options.cgroup,
+                             scheduling: options.scheduling,
+                             early_exit_window_ms,
+                             privileges: options.privileges })
     }
 
     /// Query the launcher for the PID of the named service. If the
     /// Launcher is aware of it, you'll get `Ok(Some(Pid))`
     pub fn pid_of(&self, service_name: &str) -> Result<Option<Pid>, TryIPCCommandError> {
         let msg = protocol::PidOf { service_name: service_name.to_string(), };
-        Self::send(&self.tx, &msg).map_err(|err| TryIPCCommandError::Send("pid_of", err))?;
         // This should be a recv_timeout until pidfile-less
         // supervisors are the norm. We only expect to not receive a
         // response when dealing with older Launchers that didn't know
         // how to return PIDs.
-        let reply = Self::recv_timeout::<protocol::PidIs>(&self.rx, self.timeout).map_err(|err| TryIPCCommandError::TryReceive("pid_of", err))?;
+        let reply = self.try_command::<_, protocol::PidIs>("pid_of", &msg)?;
         // TODO (CM): really, we need to have all our protocol types
         // that use pids actually use a Pid type that's nonzero, with
         // lots of descriptive errors for failures.
@@ -255,26 +1054,272 @@ impl LauncherCli {
         }
     }
 
+    /// Whether `pid` is still a running process the Launcher spawned for
+    /// `service_name`, and, given `started_at` (seconds since the Unix
+    /// epoch, as reported by `uptimes`), the one started then. Check
+    /// this before signaling a PID that may have been held for a while,
+    /// since it could have been reused by an unrelated process.
+    pub fn validate_pid(&self,
+                        pid: Pid,
+                        service_name: &str,
+                        started_at: Option<u64>)
+                        -> Result<bool, IPCCommandError> {
+        if !self.supports(protocol::capability::VALIDATE_PID) {
+            return Err(IPCCommandError::Unsupported("validate_pid",
+                                                    protocol::capability::VALIDATE_PID));
+        }
+        let msg = protocol::ValidatePid { pid: pid.into(),
+                                          service_name: service_name.to_string(),
+                                          started_at };
+        let reply = self.command::<_, protocol::PidValidity>("validate_pid", &msg)?;
+        Ok(reply.valid)
+    }
+
     /// Query the launcher for its version. If the
     /// Launcher is aware of it, you'll get `Ok(u32)`
     pub fn version(&self) -> Result<u32, TryIPCCommandError> {
         let msg = protocol::Version {};
-        Self::send(&self.tx, &msg).map_err(|err| TryIPCCommandError::Send("version", err))?;
-
         // We only expect to not receive a response when dealing with
         // older Launchers that didn't know how to return its version.
-        let reply = Self::recv_timeout::<protocol::VersionNumber>(&self.rx, self.timeout).map_err(|err| TryIPCCommandError::TryReceive("version", err))?;
+        let reply = self.try_command::<_, protocol::VersionNumber>("version", &msg)?;
         Ok(reply.version)
     }
 
+    /// Query the launcher for the number of open file descriptors of
+    /// each process it is supervising. A count is `None` where the
+    /// platform doesn't expose it.
+    pub fn fd_counts(&self) -> Result<Vec<protocol::FdCount>, TryIPCCommandError> {
+        if !self.supports(protocol::capability::FD_COUNTS) {
+            return Err(TryIPCCommandError::Unsupported("fd_counts",
+                                                       protocol::capability::FD_COUNTS));
+        }
+        let msg = protocol::FdCounts {};
+        let reply = self.try_command::<_, protocol::FdCountReport>("fd_counts", &msg)?;
+        Ok(reply.counts)
+    }
+
+    /// Query the launcher for when each of its processes started and
+    /// how many times it has restarted them.
+    pub fn uptimes(&self) -> Result<Vec<protocol::ProcessUptime>, TryIPCCommandError> {
+        if !self.supports(protocol::capability::UPTIMES) {
+            return Err(TryIPCCommandError::Unsupported("uptimes", protocol::capability::UPTIMES));
+        }
+        let msg = protocol::ProcessUptimes {};
+        let reply = self.try_command::<_, protocol::ProcessUptimeReport>("uptimes", &msg)?;
+        Ok(reply.uptimes)
+    }
+
+    /// Query the launcher for the configuration it is running with.
+    /// Fields that are newer than this client are ignored.
+    pub fn config(&self) -> Result<protocol::LauncherConfigIs, TryIPCCommandError> {
+        if !self.supports(protocol::capability::CONFIG) {
+            return Err(TryIPCCommandError::Unsupported("config", protocol::capability::CONFIG));
+        }
+        let msg = protocol::LauncherConfig {};
+        self.try_command::<_, protocol::LauncherConfigIs>("config", &msg)
+    }
+
+    /// Problems the Launcher has run into outside of a command's reply,
+    /// such as processes that failed to spawn or to be reaped. With
+    /// `clear`, the Launcher forgets them once they are returned.
+    pub fn diagnostics(&self, clear: bool) -> Result<protocol::DiagnosticReport, IPCCommandError> {
+        if !self.supports(protocol::capability::DIAGNOSTICS) {
+            return Err(IPCCommandError::Unsupported("diagnostics",
+                                                    protocol::capability::DIAGNOSTICS));
+        }
+        let msg = protocol::Diagnostics { clear };
+        self.command("diagnostics", &msg)
+    }
+
+    /// How many times the Launcher has restarted each supervised
+    /// process, with the most recent of those restarts. The Launcher
+    /// only keeps a bounded number of them per process.
+    pub fn restart_history(&self) -> Result<Vec<protocol::ProcessRestarts>, IPCCommandError> {
+        if !self.supports(protocol::capability::RESTART_HISTORY) {
+            return Err(IPCCommandError::Unsupported("restart_history",
+                                                    protocol::capability::RESTART_HISTORY));
+        }
+        let msg = protocol::RestartHistory {};
+        let reply = self.command::<_, protocol::RestartHistoryReport>("restart_history", &msg)?;
+        Ok(reply.processes)
+    }
+
+    /// How many supervised processes are running, and how many have
+    /// stopped or crashed since the Launcher started, counted by the
+    /// Launcher in one pass. Much cheaper than asking after each service
+    /// when only the overall picture is needed. Gives up after the
+    /// interaction timeout.
+    pub fn health_summary(&self) -> Result<protocol::HealthSummaryReport, TryIPCCommandError> {
+        if !self.supports(protocol::capability::HEALTH_SUMMARY) {
+            return Err(TryIPCCommandError::Unsupported("health_summary",
+                                                       protocol::capability::HEALTH_SUMMARY));
+        }
+        self.try_command("health_summary", &protocol::HealthSummary {})
+    }
+
+    /// How much CPU, memory and file descriptors the Launcher itself is
+    /// using, apart from the processes it supervises. Watched over time,
+    /// this shows up leaks in the Launcher, which outlives many
+    /// Supervisors. Gives up after the interaction timeout.
+    pub fn resource_usage(&self) -> Result<LauncherResourceUsage, TryIPCCommandError> {
+        if !self.supports(protocol::capability::RESOURCE_USAGE) {
+            return Err(TryIPCCommandError::Unsupported("resource_usage",
+                                                       protocol::capability::RESOURCE_USAGE));
+        }
+        let reply =
+            self.try_command::<_, protocol::ResourceUsageReport>("resource_usage",
+                                                                 &protocol::ResourceUsage {})?;
+        Ok(reply.into())
+    }
+
+    /// Every process the Launcher supervises, with what is needed to
+    /// adopt it. A Supervisor that has restarted, e.g. into an update,
+    /// uses this to take over the services it left running rather than
+    /// orphaning them or spawning them again. Those it spawned before
+    /// restarting are marked `prior_supervisor`. Empty when the
+    /// Launcher supervises nothing.
+    pub fn list_services(&self) -> Result<Vec<protocol::SupervisedService>, IPCCommandError> {
+        if !self.supports(protocol::capability::LIST_SERVICES) {
+            return Err(IPCCommandError::Unsupported("list_services",
+                                                    protocol::capability::LIST_SERVICES));
+        }
+        let reply =
+            self.command::<_, protocol::ServiceList>("list_services", &protocol::ListServices {})?;
+        Ok(reply.services)
+    }
+
+    /// Measure how long a command takes to reach the Launcher and be
+    /// answered, adding it to the round trips `average_latency` is
+    /// taken over. Latency that keeps rising tends to come before
+    /// commands start timing out. Gives up after the interaction
+    /// timeout.
+    pub fn ping(&self) -> Result<Duration, TryIPCCommandError> {
+        if !self.supports(protocol::capability::PING) {
+            return Err(TryIPCCommandError::Unsupported("ping", protocol::capability::PING));
+        }
+        let started = Instant::now();
+        self.try_command::<_, protocol::NetOk>("ping", &protocol::Ping {})?;
+        let latency = started.elapsed();
+        let mut latencies = self.latencies
+                                .lock()
+                                .expect("Launcher latency lock poisoned");
+        if latencies.len() == LATENCY_SAMPLES {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+        Ok(latency)
+    }
+
+    /// The average round trip of the most recent pings on this
+    /// connection, if there have been any.
+    pub fn average_latency(&self) -> Option<Duration> {
+        let latencies = self.latencies
+                            .lock()
+                            .expect("Launcher latency lock poisoned");
+        let count = u32::try_from(latencies.len()).ok()
+                                                  .filter(|count| *count > 0)?;
+        Some(latencies.iter().sum::<Duration>() / count)
+    }
+
+    /// Have the Launcher notify this client whenever a process it
+    /// supervises exits, rather than the exit being discovered on the
+    /// next poll. Notifications are read off the connection along with
+    /// the replies to commands and by `launcher_status`, and sent to the
+    /// returned receiver. Subscribing again replaces the previous
+    /// receiver. The receiver is closed when the client reconnects,
+    /// since the subscription doesn't carry over to the new connection.
+    pub fn subscribe_exits(&self) -> Result<ExitNotifications, IPCCommandError> {
+        if !self.supports(protocol::capability::EXIT_NOTIFICATIONS) {
+            return Err(IPCCommandError::Unsupported("subscribe_exits",
+                                                    protocol::capability::EXIT_NOTIFICATIONS));
+        }
+        self.command::<_, protocol::NetOk>("subscribe_exits", &protocol::SubscribeExits {})?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        *self.exits
+             .lock()
+             .expect("Launcher exit subscriber lock poisoned") = Some(tx);
+        Ok(rx)
+    }
+
+    /// How the process with the given PID is scheduled.
+    pub fn scheduling(&self, pid: Pid) -> Result<protocol::Scheduling, IPCCommandError> {
+        if !self.supports(protocol::capability::SCHEDULING) {
+            return Err(IPCCommandError::Unsupported("scheduling",
+                                                    protocol::capability::SCHEDULING));
+        }
+        let msg = protocol::SchedulingOf { pid: pid.into() };
+        let reply = self.command::<_, protocol::SchedulingIs>("scheduling", &msg)?;
+        Ok(reply.scheduling)
+    }
+
+    /// Change how the process with the given PID is scheduled, returning
+    /// how it is scheduled afterwards. The Launcher refuses real-time
+    /// scheduling and negative nice values for services it hasn't been
+    /// configured to trust, replying with `ErrCode::InvalidScheduling`.
+    pub fn set_scheduling(&self,
+                          pid: Pid,
+                          scheduling: protocol::Scheduling)
+                          -> Result<protocol::Scheduling, IPCCommandError> {
+        if !self.supports(protocol::capability::SCHEDULING) {
+            return Err(IPCCommandError::Unsupported("set_scheduling",
+                                                    protocol::capability::SCHEDULING));
+        }
+        let msg = protocol::SetScheduling { pid: pid.into(),
+                                            scheduling };
+        let reply = self.command::<_, protocol::SchedulingIs>("set_scheduling", &msg)?;
+        Ok(reply.scheduling)
+    }
+
+    /// Have the Launcher prepare to spawn `bin`, checking that it can be
+    /// run and reading it and everything under `paths` into the page
+    /// cache where the platform allows, so that a later spawn starts
+    /// quickly. Failures the Launcher explains, such as `bin` not being
+    /// found, are returned as the `NetErr` in the `IPCCommandError`.
+    pub fn preload(&self,
+                   bin: &Path,
+                   paths: &[PathBuf])
+                   -> Result<protocol::PreloadOk, IPCCommandError> {
+        if !self.supports(protocol::capability::PRELOAD) {
+            return Err(IPCCommandError::Unsupported("preload", protocol::capability::PRELOAD));
+        }
+        let msg = protocol::Preload { binary: bin.to_string_lossy().into_owned(),
+                                      paths:  paths.iter()
+                                                   .map(|path| path.to_string_lossy().into_owned())
+                                                   .collect(), };
+        self.command("preload", &msg)
+    }
+
+    /// Whether the process with the given PID has passed the readiness
+    /// probe it was spawned with.
+    pub fn readiness(&self, pid: Pid) -> Result<protocol::ReadinessState, IPCCommandError> {
+        if !self.supports(protocol::capability::READINESS_PROBE) {
+            return Err(IPCCommandError::Unsupported("readiness",
+                                                    protocol::capability::READINESS_PROBE));
+        }
+        let msg = protocol::ReadinessOf { pid: pid.into() };
+        let reply = self.command::<_, protocol::ReadinessIs>("readiness", &msg)?;
+        Ok(reply.readiness)
+    }
+
+    /// Block until the process with the given PID passes its readiness
+    /// probe. The Launcher enforces the probe's timeout, so this
+    /// returns `ReadinessError::ReadinessTimeout` once it gives up.
+    pub fn wait_until_ready(&self, pid: Pid) -> Result<(), ReadinessError> {
+        loop {
+            match self.readiness(pid)? {
+                protocol::ReadinessState::Ready => return Ok(()),
+                protocol::ReadinessState::TimedOut => {
+                    return Err(ReadinessError::ReadinessTimeout(pid));
+                }
+                protocol::ReadinessState::NoProbe => return Err(ReadinessError::NoProbe(pid)),
+                protocol::ReadinessState::Pending => thread::sleep(READINESS_POLL_INTERVAL),
+            }
+        }
+    }
+
     pub fn terminate(&self, pid: Pid) -> Result<i32, IPCCommandError> {
         let msg = protocol::Terminate { pid: pid.into() };
-        Self::send(&self.tx, &msg).map_err(|err| IPCCommandError::Send("terminate", err))?;
-        let reply =
-            Self::recv::<protocol::TerminateOk>(&self.rx).map_err(|err| {
-                                                             IPCCommandError::Receive("terminate",
-                                                                                      err)
-                                                         })?;
+        let reply = self.command::<_, protocol::TerminateOk>("terminate", &msg)?;
         Ok(reply.exit_code)
     }
 }
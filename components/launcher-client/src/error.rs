@@ -1,3 +1,4 @@
+use habitat_core::os::process::Pid;
 use habitat_launcher_protocol as protocol;
 use std::io;
 use thiserror::Error;
@@ -15,6 +16,9 @@ pub enum ConnectError {
     LauncherRegisterSend(#[source] SendError),
     #[error("Failed to receive registration IPC command response from the launcher")]
     LauncherRegisterReceive(#[source] IPCReadError),
+    #[error("The launcher speaks protocol version {launcher}, but this client speaks version \
+             {client}")]
+    VersionMismatch { launcher: u32, client: u32 },
 }
 
 /// Errors that occur when remotely executing a command on the Habitat Launcher
@@ -24,6 +28,85 @@ pub enum IPCCommandError {
     Send(&'static str, #[source] SendError),
     #[error("Failed to receive '{0}' command response from launcher")]
     Receive(&'static str, #[source] ReceiveError),
+    #[error("Not sending '{0}' command; the launcher is shutting down")]
+    LauncherShuttingDown(&'static str),
+    #[error("Not sending '{0}' command; the launcher does not support '{1}'")]
+    Unsupported(&'static str, &'static str),
+}
+
+/// Why the Launcher failed to spawn a service. Failures the Launcher
+/// was able to explain get their own variant; anything else is the
+/// underlying `IPCCommandError`.
+#[derive(Debug, Error)]
+pub enum SpawnError {
+    #[error("{0}")]
+    BinaryNotFound(String),
+    #[error("{0}")]
+    PermissionDenied(String),
+    #[error("{0}")]
+    MissingDependency(String),
+    #[error("{0}")]
+    UserNotFound(String),
+    #[error("{0}")]
+    GroupNotFound(String),
+    #[error("{0}")]
+    InvalidCpuAffinity(String),
+    #[error("{0}")]
+    CgroupUnsupported(String),
+    #[error("{0}")]
+    InvalidScheduling(String),
+    #[error("{0}")]
+    InvalidPrivileges(String),
+    #[error("{0}")]
+    ExitedEarly(String),
+    #[error(transparent)]
+    Command(IPCCommandError),
+}
+
+impl SpawnError {
+    /// Classify a failure the Launcher reported while running
+    /// `command`.
+    pub(crate) fn from_launcher(command: &'static str, err: protocol::NetErr) -> Self {
+        match err.code {
+            protocol::ErrCode::BinaryNotFound => SpawnError::BinaryNotFound(err.msg),
+            protocol::ErrCode::PermissionDenied => SpawnError::PermissionDenied(err.msg),
+            protocol::ErrCode::MissingDependency => SpawnError::MissingDependency(err.msg),
+            protocol::ErrCode::UserNotFound => SpawnError::UserNotFound(err.msg),
+            protocol::ErrCode::GroupNotFound => SpawnError::GroupNotFound(err.msg),
+            protocol::ErrCode::InvalidCpuAffinity => SpawnError::InvalidCpuAffinity(err.msg),
+            protocol::ErrCode::CgroupUnsupported => SpawnError::CgroupUnsupported(err.msg),
+            protocol::ErrCode::InvalidScheduling => SpawnError::InvalidScheduling(err.msg),
+            protocol::ErrCode::InvalidPrivileges => SpawnError::InvalidPrivileges(err.msg),
+            protocol::ErrCode::ExitedEarly => SpawnError::ExitedEarly(err.msg),
+            _ => {
+                let err = ReceiveError::IPCRead(IPCReadError::LauncherCommand(err));
+                SpawnError::Command(IPCCommandError::Receive(command, err))
+            }
+        }
+    }
+}
+
+impl From<IPCCommandError> for SpawnError {
+    fn from(err: IPCCommandError) -> Self {
+        match err {
+            IPCCommandError::Receive(command,
+                                     ReceiveError::IPCRead(IPCReadError::LauncherCommand(err))) => {
+                SpawnError::from_launcher(command, err)
+            }
+            err => SpawnError::Command(err),
+        }
+    }
+}
+
+/// Why a spawned process could not be confirmed ready.
+#[derive(Debug, Error)]
+pub enum ReadinessError {
+    #[error("Process {0} did not pass its readiness probe before the timeout")]
+    ReadinessTimeout(Pid),
+    #[error("Process {0} was spawned without a readiness probe")]
+    NoProbe(Pid),
+    #[error(transparent)]
+    Command(#[from] IPCCommandError),
 }
 
 /// Errors that occur when trying to remotely executing a command on the Habitat Launcher
@@ -33,6 +116,10 @@ pub enum TryIPCCommandError {
     Send(&'static str, #[source] SendError),
     #[error("Failed to try receiving '{0}' command response from launcher")]
     TryReceive(&'static str, #[source] TryReceiveError),
+    #[error("Not sending '{0}' command; the launcher is shutting down")]
+    LauncherShuttingDown(&'static str),
+    #[error("Not sending '{0}' command; the launcher does not support '{1}'")]
+    Unsupported(&'static str, &'static str),
 }
 
 /// Errors that occur when attempting to read an IPC response from the Habitat Launcher
@@ -44,6 +131,13 @@ pub enum IPCReadError {
     PayloadDeserialize(protocol::Error),
     #[error("Launcher command execution failed: {0}")]
     LauncherCommand(protocol::NetErr),
+    #[error("Launcher announced that it is shutting down")]
+    LauncherShuttingDown,
+    #[error("Received a {received} from the launcher, expecting a {expected}")]
+    UnexpectedReply {
+        expected: &'static str,
+        received: String,
+    },
 }
 
 ///  Errors that occur when attempting to send a command to the Habitat Launcher via IPC
@@ -55,6 +149,8 @@ pub enum SendError {
     PayloadSerialize(protocol::Error),
     #[error("Failed to send command to launcher")]
     IPCSend(#[source] ipc_channel::Error),
+    #[error("Not sending a {size} byte message; the launcher accepts at most {max} bytes")]
+    MessageTooLarge { size: usize, max: usize },
 }
 
 /// Errors that occur when attempting to blocking receive command responses from the Habitat
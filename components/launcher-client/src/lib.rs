@@ -1,12 +1,23 @@
 mod client;
 pub mod error;
+mod trace;
 
 pub use habitat_launcher_protocol::{ERR_NO_RETRY_EXCODE,
                                     LAUNCHER_PID_ENV,
                                     OK_NO_RETRY_EXCODE};
 
-pub use crate::{client::{LauncherCli,
-                         LauncherStatus},
+pub use crate::{client::{Capabilities,
+                         ConnectionState,
+                         ExitNotifications,
+                         LauncherCli,
+                         LauncherResourceUsage,
+                         LauncherStatus,
+                         LauncherSummary,
+                         RestartOutcome,
+                         SpawnOptions,
+                         SpawnOutcome,
+                         SpawnSpec,
+                         Spawned},
                 error::*};
 
 pub fn env_pipe() -> Option<String> {
@@ -0,0 +1,131 @@
+//! Trace logging of the conversation with the Launcher.
+//!
+//! With the `ipc_trace` feature, every command sent and every reply
+//! received is logged at trace level, tagged with a correlation ID so
+//! the two halves of an exchange can be matched up. Payloads are
+//! logged with environment values and credentials redacted. Without
+//! the feature these functions do nothing.
+//!
+//! The correlation ID is local to this process; it is not sent to the
+//! Launcher.
+
+#[cfg(feature = "ipc_trace")]
+pub(crate) use self::enabled::*;
+
+#[cfg(not(feature = "ipc_trace"))]
+pub(crate) use self::disabled::*;
+
+#[cfg(feature = "ipc_trace")]
+mod enabled {
+    use habitat_launcher_protocol::{self as protocol,
+                                    LauncherMessage};
+    use log::trace;
+    use std::{any::Any,
+              fmt,
+              sync::atomic::{AtomicU64,
+                             Ordering}};
+
+    const REDACTED: &str = "<redacted>";
+
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+    /// Log a command that was just sent, returning the correlation ID
+    /// for its reply.
+    pub(crate) fn sent<T>(name: &str, message: &T, size: usize) -> u64
+        where T: LauncherMessage + 'static
+    {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        trace!("IPC #{} sent '{}' {} ({} bytes): {}",
+               id,
+               name,
+               T::MESSAGE_ID,
+               size,
+               redacted(message));
+        id
+    }
+
+    /// Log the outcome of the command with correlation ID `id`.
+    pub(crate) fn received<R, E>(id: u64, name: &str, reply: &Result<R, E>)
+        where R: LauncherMessage,
+              E: fmt::Display
+    {
+        match reply {
+            Ok(reply) => trace!("IPC #{} '{}' succeeded: {:?}", id, name, reply),
+            Err(err) => trace!("IPC #{} '{}' failed: {}", id, name, err),
+        }
+    }
+
+    /// Log a message read from the Launcher, before it is decoded.
+    pub(crate) fn read(message_id: &str, size: usize) {
+        trace!("IPC read {} ({} bytes)", message_id, size);
+    }
+
+    fn redacted<T>(message: &T) -> String
+        where T: LauncherMessage + 'static
+    {
+        let any: &dyn Any = message;
+        if let Some(spawn) = any.downcast_ref::<protocol::Spawn>() {
+            let mut spawn = spawn.clone();
+            redact(&mut spawn);
+            format!("{:?}", spawn)
+        } else if let Some(restart) = any.downcast_ref::<protocol::RestartWith>() {
+            let mut restart = restart.clone();
+            redact(&mut restart.spawn);
+            format!("{:?}", restart)
+        } else {
+            format!("{:?}", message)
+        }
+    }
+
+    fn redact(spawn: &mut protocol::Spawn) {
+        for value in spawn.env.values_mut() {
+            *value = REDACTED.to_string();
+        }
+        if spawn.svc_password.is_some() {
+            spawn.svc_password = Some(REDACTED.to_string());
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn spawn() -> protocol::Spawn {
+            let mut spawn = protocol::Spawn { id: String::from("redis.default"),
+                                              svc_password: Some(String::from("hunter2")),
+                                              ..Default::default() };
+            spawn.env
+                 .insert(String::from("REDIS_AUTH"), String::from("s3cret"));
+            spawn
+        }
+
+        #[test]
+        fn spawns_are_logged_without_secrets() {
+            let logged = redacted(&spawn());
+            assert!(logged.contains("REDIS_AUTH"));
+            assert!(!logged.contains("s3cret"));
+            assert!(!logged.contains("hunter2"));
+        }
+
+        #[test]
+        fn restarts_are_logged_without_the_secrets_of_their_spawn() {
+            let logged = redacted(&protocol::RestartWith { pid:   1234,
+                                                           spawn: spawn(), });
+            assert!(logged.contains("redis.default"));
+            assert!(!logged.contains("s3cret"));
+            assert!(!logged.contains("hunter2"));
+        }
+    }
+}
+
+#[cfg(not(feature = "ipc_trace"))]
+mod disabled {
+    #[inline(always)]
+    pub(crate) fn sent<T>(_name: &str, _message: &T, _size: usize) -> u64 { 0 }
+
+    #[inline(always)]
+    pub(crate) fn received<R, E>(_id: u64, _name: &str, _reply: &Result<R, E>) {}
+
+    #[inline(always)]
+    pub(crate) fn read(_message_id: &str, _size: usize) {}
+}
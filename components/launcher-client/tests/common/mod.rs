@@ -0,0 +1,130 @@
+//! A fake Launcher that runs on a thread of the test process, shared by
+//! the integration tests.
+
+use habitat_launcher_protocol::{self as protocol,
+                                Framing,
+                                LauncherMessage,
+                                NetTxn};
+use ipc_channel::ipc::{IpcOneShotServer,
+                       IpcSender};
+use std::thread::{self,
+                  JoinHandle};
+
+pub const FAKE_VERSION: u32 = 7;
+
+/// A stand-in for the Launcher that answers the handshake, then
+/// `commands` further commands, then drops its end of the connection.
+/// It stops early if the client hangs up.
+pub struct FakeLauncher {
+    pub pipe: String,
+    handle:   JoinHandle<()>,
+}
+
+impl FakeLauncher {
+    pub fn start(commands: usize) -> Self {
+        Self::speaking(protocol::PROTOCOL_VERSION,
+                       vec![protocol::capability::FRAMING],
+                       commands)
+    }
+
+    /// Start a fake Launcher that reports `protocol_version` and
+    /// `capabilities` in the handshake.
+    pub fn speaking(protocol_version: u32,
+                    capabilities: Vec<&'static str>,
+                    commands: usize)
+                    -> Self {
+        let capabilities = capabilities.into_iter()
+                                       .map(str::to_string)
+                                       .collect::<Vec<_>>();
+        let (server, pipe) = IpcOneShotServer::<Vec<u8>>::new().unwrap();
+        let handle = thread::spawn(move || {
+            let (rx, raw) = server.accept().unwrap();
+            let register = NetTxn::from_bytes(&raw).unwrap()
+                                                   .decode::<protocol::Register>()
+                                                   .unwrap();
+            let tx = IpcSender::connect(register.pipe).unwrap();
+            let mut framing = Framing::Unframed;
+            let mut subscribed = false;
+            send(&tx, framing, &protocol::NetOk::default());
+
+            let mut answered = 0;
+            while answered < commands {
+                let bytes = match rx.recv() {
+                    Ok(bytes) => bytes,
+                    Err(_) => break,
+                };
+                let txn = NetTxn::from_framed_bytes(&bytes, framing).unwrap();
+                match txn.message_id() {
+                    "Capabilities" => {
+                        let reply =
+                            protocol::CapabilityList { capabilities:     capabilities.clone(),
+                                                       max_message_size: None,
+                                                       protocol_version: Some(protocol_version), };
+                        send(&tx, framing, &reply);
+                    }
+                    "EnableFraming" => {
+                        send(&tx, framing, &protocol::NetOk::default());
+                        framing = Framing::LengthPrefixed;
+                    }
+                    "Version" => {
+                        // An exit pushed while the command was on its
+                        // way lands ahead of the reply
+                        if subscribed {
+                            send(&tx, framing, &exit(answered));
+                        }
+                        send(&tx,
+                             framing,
+                             &protocol::VersionNumber { version: FAKE_VERSION, });
+                        answered += 1;
+                    }
+                    "Ping" => {
+                        send(&tx, framing, &protocol::NetOk::default());
+                        answered += 1;
+                    }
+                    "HealthSummary" => {
+                        // The reply to an earlier command that timed
+                        // out, arriving late
+                        send(&tx, framing, &protocol::NetOk::default());
+                        send(&tx,
+                             framing,
+                             &protocol::HealthSummaryReport { running: 2,
+                                                              ..Default::default() });
+                        answered += 1;
+                    }
+                    "ListServices" => {
+                        send(&tx, framing, &protocol::ServiceList::default());
+                        answered += 1;
+                    }
+                    "SubscribeExits" => {
+                        send(&tx, framing, &protocol::NetOk::default());
+                        subscribed = true;
+                        answered += 1;
+                    }
+                    unknown => panic!("fake Launcher received unexpected {}", unknown),
+                }
+            }
+        });
+        FakeLauncher { pipe, handle }
+    }
+
+    /// Wait for the fake Launcher to drop its connection.
+    pub fn finish(self) { self.handle.join().unwrap() }
+}
+
+#[allow(dead_code)]
+pub fn exit(pid: usize) -> protocol::ProcessExited {
+    protocol::ProcessExited { service_name: String::from("redis.default"),
+                              pid:          pid as u32,
+                              exit_code:    Some(1),
+                              signal:       None,
+                              terminated:   false, }
+}
+
+fn send<T>(tx: &IpcSender<Vec<u8>>, framing: Framing, message: &T)
+    where T: LauncherMessage
+{
+    let bytes = NetTxn::build(message).unwrap()
+                                      .to_framed_bytes(framing)
+                                      .unwrap();
+    tx.send(bytes).unwrap();
+}
@@ -0,0 +1,87 @@
+//! Exercises losing the connection to the Launcher and reconnecting,
+//! against a fake Launcher that runs on a thread of the test process.
+
+mod common;
+
+use common::{FakeLauncher,
+             FAKE_VERSION};
+use habitat_launcher_client::{ConnectError,
+                              ConnectionState,
+                              LauncherCli,
+                              LauncherStatus};
+use habitat_launcher_protocol as protocol;
+
+#[test]
+fn commands_resume_after_reconnecting() {
+    let first = FakeLauncher::start(1);
+    let mut client = LauncherCli::connect(first.pipe.clone()).unwrap();
+    let mut state = client.subscribe_state();
+    assert!(client.active_capabilities().framing);
+    assert_eq!(client.version().unwrap(), FAKE_VERSION);
+    first.finish();
+
+    assert!(matches!(client.launcher_status(), LauncherStatus::Shutdown));
+    assert_eq!(client.state(), ConnectionState::Disconnected);
+    assert!(client.version().is_err());
+
+    let second = FakeLauncher::start(1);
+    client.reconnect(second.pipe.clone()).unwrap();
+    assert_eq!(client.state(), ConnectionState::Connected);
+    assert_eq!(*state.borrow_and_update(), ConnectionState::Connected);
+    assert_eq!(client.version().unwrap(), FAKE_VERSION);
+    second.finish();
+}
+
+#[test]
+fn reconnecting_renegotiates_with_a_replaced_launcher() {
+    let first = FakeLauncher::start(1);
+    let mut client = LauncherCli::connect(first.pipe.clone()).unwrap();
+    assert_eq!(client.protocol_version(), protocol::PROTOCOL_VERSION);
+    assert!(client.active_capabilities().framing);
+    assert_eq!(client.version().unwrap(), FAKE_VERSION);
+    first.finish();
+    assert!(matches!(client.launcher_status(), LauncherStatus::Shutdown));
+
+    // A Launcher speaking a newer protocol is refused, and the client
+    // keeps what it knew
+    let newer = protocol::PROTOCOL_VERSION + 1;
+    let incompatible = FakeLauncher::speaking(newer, vec![protocol::capability::FRAMING], 1);
+    match client.reconnect(incompatible.pipe.clone()) {
+        Err(ConnectError::VersionMismatch { launcher, client }) => {
+            assert_eq!(launcher, newer);
+            assert_eq!(client, protocol::PROTOCOL_VERSION);
+        }
+        other => panic!("expected a version mismatch, got {:?}", other),
+    }
+    incompatible.finish();
+    assert_eq!(client.state(), ConnectionState::Disconnected);
+    assert_eq!(client.protocol_version(), protocol::PROTOCOL_VERSION);
+
+    // A compatible Launcher with different capabilities replaces the
+    // previous negotiation
+    let compatible = FakeLauncher::speaking(protocol::PROTOCOL_VERSION, vec![], 1);
+    client.reconnect(compatible.pipe.clone()).unwrap();
+    assert_eq!(client.state(), ConnectionState::Connected);
+    assert!(!client.active_capabilities().framing);
+    assert_eq!(client.version().unwrap(), FAKE_VERSION);
+    compatible.finish();
+}
+
+#[test]
+fn latency_is_only_averaged_over_the_current_connection() {
+    let capabilities = vec![protocol::capability::FRAMING, protocol::capability::PING];
+    let first = FakeLauncher::speaking(protocol::PROTOCOL_VERSION, capabilities.clone(), 2);
+    let mut client = LauncherCli::connect(first.pipe.clone()).unwrap();
+    assert_eq!(client.average_latency(), None);
+    let latencies = [client.ping().unwrap(), client.ping().unwrap()];
+    assert_eq!(client.average_latency(),
+               Some((latencies[0] + latencies[1]) / 2));
+    first.finish();
+
+    let second = FakeLauncher::speaking(protocol::PROTOCOL_VERSION, capabilities, 1);
+    client.reconnect(second.pipe.clone()).unwrap();
+    assert_eq!(client.average_latency(), None);
+    let latency = client.ping().unwrap();
+    assert_eq!(client.summary().average_latency, Some(latency));
+    second.finish();
+}
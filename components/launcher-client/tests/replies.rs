@@ -0,0 +1,21 @@
+//! Exercises matching the Launcher's replies to the commands they
+//! answer.
+
+mod common;
+
+use common::FakeLauncher;
+use habitat_launcher_client::{ConnectionState,
+                              LauncherCli};
+use habitat_launcher_protocol as protocol;
+
+#[test]
+fn a_stale_reply_is_not_taken_for_the_next_one() {
+    let capabilities = vec![protocol::capability::FRAMING,
+                            protocol::capability::HEALTH_SUMMARY];
+    let launcher = FakeLauncher::speaking(protocol::PROTOCOL_VERSION, capabilities, 1);
+    let client = LauncherCli::connect(launcher.pipe.clone()).unwrap();
+    let report = client.health_summary().unwrap();
+    assert_eq!(report.running, 2);
+    assert_eq!(client.state(), ConnectionState::Connected);
+    launcher.finish();
+}
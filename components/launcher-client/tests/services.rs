@@ -0,0 +1,53 @@
+//! Exercises listing the Launcher's services and subscribing to their
+//! exits.
+
+mod common;
+
+use common::{exit,
+             FakeLauncher,
+             FAKE_VERSION};
+use habitat_launcher_client::{IPCCommandError,
+                              LauncherCli};
+use habitat_launcher_protocol as protocol;
+use tokio::sync::mpsc::error::TryRecvError;
+
+#[test]
+fn exits_arriving_ahead_of_a_reply_go_to_the_subscriber() {
+    let capabilities = vec![protocol::capability::FRAMING,
+                            protocol::capability::EXIT_NOTIFICATIONS];
+    let first = FakeLauncher::speaking(protocol::PROTOCOL_VERSION, capabilities.clone(), 3);
+    let mut client = LauncherCli::connect(first.pipe.clone()).unwrap();
+    let mut exits = client.subscribe_exits().unwrap();
+    assert_eq!(client.version().unwrap(), FAKE_VERSION);
+    assert_eq!(client.version().unwrap(), FAKE_VERSION);
+    assert_eq!(exits.try_recv().unwrap(), exit(1));
+    assert_eq!(exits.try_recv().unwrap(), exit(2));
+    assert!(exits.try_recv().is_err());
+    first.finish();
+
+    // The subscription doesn't outlive the connection
+    let second = FakeLauncher::speaking(protocol::PROTOCOL_VERSION, capabilities, 1);
+    client.reconnect(second.pipe.clone()).unwrap();
+    assert!(matches!(exits.try_recv(), Err(TryRecvError::Disconnected)));
+    assert_eq!(client.version().unwrap(), FAKE_VERSION);
+    second.finish();
+}
+
+#[test]
+fn an_empty_service_list_is_not_an_error() {
+    let capabilities = vec![protocol::capability::FRAMING,
+                            protocol::capability::LIST_SERVICES];
+    let launcher = FakeLauncher::speaking(protocol::PROTOCOL_VERSION, capabilities, 1);
+    let client = LauncherCli::connect(launcher.pipe.clone()).unwrap();
+    assert!(client.active_capabilities().list_services);
+    assert_eq!(client.list_services().unwrap(), Vec::new());
+    launcher.finish();
+
+    // A Launcher without the capability isn't asked
+    let older = FakeLauncher::start(1);
+    let client = LauncherCli::connect(older.pipe.clone()).unwrap();
+    assert!(matches!(client.list_services(),
+                     Err(IPCCommandError::Unsupported("list_services", _))));
+    assert_eq!(client.version().unwrap(), FAKE_VERSION);
+    older.finish();
+}
@@ -5,9 +5,20 @@ use std::{fmt,
 #[derive(Debug)]
 pub enum Error {
     Deserialize(prost::DecodeError),
+    /// A length-prefixed frame declared more bytes than the limit.
+    MessageTooLarge {
+        size: usize,
+        max:  usize,
+    },
     NetErr(NetErr),
     ProtocolMismatch(&'static str),
     Serialize(prost::EncodeError),
+    /// A length-prefixed frame held a different number of bytes than
+    /// it declared.
+    Truncated {
+        expected: usize,
+        actual:   usize,
+    },
 }
 
 pub type Result<T> = result::Result<T, Error>;
@@ -16,12 +27,20 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let msg = match *self {
             Error::Deserialize(ref e) => format!("Unable to deserialize message: {}", e),
+            Error::MessageTooLarge { size, max } => {
+                format!("Message of {} bytes exceeds the maximum of {} bytes",
+                        size, max)
+            }
             Error::NetErr(ref e) => format!("Net error: {}", e),
             Error::ProtocolMismatch(ref field) => {
                 format!("Received an unsupported or bad protocol message. Missing field: {}",
                         field)
             }
             Error::Serialize(ref e) => format!("Unable to serialize message: {}", e),
+            Error::Truncated { expected, actual } => {
+                format!("Message truncated: expected {} bytes, received {}",
+                        expected, actual)
+            }
         };
         write!(f, "{}", msg)
     }
@@ -15,6 +15,41 @@ pub const OK_NO_RETRY_EXCODE: i32 = 84;
 /// Same as `OK_NO_RETRY_EXCODE` except the Supervisor ran to completion with an unsuccessful
 /// exit code. The Launcher should exit immediately with a non-zero exit code.
 pub const ERR_NO_RETRY_EXCODE: i32 = 86;
+/// The version of the protocol this crate speaks, reported in
+/// `CapabilityList`. Additions that older peers can do without are
+/// negotiated through capabilities instead; this only changes when the
+/// two ends can no longer understand each other, so a client and a
+/// Launcher must speak the same version.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// The version of length-prefixed framing this crate speaks, sent in
+/// `EnableFraming`.
+pub const FRAMING_VERSION: u32 = 1;
+/// The largest envelope a length-prefixed frame may declare.
+pub const MAX_FRAME_SIZE: usize = 64 * 1024 * 1024;
+
+/// The size of the big-endian length that starts a length-prefixed
+/// frame.
+const LENGTH_PREFIX_SIZE: usize = 4;
+
+/// How a `NetTxn` is laid out in a single IPC message.
+///
+/// Every connection starts out `Unframed`. If the Launcher reports the
+/// `capability::FRAMING` capability, the client sends `EnableFraming`
+/// and, once the Launcher acknowledges it, both ends switch to
+/// `LengthPrefixed` for every later message. Either end that predates
+/// framing never negotiates it, so the connection stays `Unframed`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// The message is the encoded envelope and nothing else.
+    Unframed,
+    /// The message is the envelope's length as a big-endian `u32`,
+    /// followed by the envelope.
+    LengthPrefixed,
+}
+
+impl Default for Framing {
+    fn default() -> Self { Framing::Unframed }
+}
 
 #[derive(Debug)]
 pub struct NetTxn(Envelope);
@@ -35,6 +70,50 @@ impl NetTxn {
 
     pub fn to_bytes(&self) -> Result<Vec<u8>> { self.0.clone().to_bytes() }
 
+    /// Decode a transaction from an IPC message laid out as `framing`.
+    pub fn from_framed_bytes(bytes: &[u8], framing: Framing) -> Result<Self> {
+        match framing {
+            Framing::Unframed => Self::from_bytes(bytes),
+            Framing::LengthPrefixed => {
+                if bytes.len() < LENGTH_PREFIX_SIZE {
+                    return Err(Error::Truncated { expected: LENGTH_PREFIX_SIZE,
+                                                  actual:   bytes.len(), });
+                }
+                let (prefix, body) = bytes.split_at(LENGTH_PREFIX_SIZE);
+                let mut length = [0; LENGTH_PREFIX_SIZE];
+                length.copy_from_slice(prefix);
+                let expected = u32::from_be_bytes(length) as usize;
+                if expected > MAX_FRAME_SIZE {
+                    return Err(Error::MessageTooLarge { size: expected,
+                                                        max:  MAX_FRAME_SIZE, });
+                }
+                if body.len() != expected {
+                    return Err(Error::Truncated { expected,
+                                                  actual: body.len() });
+                }
+                Self::from_bytes(body)
+            }
+        }
+    }
+
+    /// Encode the transaction as an IPC message laid out as `framing`.
+    pub fn to_framed_bytes(&self, framing: Framing) -> Result<Vec<u8>> {
+        let body = self.to_bytes()?;
+        match framing {
+            Framing::Unframed => Ok(body),
+            Framing::LengthPrefixed => {
+                if body.len() > MAX_FRAME_SIZE {
+                    return Err(Error::MessageTooLarge { size: body.len(),
+                                                        max:  MAX_FRAME_SIZE, });
+                }
+                let mut bytes = Vec::with_capacity(LENGTH_PREFIX_SIZE + body.len());
+                bytes.extend_from_slice(&(body.len() as u32).to_be_bytes());
+                bytes.extend_from_slice(&body);
+                Ok(bytes)
+            }
+        }
+    }
+
     pub fn decode<T>(&self) -> Result<T>
         where T: LauncherMessage
     {
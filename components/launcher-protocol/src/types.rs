@@ -2,6 +2,7 @@ use crate::{error::{Error,
                     Result},
             generated};
 use prost::Message;
+use serde::Serialize;
 use std::{collections::BTreeMap,
           convert::TryFrom,
           fmt};
@@ -27,7 +28,11 @@ pub trait LauncherMessage
     }
 }
 
-pub use generated::{ErrCode,
+pub use generated::{DiagnosticKind,
+                    ErrCode,
+                    ReadinessState,
+                    RestartReason,
+                    SchedulingPolicy,
                     ShutdownMethod};
 
 // Now we're going to define our own set of structs to use internally, as well as conversion
@@ -126,14 +131,25 @@ impl From<Restart> for generated::Restart {
 
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Spawn {
-    pub id:           String,
-    pub binary:       String,
-    pub svc_user:     Option<String>,
-    pub svc_group:    Option<String>,
-    pub svc_password: Option<String>,
-    pub env:          BTreeMap<String, String>,
-    pub svc_user_id:  Option<u32>,
-    pub svc_group_id: Option<u32>,
+    pub id:                   String,
+    pub binary:               String,
+    pub svc_user:             Option<String>,
+    pub svc_group:            Option<String>,
+    pub svc_password:         Option<String>,
+    pub env:                  BTreeMap<String, String>,
+    pub svc_user_id:          Option<u32>,
+    pub svc_group_id:         Option<u32>,
+    pub cpu_affinity:         Vec<u32>,
+    pub args:                 Vec<String>,
+    pub cwd:                  Option<String>,
+    pub readiness:            Option<ReadinessProbe>,
+    pub key:                  Option<String>,
+    pub cgroup:               Option<CgroupLimits>,
+    pub scheduling:           Option<Scheduling>,
+    /// How long, in milliseconds, to watch the process for exiting
+    /// straight away.
+    pub early_exit_window_ms: Option<u32>,
+    pub privileges:           Option<Privileges>,
 }
 
 impl LauncherMessage for Spawn {
@@ -142,33 +158,159 @@ impl LauncherMessage for Spawn {
     const MESSAGE_ID: &'static str = "Spawn";
 
     fn from_proto(proto: generated::Spawn) -> Result<Self> {
-        Ok(Spawn { id:           proto.id.ok_or(Error::ProtocolMismatch("id"))?,
-                   binary:       proto.binary.ok_or(Error::ProtocolMismatch("binary"))?,
-                   svc_user:     proto.svc_user,
-                   svc_group:    proto.svc_group,
-                   svc_password: proto.svc_password,
-                   env:          proto.env.into_iter().collect(),
-                   svc_user_id:  proto.svc_user_id,
-                   svc_group_id: proto.svc_group_id, })
+        Ok(Spawn { id:                   proto.id.ok_or(Error::ProtocolMismatch("id"))?,
+                   binary:               proto.binary.ok_or(Error::ProtocolMismatch("binary"))?,
+                   svc_user:             proto.svc_user,
+                   svc_group:            proto.svc_group,
+                   svc_password:         proto.svc_password,
+                   env:                  proto.env.into_iter().collect(),
+                   svc_user_id:          proto.svc_user_id,
+                   svc_group_id:         proto.svc_group_id,
+                   cpu_affinity:         proto.cpu_affinity,
+                   args:                 proto.args,
+                   cwd:                  proto.cwd,
+                   readiness:            proto.readiness.map(Into::into),
+                   key:                  proto.key,
+                   cgroup:               proto.cgroup.map(Into::into),
+                   scheduling:           proto.scheduling.map(Scheduling::from_proto).transpose()?,
+                   early_exit_window_ms: proto.early_exit_window_ms,
+                   privileges:           proto.privileges.map(Into::into), })
     }
 }
 
 impl From<Spawn> for generated::Spawn {
     fn from(value: Spawn) -> Self {
-        generated::Spawn { id:           Some(value.id),
-                           binary:       Some(value.binary),
-                           svc_user:     value.svc_user,
-                           svc_group:    value.svc_group,
-                           svc_password: value.svc_password,
-                           env:          value.env.into_iter().collect(),
-                           svc_user_id:  value.svc_user_id,
-                           svc_group_id: value.svc_group_id, }
+        generated::Spawn { id:                   Some(value.id),
+                           binary:               Some(value.binary),
+                           svc_user:             value.svc_user,
+                           svc_group:            value.svc_group,
+                           svc_password:         value.svc_password,
+                           env:                  value.env.into_iter().collect(),
+                           svc_user_id:          value.svc_user_id,
+                           svc_group_id:         value.svc_group_id,
+                           cpu_affinity:         value.cpu_affinity,
+                           args:                 value.args,
+                           cwd:                  value.cwd,
+                           readiness:            value.readiness.map(Into::into),
+                           key:                  value.key,
+                           cgroup:               value.cgroup.map(Into::into),
+                           scheduling:           value.scheduling.map(Into::into),
+                           early_exit_window_ms: value.early_exit_window_ms,
+                           privileges:           value.privileges.map(Into::into), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReadinessProbe {
+    /// The process is ready once this path exists.
+    pub file:         Option<String>,
+    /// The process is ready once this command exits successfully.
+    pub command:      Vec<String>,
+    pub timeout_secs: Option<u32>,
+    pub interval_ms:  Option<u32>,
+}
+
+impl From<generated::ReadinessProbe> for ReadinessProbe {
+    fn from(proto: generated::ReadinessProbe) -> Self {
+        ReadinessProbe { file:         proto.file,
+                         command:      proto.command,
+                         timeout_secs: proto.timeout_secs,
+                         interval_ms:  proto.interval_ms, }
+    }
+}
+
+impl From<ReadinessProbe> for generated::ReadinessProbe {
+    fn from(value: ReadinessProbe) -> Self {
+        generated::ReadinessProbe { file:         value.file,
+                                    command:      value.command,
+                                    timeout_secs: value.timeout_secs,
+                                    interval_ms:  value.interval_ms, }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CgroupLimits {
+    /// The most memory the process may use, in bytes.
+    pub memory_max:    Option<u64>,
+    /// The CPU time the process may use in every `cpu_period_us`.
+    pub cpu_quota_us:  Option<u64>,
+    pub cpu_period_us: Option<u64>,
+}
+
+impl From<generated::CgroupLimits> for CgroupLimits {
+    fn from(proto: generated::CgroupLimits) -> Self {
+        CgroupLimits { memory_max:    proto.memory_max,
+                       cpu_quota_us:  proto.cpu_quota_us,
+                       cpu_period_us: proto.cpu_period_us, }
+    }
+}
+
+impl From<CgroupLimits> for generated::CgroupLimits {
+    fn from(value: CgroupLimits) -> Self {
+        generated::CgroupLimits { memory_max:    value.memory_max,
+                                  cpu_quota_us:  value.cpu_quota_us,
+                                  cpu_period_us: value.cpu_period_us, }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Privileges {
+    pub supplementary_groups: Vec<u32>,
+    /// Capability names, e.g. "CAP_NET_BIND_SERVICE".
+    pub grant_capabilities:   Vec<String>,
+    pub drop_capabilities:    Vec<String>,
+}
+
+impl From<generated::Privileges> for Privileges {
+    fn from(proto: generated::Privileges) -> Self {
+        Privileges { supplementary_groups: proto.supplementary_groups,
+                     grant_capabilities:   proto.grant_capabilities,
+                     drop_capabilities:    proto.drop_capabilities, }
+    }
+}
+
+impl From<Privileges> for generated::Privileges {
+    fn from(value: Privileges) -> Self {
+        generated::Privileges { supplementary_groups: value.supplementary_groups,
+                                grant_capabilities:   value.grant_capabilities,
+                                drop_capabilities:    value.drop_capabilities, }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Scheduling {
+    pub policy:   generated::SchedulingPolicy,
+    /// The `SchedulingPolicy::Fifo` priority, from 1 to 99.
+    pub priority: u32,
+    /// The `SchedulingPolicy::Other` nice value, from -20 to 19.
+    pub nice:     i32,
+}
+
+impl Scheduling {
+    fn from_proto(proto: generated::Scheduling) -> Result<Self> {
+        let policy = proto.policy.unwrap_or_default();
+        Ok(Scheduling { policy:   generated::SchedulingPolicy::try_from(policy)
+                                      .or(Err(Error::ProtocolMismatch("policy")))?,
+                        priority: proto.priority.unwrap_or_default(),
+                        nice:     proto.nice.unwrap_or_default(), })
+    }
+}
+
+impl From<Scheduling> for generated::Scheduling {
+    fn from(value: Scheduling) -> Self {
+        generated::Scheduling { policy:   Some(value.policy as i32),
+                                priority: Some(value.priority),
+                                nice:     Some(value.nice), }
     }
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct SpawnOk {
-    pub pid: i64,
+    pub pid:             i64,
+    /// `pid` was already running for the request's key.
+    pub duplicate_spawn: bool,
+    /// The cgroup the process runs in, if it was given limits.
+    pub cgroup_path:     Option<String>,
 }
 
 impl LauncherMessage for SpawnOk {
@@ -177,12 +319,18 @@ impl LauncherMessage for SpawnOk {
     const MESSAGE_ID: &'static str = "SpawnOk";
 
     fn from_proto(proto: generated::SpawnOk) -> Result<Self> {
-        Ok(SpawnOk { pid: proto.pid.ok_or(Error::ProtocolMismatch("pid"))?, })
+        Ok(SpawnOk { pid:             proto.pid.ok_or(Error::ProtocolMismatch("pid"))?,
+                     duplicate_spawn: proto.duplicate_spawn.unwrap_or_default(),
+                     cgroup_path:     proto.cgroup_path, })
     }
 }
 
 impl From<SpawnOk> for generated::SpawnOk {
-    fn from(value: SpawnOk) -> Self { generated::SpawnOk { pid: Some(value.pid), } }
+    fn from(value: SpawnOk) -> Self {
+        generated::SpawnOk { pid:             Some(value.pid),
+                             duplicate_spawn: Some(value.duplicate_spawn),
+                             cgroup_path:     value.cgroup_path, }
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -356,3 +504,1044 @@ impl LauncherMessage for Version {
 impl From<Version> for generated::Version {
     fn from(_value: Version) -> Self { generated::Version {} }
 }
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FdCounts {}
+
+impl LauncherMessage for FdCounts {
+    type Generated = generated::FdCounts;
+
+    const MESSAGE_ID: &'static str = "FdCounts";
+
+    fn from_proto(_proto: generated::FdCounts) -> Result<Self> { Ok(FdCounts {}) }
+}
+
+impl From<FdCounts> for generated::FdCounts {
+    fn from(_value: FdCounts) -> Self { generated::FdCounts {} }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FdCount {
+    pub service_name: String,
+    pub pid:          u32,
+    /// `None` where the platform doesn't expose the count.
+    pub count:        Option<u32>,
+}
+
+impl FdCount {
+    fn from_proto(proto: generated::FdCount) -> Result<Self> {
+        Ok(FdCount { service_name: proto.service_name
+                                        .ok_or(Error::ProtocolMismatch("service_name"))?,
+                     pid:          proto.pid.ok_or(Error::ProtocolMismatch("pid"))?,
+                     count:        proto.count, })
+    }
+}
+
+impl From<FdCount> for generated::FdCount {
+    fn from(value: FdCount) -> Self {
+        generated::FdCount { service_name: Some(value.service_name),
+                             pid:          Some(value.pid),
+                             count:        value.count, }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FdCountReport {
+    pub counts: Vec<FdCount>,
+}
+
+impl LauncherMessage for FdCountReport {
+    type Generated = generated::FdCountReport;
+
+    const MESSAGE_ID: &'static str = "FdCountReport";
+
+    fn from_proto(proto: generated::FdCountReport) -> Result<Self> {
+        let counts = proto.counts
+                          .into_iter()
+                          .map(FdCount::from_proto)
+                          .collect::<Result<_>>()?;
+        Ok(FdCountReport { counts })
+    }
+}
+
+impl From<FdCountReport> for generated::FdCountReport {
+    fn from(value: FdCountReport) -> Self {
+        generated::FdCountReport { counts: value.counts.into_iter().map(Into::into).collect(), }
+    }
+}
+
+/// Names of the optional features a Launcher can report in a
+/// `CapabilityList`.
+pub mod capability {
+    /// The Launcher applies `Spawn::cpu_affinity` to spawned processes.
+    pub const CPU_AFFINITY: &str = "cpu_affinity";
+    /// The Launcher honors `Spawn::args` and `Spawn::cwd`.
+    pub const SPAWN_ARGS: &str = "spawn_args";
+    /// The Launcher handles `RestartWith`.
+    pub const RESTART_WITH: &str = "restart_with";
+    /// The Launcher handles `EnableFraming`.
+    pub const FRAMING: &str = "framing";
+    /// The Launcher handles `Diagnostics`.
+    pub const DIAGNOSTICS: &str = "diagnostics";
+    /// The Launcher honors `Spawn::readiness` and handles `ReadinessOf`.
+    pub const READINESS_PROBE: &str = "readiness_probe";
+    /// The Launcher deduplicates spawns by `Spawn::key`.
+    pub const SPAWN_KEY: &str = "spawn_key";
+    /// The Launcher handles `RestartHistory`.
+    pub const RESTART_HISTORY: &str = "restart_history";
+    /// The Launcher applies `Spawn::cgroup` to spawned processes.
+    pub const CGROUP: &str = "cgroup";
+    /// The Launcher handles `Preload`.
+    pub const PRELOAD: &str = "preload";
+    /// The Launcher handles `ValidatePid`.
+    pub const VALIDATE_PID: &str = "validate_pid";
+    /// The Launcher handles `HealthSummary`.
+    pub const HEALTH_SUMMARY: &str = "health_summary";
+    /// The Launcher applies `Spawn::scheduling` to spawned processes and
+    /// handles `SchedulingOf` and `SetScheduling`.
+    pub const SCHEDULING: &str = "scheduling";
+    /// The Launcher honors `Spawn::early_exit_window_ms`.
+    pub const EARLY_EXIT: &str = "early_exit";
+    /// The Launcher handles `Ping`.
+    pub const PING: &str = "ping";
+    /// The Launcher handles `SubscribeExits`, pushing `ProcessExited`
+    /// once subscribed.
+    pub const EXIT_NOTIFICATIONS: &str = "exit_notifications";
+    /// The Launcher handles `ResourceUsage`.
+    pub const RESOURCE_USAGE: &str = "resource_usage";
+    /// The Launcher applies `Spawn::privileges` to spawned processes.
+    pub const PRIVILEGES: &str = "privileges";
+    /// The Launcher handles `ListServices`.
+    pub const LIST_SERVICES: &str = "list_services";
+    /// The Launcher handles `FdCounts`.
+    pub const FD_COUNTS: &str = "fd_counts";
+    /// The Launcher handles `ProcessUptimes`.
+    pub const UPTIMES: &str = "uptimes";
+    /// The Launcher handles `LauncherConfig`.
+    pub const CONFIG: &str = "config";
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnableFraming {
+    pub version: u32,
+}
+
+impl LauncherMessage for EnableFraming {
+    type Generated = generated::EnableFraming;
+
+    const MESSAGE_ID: &'static str = "EnableFraming";
+
+    fn from_proto(proto: generated::EnableFraming) -> Result<Self> {
+        Ok(EnableFraming { version: proto.version.ok_or(Error::ProtocolMismatch("version"))?, })
+    }
+}
+
+impl From<EnableFraming> for generated::EnableFraming {
+    fn from(value: EnableFraming) -> Self {
+        generated::EnableFraming { version: Some(value.version), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Capabilities {}
+
+impl LauncherMessage for Capabilities {
+    type Generated = generated::Capabilities;
+
+    const MESSAGE_ID: &'static str = "Capabilities";
+
+    fn from_proto(_proto: generated::Capabilities) -> Result<Self> { Ok(Capabilities {}) }
+}
+
+impl From<Capabilities> for generated::Capabilities {
+    fn from(_value: Capabilities) -> Self { generated::Capabilities {} }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CapabilityList {
+    pub capabilities:     Vec<String>,
+    pub max_message_size: Option<u64>,
+    pub protocol_version: Option<u32>,
+}
+
+impl LauncherMessage for CapabilityList {
+    type Generated = generated::CapabilityList;
+
+    const MESSAGE_ID: &'static str = "CapabilityList";
+
+    fn from_proto(proto: generated::CapabilityList) -> Result<Self> {
+        Ok(CapabilityList { capabilities:     proto.capabilities,
+                            max_message_size: proto.max_message_size,
+                            protocol_version: proto.protocol_version, })
+    }
+}
+
+impl From<CapabilityList> for generated::CapabilityList {
+    fn from(value: CapabilityList) -> Self {
+        generated::CapabilityList { capabilities:     value.capabilities,
+                                    max_message_size: value.max_message_size,
+                                    protocol_version: value.protocol_version, }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LauncherConfig {}
+
+impl LauncherMessage for LauncherConfig {
+    type Generated = generated::LauncherConfig;
+
+    const MESSAGE_ID: &'static str = "LauncherConfig";
+
+    fn from_proto(_proto: generated::LauncherConfig) -> Result<Self> { Ok(LauncherConfig {}) }
+}
+
+impl From<LauncherConfig> for generated::LauncherConfig {
+    fn from(_value: LauncherConfig) -> Self { generated::LauncherConfig {} }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LauncherConfigIs {
+    pub schema_version:           u32,
+    pub version:                  Option<String>,
+    pub fs_root:                  String,
+    pub launcher_root:            String,
+    pub pid_file:                 String,
+    pub sup_binary:               Option<String>,
+    pub sup_version_check:        bool,
+    pub sup_version_req:          String,
+    pub ipc_connect_timeout_secs: u64,
+    pub capabilities:             Vec<String>,
+}
+
+impl LauncherConfigIs {
+    /// The `schema_version` of the fields defined here.
+    pub const SCHEMA_VERSION: u32 = 1;
+}
+
+impl LauncherMessage for LauncherConfigIs {
+    type Generated = generated::LauncherConfigIs;
+
+    const MESSAGE_ID: &'static str = "LauncherConfigIs";
+
+    fn from_proto(proto: generated::LauncherConfigIs) -> Result<Self> {
+        Ok(LauncherConfigIs { schema_version:
+                                  proto.schema_version
+                                       .ok_or(Error::ProtocolMismatch("schema_version"))?,
+                              version:                  proto.version,
+                              fs_root:
+                                  proto.fs_root.ok_or(Error::ProtocolMismatch("fs_root"))?,
+                              launcher_root:
+                                  proto.launcher_root
+                                       .ok_or(Error::ProtocolMismatch("launcher_root"))?,
+                              pid_file:
+                                  proto.pid_file.ok_or(Error::ProtocolMismatch("pid_file"))?,
+                              sup_binary:               proto.sup_binary,
+                              sup_version_check:
+                                  proto.sup_version_check
+                                       .ok_or(Error::ProtocolMismatch("sup_version_check"))?,
+                              sup_version_req:
+                                  proto.sup_version_req
+                                       .ok_or(Error::ProtocolMismatch("sup_version_req"))?,
+                              ipc_connect_timeout_secs:
+                                  proto.ipc_connect_timeout_secs
+                                       .ok_or(Error::ProtocolMismatch("ipc_connect_timeout_secs"))?,
+                              capabilities:             proto.capabilities, })
+    }
+}
+
+impl From<LauncherConfigIs> for generated::LauncherConfigIs {
+    fn from(value: LauncherConfigIs) -> Self {
+        generated::LauncherConfigIs { schema_version:           Some(value.schema_version),
+                                      version:                  value.version,
+                                      fs_root:                  Some(value.fs_root),
+                                      launcher_root:            Some(value.launcher_root),
+                                      pid_file:                 Some(value.pid_file),
+                                      sup_binary:               value.sup_binary,
+                                      sup_version_check:        Some(value.sup_version_check),
+                                      sup_version_req:          Some(value.sup_version_req),
+                                      ipc_connect_timeout_secs:
+                                          Some(value.ipc_connect_timeout_secs),
+                                      capabilities:             value.capabilities, }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProcessUptimes {}
+
+impl LauncherMessage for ProcessUptimes {
+    type Generated = generated::ProcessUptimes;
+
+    const MESSAGE_ID: &'static str = "ProcessUptimes";
+
+    fn from_proto(_proto: generated::ProcessUptimes) -> Result<Self> { Ok(ProcessUptimes {}) }
+}
+
+impl From<ProcessUptimes> for generated::ProcessUptimes {
+    fn from(_value: ProcessUptimes) -> Self { generated::ProcessUptimes {} }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ProcessUptime {
+    pub service_name: String,
+    pub pid:          u32,
+    /// Seconds since the Unix epoch.
+    pub started_at:   u64,
+    pub uptime_secs:  u64,
+    pub restarts:     u32,
+}
+
+impl ProcessUptime {
+    fn from_proto(proto: generated::ProcessUptime) -> Result<Self> {
+        Ok(ProcessUptime { service_name: proto.service_name
+                                              .ok_or(Error::ProtocolMismatch("service_name"))?,
+                           pid:          proto.pid.ok_or(Error::ProtocolMismatch("pid"))?,
+                           started_at:   proto.started_at
+                                              .ok_or(Error::ProtocolMismatch("started_at"))?,
+                           uptime_secs:  proto.uptime_secs
+                                              .ok_or(Error::ProtocolMismatch("uptime_secs"))?,
+                           restarts:     proto.restarts
+                                              .ok_or(Error::ProtocolMismatch("restarts"))?, })
+    }
+}
+
+impl From<ProcessUptime> for generated::ProcessUptime {
+    fn from(value: ProcessUptime) -> Self {
+        generated::ProcessUptime { service_name: Some(value.service_name),
+                                   pid:          Some(value.pid),
+                                   started_at:   Some(value.started_at),
+                                   uptime_secs:  Some(value.uptime_secs),
+                                   restarts:     Some(value.restarts), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProcessUptimeReport {
+    pub uptimes: Vec<ProcessUptime>,
+}
+
+impl LauncherMessage for ProcessUptimeReport {
+    type Generated = generated::ProcessUptimeReport;
+
+    const MESSAGE_ID: &'static str = "ProcessUptimeReport";
+
+    fn from_proto(proto: generated::ProcessUptimeReport) -> Result<Self> {
+        let uptimes = proto.uptimes
+                           .into_iter()
+                           .map(ProcessUptime::from_proto)
+                           .collect::<Result<_>>()?;
+        Ok(ProcessUptimeReport { uptimes })
+    }
+}
+
+impl From<ProcessUptimeReport> for generated::ProcessUptimeReport {
+    fn from(value: ProcessUptimeReport) -> Self {
+        generated::ProcessUptimeReport { uptimes: value.uptimes
+                                                       .into_iter()
+                                                       .map(Into::into)
+                                                       .collect(), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RestartWith {
+    pub pid:   i64,
+    pub spawn: Spawn,
+}
+
+impl LauncherMessage for RestartWith {
+    type Generated = generated::RestartWith;
+
+    const MESSAGE_ID: &'static str = "RestartWith";
+
+    fn from_proto(proto: generated::RestartWith) -> Result<Self> {
+        let spawn = proto.spawn.ok_or(Error::ProtocolMismatch("spawn"))?;
+        Ok(RestartWith { pid:   proto.pid.ok_or(Error::ProtocolMismatch("pid"))?,
+                         spawn: Spawn::from_proto(spawn)?, })
+    }
+}
+
+impl From<RestartWith> for generated::RestartWith {
+    fn from(value: RestartWith) -> Self {
+        generated::RestartWith { pid:   Some(value.pid),
+                                 spawn: Some(value.spawn.into()), }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RestartWithResult {
+    /// The new process started with this PID.
+    Restarted(i64),
+    /// The old process stopped, but the new one failed to start.
+    StartFailed(NetErr),
+}
+
+impl LauncherMessage for RestartWithResult {
+    type Generated = generated::RestartWithResult;
+
+    const MESSAGE_ID: &'static str = "RestartWithResult";
+
+    fn from_proto(proto: generated::RestartWithResult) -> Result<Self> {
+        match (proto.pid, proto.error_code) {
+            (Some(pid), _) => Ok(RestartWithResult::Restarted(pid)),
+            (None, Some(code)) => {
+                let code =
+                    generated::ErrCode::try_from(code).unwrap_or(generated::ErrCode::Unknown);
+                Ok(RestartWithResult::StartFailed(NetErr { code,
+                                                           msg:
+                                                               proto.error_msg.unwrap_or_default() }))
+            }
+            (None, None) => Err(Error::ProtocolMismatch("pid")),
+        }
+    }
+}
+
+impl From<RestartWithResult> for generated::RestartWithResult {
+    fn from(value: RestartWithResult) -> Self {
+        match value {
+            RestartWithResult::Restarted(pid) => {
+                generated::RestartWithResult { pid: Some(pid),
+                                               ..Default::default() }
+            }
+            RestartWithResult::StartFailed(err) => {
+                generated::RestartWithResult { pid:        None,
+                                               error_code: Some(err.code as i32),
+                                               error_msg:  Some(err.msg), }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Diagnostics {
+    pub clear: bool,
+}
+
+impl LauncherMessage for Diagnostics {
+    type Generated = generated::Diagnostics;
+
+    const MESSAGE_ID: &'static str = "Diagnostics";
+
+    fn from_proto(proto: generated::Diagnostics) -> Result<Self> {
+        Ok(Diagnostics { clear: proto.clear.unwrap_or(false), })
+    }
+}
+
+impl From<Diagnostics> for generated::Diagnostics {
+    fn from(value: Diagnostics) -> Self { generated::Diagnostics { clear: Some(value.clear), } }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub kind:         generated::DiagnosticKind,
+    pub service_name: String,
+    /// Seconds since the Unix epoch.
+    pub at:           u64,
+    pub msg:          String,
+}
+
+impl Diagnostic {
+    fn from_proto(proto: generated::Diagnostic) -> Result<Self> {
+        let kind = proto.kind.ok_or(Error::ProtocolMismatch("kind"))?;
+        Ok(Diagnostic { kind:         generated::DiagnosticKind::try_from(kind)
+                                          .or(Err(Error::ProtocolMismatch("kind")))?,
+                        service_name: proto.service_name
+                                           .ok_or(Error::ProtocolMismatch("service_name"))?,
+                        at:           proto.at.ok_or(Error::ProtocolMismatch("at"))?,
+                        msg:          proto.msg.unwrap_or_default(), })
+    }
+}
+
+impl From<Diagnostic> for generated::Diagnostic {
+    fn from(value: Diagnostic) -> Self {
+        generated::Diagnostic { kind:         Some(value.kind as i32),
+                                service_name: Some(value.service_name),
+                                at:           Some(value.at),
+                                msg:          Some(value.msg), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiagnosticReport {
+    pub diagnostics: Vec<Diagnostic>,
+    pub dropped:     u64,
+}
+
+impl LauncherMessage for DiagnosticReport {
+    type Generated = generated::DiagnosticReport;
+
+    const MESSAGE_ID: &'static str = "DiagnosticReport";
+
+    fn from_proto(proto: generated::DiagnosticReport) -> Result<Self> {
+        let diagnostics = proto.diagnostics
+                               .into_iter()
+                               .map(Diagnostic::from_proto)
+                               .collect::<Result<_>>()?;
+        Ok(DiagnosticReport { diagnostics,
+                              dropped: proto.dropped.unwrap_or_default() })
+    }
+}
+
+impl From<DiagnosticReport> for generated::DiagnosticReport {
+    fn from(value: DiagnosticReport) -> Self {
+        generated::DiagnosticReport { diagnostics: value.diagnostics
+                                                        .into_iter()
+                                                        .map(Into::into)
+                                                        .collect(),
+                                      dropped:     Some(value.dropped), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReadinessOf {
+    pub pid: i64,
+}
+
+impl LauncherMessage for ReadinessOf {
+    type Generated = generated::ReadinessOf;
+
+    const MESSAGE_ID: &'static str = "ReadinessOf";
+
+    fn from_proto(proto: generated::ReadinessOf) -> Result<Self> {
+        Ok(ReadinessOf { pid: proto.pid.ok_or(Error::ProtocolMismatch("pid"))?, })
+    }
+}
+
+impl From<ReadinessOf> for generated::ReadinessOf {
+    fn from(value: ReadinessOf) -> Self { generated::ReadinessOf { pid: Some(value.pid), } }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReadinessIs {
+    pub readiness: generated::ReadinessState,
+}
+
+impl LauncherMessage for ReadinessIs {
+    type Generated = generated::ReadinessIs;
+
+    const MESSAGE_ID: &'static str = "ReadinessIs";
+
+    fn from_proto(proto: generated::ReadinessIs) -> Result<Self> {
+        let readiness = proto.readiness
+                             .ok_or(Error::ProtocolMismatch("readiness"))?;
+        Ok(ReadinessIs { readiness: generated::ReadinessState::try_from(readiness)
+                                        .or(Err(Error::ProtocolMismatch("readiness")))?, })
+    }
+}
+
+impl From<ReadinessIs> for generated::ReadinessIs {
+    fn from(value: ReadinessIs) -> Self {
+        generated::ReadinessIs { readiness: Some(value.readiness as i32), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RestartHistory {}
+
+impl LauncherMessage for RestartHistory {
+    type Generated = generated::RestartHistory;
+
+    const MESSAGE_ID: &'static str = "RestartHistory";
+
+    fn from_proto(_proto: generated::RestartHistory) -> Result<Self> { Ok(RestartHistory {}) }
+}
+
+impl From<RestartHistory> for generated::RestartHistory {
+    fn from(_value: RestartHistory) -> Self { generated::RestartHistory {} }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RestartEvent {
+    /// Seconds since the Unix epoch.
+    pub at:           u64,
+    pub reason:       generated::RestartReason,
+    pub previous_pid: u32,
+    /// `None` if the replaced process was ended by a signal.
+    pub exit_code:    Option<i32>,
+}
+
+impl RestartEvent {
+    fn from_proto(proto: generated::RestartEvent) -> Result<Self> {
+        let reason = proto.reason.ok_or(Error::ProtocolMismatch("reason"))?;
+        Ok(RestartEvent { at:           proto.at.ok_or(Error::ProtocolMismatch("at"))?,
+                          reason:       generated::RestartReason::try_from(reason)
+                                            .or(Err(Error::ProtocolMismatch("reason")))?,
+                          previous_pid: proto.previous_pid
+                                             .ok_or(Error::ProtocolMismatch("previous_pid"))?,
+                          exit_code:    proto.exit_code, })
+    }
+}
+
+impl From<RestartEvent> for generated::RestartEvent {
+    fn from(value: RestartEvent) -> Self {
+        generated::RestartEvent { at:           Some(value.at),
+                                  reason:       Some(value.reason as i32),
+                                  previous_pid: Some(value.previous_pid),
+                                  exit_code:    value.exit_code, }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcessRestarts {
+    pub service_name: String,
+    pub pid:          u32,
+    pub restarts:     u32,
+    /// Oldest first.
+    pub recent:       Vec<RestartEvent>,
+}
+
+impl ProcessRestarts {
+    fn from_proto(proto: generated::ProcessRestarts) -> Result<Self> {
+        let recent = proto.recent
+                          .into_iter()
+                          .map(RestartEvent::from_proto)
+                          .collect::<Result<_>>()?;
+        Ok(ProcessRestarts { service_name: proto.service_name
+                                                .ok_or(Error::ProtocolMismatch("service_name"))?,
+                             pid: proto.pid.ok_or(Error::ProtocolMismatch("pid"))?,
+                             restarts: proto.restarts.unwrap_or_default(),
+                             recent })
+    }
+}
+
+impl From<ProcessRestarts> for generated::ProcessRestarts {
+    fn from(value: ProcessRestarts) -> Self {
+        generated::ProcessRestarts { service_name: Some(value.service_name),
+                                     pid:          Some(value.pid),
+                                     restarts:     Some(value.restarts),
+                                     recent:       value.recent
+                                                        .into_iter()
+                                                        .map(Into::into)
+                                                        .collect(), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RestartHistoryReport {
+    pub processes: Vec<ProcessRestarts>,
+}
+
+impl LauncherMessage for RestartHistoryReport {
+    type Generated = generated::RestartHistoryReport;
+
+    const MESSAGE_ID: &'static str = "RestartHistoryReport";
+
+    fn from_proto(proto: generated::RestartHistoryReport) -> Result<Self> {
+        let processes = proto.processes
+                             .into_iter()
+                             .map(ProcessRestarts::from_proto)
+                             .collect::<Result<_>>()?;
+        Ok(RestartHistoryReport { processes })
+    }
+}
+
+impl From<RestartHistoryReport> for generated::RestartHistoryReport {
+    fn from(value: RestartHistoryReport) -> Self {
+        generated::RestartHistoryReport { processes: value.processes
+                                                          .into_iter()
+                                                          .map(Into::into)
+                                                          .collect(), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Preload {
+    pub binary: String,
+    pub paths:  Vec<String>,
+}
+
+impl LauncherMessage for Preload {
+    type Generated = generated::Preload;
+
+    const MESSAGE_ID: &'static str = "Preload";
+
+    fn from_proto(proto: generated::Preload) -> Result<Self> {
+        Ok(Preload { binary: proto.binary.ok_or(Error::ProtocolMismatch("binary"))?,
+                     paths:  proto.paths, })
+    }
+}
+
+impl From<Preload> for generated::Preload {
+    fn from(value: Preload) -> Self {
+        generated::Preload { binary: Some(value.binary),
+                             paths:  value.paths, }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PreloadOk {
+    pub files: u32,
+    pub bytes: u64,
+}
+
+impl LauncherMessage for PreloadOk {
+    type Generated = generated::PreloadOk;
+
+    const MESSAGE_ID: &'static str = "PreloadOk";
+
+    fn from_proto(proto: generated::PreloadOk) -> Result<Self> {
+        Ok(PreloadOk { files: proto.files.unwrap_or_default(),
+                       bytes: proto.bytes.unwrap_or_default(), })
+    }
+}
+
+impl From<PreloadOk> for generated::PreloadOk {
+    fn from(value: PreloadOk) -> Self {
+        generated::PreloadOk { files: Some(value.files),
+                               bytes: Some(value.bytes), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidatePid {
+    pub pid:          i64,
+    pub service_name: String,
+    /// Seconds since the Unix epoch.
+    pub started_at:   Option<u64>,
+}
+
+impl LauncherMessage for ValidatePid {
+    type Generated = generated::ValidatePid;
+
+    const MESSAGE_ID: &'static str = "ValidatePid";
+
+    fn from_proto(proto: generated::ValidatePid) -> Result<Self> {
+        Ok(ValidatePid { pid:          proto.pid.ok_or(Error::ProtocolMismatch("pid"))?,
+                         service_name: proto.service_name
+                                            .ok_or(Error::ProtocolMismatch("service_name"))?,
+                         started_at:   proto.started_at, })
+    }
+}
+
+impl From<ValidatePid> for generated::ValidatePid {
+    fn from(value: ValidatePid) -> Self {
+        generated::ValidatePid { pid:          Some(value.pid),
+                                 service_name: Some(value.service_name),
+                                 started_at:   value.started_at, }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PidValidity {
+    pub valid: bool,
+}
+
+impl LauncherMessage for PidValidity {
+    type Generated = generated::PidValidity;
+
+    const MESSAGE_ID: &'static str = "PidValidity";
+
+    fn from_proto(proto: generated::PidValidity) -> Result<Self> {
+        Ok(PidValidity { valid: proto.valid.unwrap_or_default(), })
+    }
+}
+
+impl From<PidValidity> for generated::PidValidity {
+    fn from(value: PidValidity) -> Self { generated::PidValidity { valid: Some(value.valid), } }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HealthSummary {}
+
+impl LauncherMessage for HealthSummary {
+    type Generated = generated::HealthSummary;
+
+    const MESSAGE_ID: &'static str = "HealthSummary";
+
+    fn from_proto(_proto: generated::HealthSummary) -> Result<Self> { Ok(HealthSummary {}) }
+}
+
+impl From<HealthSummary> for generated::HealthSummary {
+    fn from(_value: HealthSummary) -> Self { generated::HealthSummary {} }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HealthSummaryReport {
+    pub running: u32,
+    pub stopped: u64,
+    pub crashed: u64,
+}
+
+impl LauncherMessage for HealthSummaryReport {
+    type Generated = generated::HealthSummaryReport;
+
+    const MESSAGE_ID: &'static str = "HealthSummaryReport";
+
+    fn from_proto(proto: generated::HealthSummaryReport) -> Result<Self> {
+        Ok(HealthSummaryReport { running: proto.running.unwrap_or_default(),
+                                 stopped: proto.stopped.unwrap_or_default(),
+                                 crashed: proto.crashed.unwrap_or_default(), })
+    }
+}
+
+impl From<HealthSummaryReport> for generated::HealthSummaryReport {
+    fn from(value: HealthSummaryReport) -> Self {
+        generated::HealthSummaryReport { running: Some(value.running),
+                                         stopped: Some(value.stopped),
+                                         crashed: Some(value.crashed), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchedulingOf {
+    pub pid: i64,
+}
+
+impl LauncherMessage for SchedulingOf {
+    type Generated = generated::SchedulingOf;
+
+    const MESSAGE_ID: &'static str = "SchedulingOf";
+
+    fn from_proto(proto: generated::SchedulingOf) -> Result<Self> {
+        Ok(SchedulingOf { pid: proto.pid.ok_or(Error::ProtocolMismatch("pid"))?, })
+    }
+}
+
+impl From<SchedulingOf> for generated::SchedulingOf {
+    fn from(value: SchedulingOf) -> Self { generated::SchedulingOf { pid: Some(value.pid), } }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SetScheduling {
+    pub pid:        i64,
+    pub scheduling: Scheduling,
+}
+
+impl LauncherMessage for SetScheduling {
+    type Generated = generated::SetScheduling;
+
+    const MESSAGE_ID: &'static str = "SetScheduling";
+
+    fn from_proto(proto: generated::SetScheduling) -> Result<Self> {
+        let scheduling = proto.scheduling
+                              .ok_or(Error::ProtocolMismatch("scheduling"))?;
+        Ok(SetScheduling { pid:        proto.pid.ok_or(Error::ProtocolMismatch("pid"))?,
+                           scheduling: Scheduling::from_proto(scheduling)?, })
+    }
+}
+
+impl From<SetScheduling> for generated::SetScheduling {
+    fn from(value: SetScheduling) -> Self {
+        generated::SetScheduling { pid:        Some(value.pid),
+                                   scheduling: Some(value.scheduling.into()), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchedulingIs {
+    pub scheduling: Scheduling,
+}
+
+impl LauncherMessage for SchedulingIs {
+    type Generated = generated::SchedulingIs;
+
+    const MESSAGE_ID: &'static str = "SchedulingIs";
+
+    fn from_proto(proto: generated::SchedulingIs) -> Result<Self> {
+        let scheduling = proto.scheduling
+                              .ok_or(Error::ProtocolMismatch("scheduling"))?;
+        Ok(SchedulingIs { scheduling: Scheduling::from_proto(scheduling)?, })
+    }
+}
+
+impl From<SchedulingIs> for generated::SchedulingIs {
+    fn from(value: SchedulingIs) -> Self {
+        generated::SchedulingIs { scheduling: Some(value.scheduling.into()), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ping {}
+
+impl LauncherMessage for Ping {
+    type Generated = generated::Ping;
+
+    const MESSAGE_ID: &'static str = "Ping";
+
+    fn from_proto(_proto: generated::Ping) -> Result<Self> { Ok(Ping {}) }
+}
+
+impl From<Ping> for generated::Ping {
+    fn from(_value: Ping) -> Self { generated::Ping {} }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SubscribeExits {}
+
+impl LauncherMessage for SubscribeExits {
+    type Generated = generated::SubscribeExits;
+
+    const MESSAGE_ID: &'static str = "SubscribeExits";
+
+    fn from_proto(_proto: generated::SubscribeExits) -> Result<Self> { Ok(SubscribeExits {}) }
+}
+
+impl From<SubscribeExits> for generated::SubscribeExits {
+    fn from(_value: SubscribeExits) -> Self { generated::SubscribeExits {} }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcessExited {
+    pub service_name: String,
+    pub pid:          u32,
+    /// `None` if the process was ended by a signal.
+    pub exit_code:    Option<i32>,
+    pub signal:       Option<i32>,
+    /// Whether the Launcher terminated the process on request.
+    pub terminated:   bool,
+}
+
+impl LauncherMessage for ProcessExited {
+    type Generated = generated::ProcessExited;
+
+    const MESSAGE_ID: &'static str = "ProcessExited";
+
+    fn from_proto(proto: generated::ProcessExited) -> Result<Self> {
+        Ok(ProcessExited { service_name: proto.service_name
+                                              .ok_or(Error::ProtocolMismatch("service_name"))?,
+                           pid:          proto.pid.ok_or(Error::ProtocolMismatch("pid"))?,
+                           exit_code:    proto.exit_code,
+                           signal:       proto.signal,
+                           terminated:   proto.terminated.unwrap_or_default(), })
+    }
+}
+
+impl From<ProcessExited> for generated::ProcessExited {
+    fn from(value: ProcessExited) -> Self {
+        generated::ProcessExited { service_name: Some(value.service_name),
+                                   pid:          Some(value.pid),
+                                   exit_code:    value.exit_code,
+                                   signal:       value.signal,
+                                   terminated:   Some(value.terminated), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResourceUsage {}
+
+impl LauncherMessage for ResourceUsage {
+    type Generated = generated::ResourceUsage;
+
+    const MESSAGE_ID: &'static str = "ResourceUsage";
+
+    fn from_proto(_proto: generated::ResourceUsage) -> Result<Self> { Ok(ResourceUsage {}) }
+}
+
+impl From<ResourceUsage> for generated::ResourceUsage {
+    fn from(_value: ResourceUsage) -> Self { generated::ResourceUsage {} }
+}
+
+/// Each field is `None` where the platform doesn't expose it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResourceUsageReport {
+    pub cpu_user_ms:    Option<u64>,
+    pub cpu_system_ms:  Option<u64>,
+    pub rss_bytes:      Option<u64>,
+    pub peak_rss_bytes: Option<u64>,
+    pub open_fds:       Option<u32>,
+}
+
+impl LauncherMessage for ResourceUsageReport {
+    type Generated = generated::ResourceUsageReport;
+
+    const MESSAGE_ID: &'static str = "ResourceUsageReport";
+
+    fn from_proto(proto: generated::ResourceUsageReport) -> Result<Self> {
+        Ok(ResourceUsageReport { cpu_user_ms:    proto.cpu_user_ms,
+                                 cpu_system_ms:  proto.cpu_system_ms,
+                                 rss_bytes:      proto.rss_bytes,
+                                 peak_rss_bytes: proto.peak_rss_bytes,
+                                 open_fds:       proto.open_fds, })
+    }
+}
+
+impl From<ResourceUsageReport> for generated::ResourceUsageReport {
+    fn from(value: ResourceUsageReport) -> Self {
+        generated::ResourceUsageReport { cpu_user_ms:    value.cpu_user_ms,
+                                         cpu_system_ms:  value.cpu_system_ms,
+                                         rss_bytes:      value.rss_bytes,
+                                         peak_rss_bytes: value.peak_rss_bytes,
+                                         open_fds:       value.open_fds, }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ListServices {}
+
+impl LauncherMessage for ListServices {
+    type Generated = generated::ListServices;
+
+    const MESSAGE_ID: &'static str = "ListServices";
+
+    fn from_proto(_proto: generated::ListServices) -> Result<Self> { Ok(ListServices {}) }
+}
+
+impl From<ListServices> for generated::ListServices {
+    fn from(_value: ListServices) -> Self { generated::ListServices {} }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SupervisedService {
+    pub pid:              u32,
+    /// What the process was spawned from, without `svc_password`.
+    pub spawn:            Spawn,
+    /// Seconds since the Unix epoch.
+    pub started_at:       u64,
+    pub cgroup_path:      Option<String>,
+    pub readiness:        generated::ReadinessState,
+    /// Whether the process was spawned for a Supervisor the Launcher
+    /// has since replaced.
+    pub prior_supervisor: bool,
+}
+
+impl SupervisedService {
+    fn from_proto(proto: generated::SupervisedService) -> Result<Self> {
+        let spawn = proto.spawn.ok_or(Error::ProtocolMismatch("spawn"))?;
+        let readiness = proto.readiness
+                             .ok_or(Error::ProtocolMismatch("readiness"))?;
+        let readiness = generated::ReadinessState::try_from(readiness)
+            .or(Err(Error::ProtocolMismatch("readiness")))?;
+        Ok(SupervisedService { pid: proto.pid.ok_or(Error::ProtocolMismatch("pid"))?,
+                               spawn: Spawn::from_proto(spawn)?,
+                               started_at: proto.started_at
+                                                .ok_or(Error::ProtocolMismatch("started_at"))?,
+                               cgroup_path: proto.cgroup_path,
+                               readiness,
+                               prior_supervisor: proto.prior_supervisor.unwrap_or_default() })
+    }
+}
+
+impl From<SupervisedService> for generated::SupervisedService {
+    fn from(value: SupervisedService) -> Self {
+        generated::SupervisedService { pid:              Some(value.pid),
+                                       spawn:            Some(value.spawn.into()),
+                                       started_at:       Some(value.started_at),
+                                       cgroup_path:      value.cgroup_path,
+                                       readiness:        Some(value.readiness as i32),
+                                       prior_supervisor: Some(value.prior_supervisor), }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServiceList {
+    pub services: Vec<SupervisedService>,
+}
+
+impl LauncherMessage for ServiceList {
+    type Generated = generated::ServiceList;
+
+    const MESSAGE_ID: &'static str = "ServiceList";
+
+    fn from_proto(proto: generated::ServiceList) -> Result<Self> {
+        let services = proto.services
+                            .into_iter()
+                            .map(SupervisedService::from_proto)
+                            .collect::<Result<_>>()?;
+        Ok(ServiceList { services })
+    }
+}
+
+impl From<ServiceList> for generated::ServiceList {
+    fn from(value: ServiceList) -> Self {
+        generated::ServiceList { services: value.services.into_iter().map(Into::into).collect(), }
+    }
+}
@@ -0,0 +1,146 @@
+//! cgroup v2 resource limits for spawned services.
+//!
+//! Each service that asks for limits gets a cgroup of its own beneath
+//! `habitat` at the root of the unified hierarchy. The cgroup is named
+//! after the service, so a restarted service goes back into the same
+//! one; it is removed once the service's last process has been reaped.
+
+use crate::{error::ServiceRunError,
+            protocol};
+use log::debug;
+use std::{fs::{self,
+               OpenOptions},
+          io::{self,
+               Write},
+          os::unix::{io::AsRawFd,
+                     process::CommandExt},
+          path::{Path,
+                 PathBuf},
+          process::Command};
+
+/// Where the unified hierarchy is mounted.
+const MOUNT_POINT: &str = "/sys/fs/cgroup";
+
+/// The cgroup that service cgroups are created in.
+const PARENT: &str = "habitat";
+
+/// The `cpu.max` period when the Supervisor does not say.
+const DEFAULT_CPU_PERIOD_US: u64 = 100_000;
+
+/// Whether the host has the unified (v2) cgroup hierarchy mounted.
+pub fn supported() -> bool { Path::new(MOUNT_POINT).join("cgroup.controllers").is_file() }
+
+/// Create the cgroup for the service `id`, or reuse the one left by an
+/// earlier process of it, and apply `limits` to it.
+pub fn create(id: &str, limits: &protocol::CgroupLimits) -> Result<PathBuf, ServiceRunError> {
+    if !supported() {
+        let reason = format!("cgroup v2 is not mounted at {}", MOUNT_POINT);
+        return Err(ServiceRunError::CgroupUnsupported(reason));
+    }
+    let mut controllers = vec![];
+    if limits.memory_max.is_some() {
+        controllers.push("memory");
+    }
+    if limits.cpu_quota_us.is_some() {
+        controllers.push("cpu");
+    }
+    let root = Path::new(MOUNT_POINT);
+    let available = read(&root.join("cgroup.controllers"))?;
+    let available = available.split_whitespace().collect::<Vec<_>>();
+    if let Some(missing) = controllers.iter().find(|c| !available.contains(*c)) {
+        let reason = format!("the {} controller is not available", missing);
+        return Err(ServiceRunError::CgroupUnsupported(reason));
+    }
+
+    // A controller can only be used in a cgroup if it is enabled in
+    // every cgroup above it.
+    let parent = root.join(PARENT);
+    create_dir(&parent)?;
+    for cgroup in &[root, parent.as_path()] {
+        for controller in &controllers {
+            write(&cgroup.join("cgroup.subtree_control"),
+                  &format!("+{}", controller))?;
+        }
+    }
+
+    let path = parent.join(id.replace('/', "-"));
+    create_dir(&path)?;
+    // Anything not asked for is reset, since the cgroup may be left
+    // over from a process that was spawned with other limits.
+    let memory_max = limits.memory_max.map(|max| max.to_string());
+    let period = limits.cpu_period_us.unwrap_or(DEFAULT_CPU_PERIOD_US);
+    let cpu_max = limits.cpu_quota_us
+                        .map(|quota| format!("{} {}", quota, period));
+    let limited =
+        limit(&path, "memory.max", memory_max).and_then(|_| limit(&path, "cpu.max", cpu_max));
+    if let Err(err) = limited {
+        remove(&path);
+        return Err(err);
+    }
+    Ok(path)
+}
+
+/// Have the process `cmd` spawns start out in `cgroup`.
+pub fn join(cmd: &mut Command, cgroup: &Path) -> Result<(), ServiceRunError> {
+    let procs_path = cgroup.join("cgroup.procs");
+    let procs = OpenOptions::new().write(true)
+                                  .open(&procs_path)
+                                  .map_err(|err| cgroup_error(&procs_path, &err))?;
+    // Writing "0" to cgroup.procs moves the writer, so the service is
+    // in its cgroup before it execs. The file is opened here, before
+    // the process drops privileges, and write(2) is safe to call
+    // between fork and exec.
+    unsafe {
+        cmd.pre_exec(move || {
+               if libc::write(procs.as_raw_fd(), b"0".as_ptr().cast(), 1) == 1 {
+                   Ok(())
+               } else {
+                   Err(io::Error::last_os_error())
+               }
+           });
+    }
+    Ok(())
+}
+
+/// Remove `cgroup` if no process is left in it.
+pub fn remove(cgroup: &Path) {
+    if let Err(err) = fs::remove_dir(cgroup) {
+        debug!("Unable to remove cgroup {}, {}", cgroup.display(), err);
+    }
+}
+
+/// Set the interface file `name` of `cgroup` to `value`, or to "max" if
+/// there is none.
+fn limit(cgroup: &Path, name: &str, value: Option<String>) -> Result<(), ServiceRunError> {
+    let path = cgroup.join(name);
+    match value {
+        Some(value) => write(&path, &value),
+        // The file only exists where its controller is enabled.
+        None if path.exists() => write(&path, "max"),
+        None => Ok(()),
+    }
+}
+
+fn create_dir(path: &Path) -> Result<(), ServiceRunError> {
+    match fs::create_dir(path) {
+        Err(err) if err.kind() != io::ErrorKind::AlreadyExists => Err(cgroup_error(path, &err)),
+        _ => Ok(()),
+    }
+}
+
+fn read(path: &Path) -> Result<String, ServiceRunError> {
+    fs::read_to_string(path).map_err(|err| cgroup_error(path, &err))
+}
+
+fn write(path: &Path, value: &str) -> Result<(), ServiceRunError> {
+    // Interface files take a single write each, so this must not be
+    // buffered or split.
+    OpenOptions::new().write(true)
+                      .open(path)
+                      .and_then(|mut file| file.write_all(value.as_bytes()))
+                      .map_err(|err| cgroup_error(path, &err))
+}
+
+fn cgroup_error(path: &Path, err: &io::Error) -> ServiceRunError {
+    ServiceRunError::Cgroup(format!("{}: {}", path.display(), err))
+}
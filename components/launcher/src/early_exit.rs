@@ -0,0 +1,88 @@
+//! Catching services that exit as soon as they are spawned.
+//!
+//! A process that can't find its config file or bind its port usually
+//! says so on standard error and exits within moments of starting, yet
+//! the spawn itself succeeded. When a spawn asks for it, the Launcher
+//! watches the new process for a short window, keeping the last lines
+//! it writes to standard error, and fails the spawn with them if the
+//! process exits in that time.
+
+use std::{collections::VecDeque,
+          sync::{Condvar,
+                 Mutex},
+          time::Duration};
+
+/// The longest a spawn is held up watching for the process to exit.
+/// No other message is handled in the meantime, so together with
+/// `DRAIN_TIMEOUT` it stays well below the client's interaction
+/// timeout.
+pub const MAX_WINDOW: Duration = Duration::from_millis(250);
+
+/// How often the watched process is checked for having exited.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// How long to wait for the end of a process's standard error once it
+/// has exited. Its own children may still hold the pipe open.
+const DRAIN_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How many of the last lines written to standard error are kept.
+const MAX_LINES: usize = 10;
+
+#[derive(Default)]
+struct State {
+    lines:    VecDeque<String>,
+    /// Whether the pipe has been closed.
+    closed:   bool,
+    /// Whether lines are still wanted. Once the window has passed they
+    /// aren't, and none are kept.
+    finished: bool,
+}
+
+/// The last lines a process wrote to standard error during its window.
+/// It is shared between the Launcher and the thread piping the
+/// process's standard error.
+#[derive(Default)]
+pub struct StderrTail {
+    state:  Mutex<State>,
+    closed: Condvar,
+}
+
+impl StderrTail {
+    /// Keep `line`, dropping the oldest one kept if there are too
+    /// many.
+    pub fn push(&self, line: &str) {
+        let mut state = self.state.lock().expect("stderr tail lock poisoned");
+        if state.finished {
+            return;
+        }
+        if state.lines.len() == MAX_LINES {
+            state.lines.pop_front();
+        }
+        state.lines.push_back(line.to_string());
+    }
+
+    /// Note that the process's standard error has been closed.
+    pub fn close(&self) {
+        self.state.lock().expect("stderr tail lock poisoned").closed = true;
+        self.closed.notify_all();
+    }
+
+    /// Stop keeping lines, because the process outlived its window.
+    pub fn finish(&self) {
+        let mut state = self.state.lock().expect("stderr tail lock poisoned");
+        state.finished = true;
+        state.lines.clear();
+    }
+
+    /// The lines kept for a process that has exited, once the rest of
+    /// its standard error has been read, or as many as have been read
+    /// within `DRAIN_TIMEOUT`.
+    pub fn drain(&self) -> Vec<String> {
+        let state = self.state.lock().expect("stderr tail lock poisoned");
+        let (mut state, _) = self.closed
+                                 .wait_timeout_while(state, DRAIN_TIMEOUT, |state| !state.closed)
+                                 .expect("stderr tail lock poisoned");
+        state.finished = true;
+        state.lines.drain(..).collect()
+    }
+}
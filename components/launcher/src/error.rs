@@ -1,5 +1,6 @@
 use crate::protocol;
-use std::io;
+use std::{io,
+          path::Path};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -20,6 +21,49 @@ pub enum ServiceRunError {
     GroupNotFound(String),
     #[error("No UID for user '{0}' could be found")]
     UserNotFound(String),
+    #[error("Invalid CPU affinity: {0}")]
+    InvalidCpuAffinity(String),
+    #[error("Cgroup limits are not supported on this host: {0}")]
+    CgroupUnsupported(String),
+    #[error("Unable to apply cgroup limits: {0}")]
+    Cgroup(String),
+    #[error("Invalid scheduling: {0}")]
+    InvalidScheduling(String),
+    #[error("Unable to set scheduling: {0}")]
+    Scheduling(String),
+    #[error("Invalid privileges: {0}")]
+    InvalidPrivileges(String),
+    #[error("Service binary '{0}' exited straight away, {1}")]
+    ExitedEarly(String, String),
+    #[error("Service binary '{0}' was not found")]
+    BinaryNotFound(String),
+    #[error("Permission denied running service binary '{0}'")]
+    PermissionDenied(String),
+    #[error("Service binary '{0}' exists but could not be run; its interpreter or a library it \
+             needs may be missing")]
+    MissingDependency(String),
+    #[cfg(windows)]
+    #[error("Not supported on this platform: {0}")]
+    Unsupported(&'static str),
+}
+
+impl ServiceRunError {
+    /// Classify a failure to spawn `binary`, so the Supervisor can
+    /// report why it happened.
+    pub fn spawn(err: io::Error, binary: &str) -> Self {
+        match err.kind() {
+            // The binary itself exists, so whatever wasn't found is
+            // something it needs in order to run.
+            io::ErrorKind::NotFound if Path::new(binary).is_file() => {
+                ServiceRunError::MissingDependency(binary.to_string())
+            }
+            io::ErrorKind::NotFound => ServiceRunError::BinaryNotFound(binary.to_string()),
+            io::ErrorKind::PermissionDenied => {
+                ServiceRunError::PermissionDenied(binary.to_string())
+            }
+            _ => ServiceRunError::Spawn(err),
+        }
+    }
 }
 
 impl From<ServiceRunError> for protocol::ErrCode {
@@ -27,6 +71,14 @@ impl From<ServiceRunError> for protocol::ErrCode {
         match err {
             ServiceRunError::GroupNotFound(_) => protocol::ErrCode::GroupNotFound,
             ServiceRunError::UserNotFound(_) => protocol::ErrCode::UserNotFound,
+            ServiceRunError::InvalidCpuAffinity(_) => protocol::ErrCode::InvalidCpuAffinity,
+            ServiceRunError::CgroupUnsupported(_) => protocol::ErrCode::CgroupUnsupported,
+            ServiceRunError::InvalidScheduling(_) => protocol::ErrCode::InvalidScheduling,
+            ServiceRunError::InvalidPrivileges(_) => protocol::ErrCode::InvalidPrivileges,
+            ServiceRunError::BinaryNotFound(_) => protocol::ErrCode::BinaryNotFound,
+            ServiceRunError::PermissionDenied(_) => protocol::ErrCode::PermissionDenied,
+            ServiceRunError::MissingDependency(_) => protocol::ErrCode::MissingDependency,
+            ServiceRunError::ExitedEarly(..) => protocol::ErrCode::ExitedEarly,
             _ => protocol::ErrCode::Unknown,
         }
     }
@@ -1,7 +1,17 @@
 use habitat_core as core;
 use habitat_launcher_protocol as protocol;
 
+#[cfg(target_os = "linux")]
+pub mod cgroup;
+pub mod early_exit;
 pub mod error;
+pub mod preload;
+#[cfg(target_os = "linux")]
+pub mod privileges;
+pub mod readiness;
+pub mod resource_usage;
+#[cfg(target_os = "linux")]
+pub mod scheduling;
 pub mod server;
 pub mod service;
 mod sys;
@@ -0,0 +1,92 @@
+//! Preparing a package ahead of a spawn.
+//!
+//! Preloading runs on the Launcher's main loop, so it never reads file
+//! contents itself: on Linux it asks the kernel to start reading them
+//! in the background, and elsewhere it only checks that the binary can
+//! be run. Either way it gives up on a tree after `MAX_FILES` files.
+
+use crate::{error::ServiceRunError,
+            protocol};
+use log::debug;
+use std::{fs::{self,
+               File},
+          path::Path};
+
+/// The most files a single preload visits.
+const MAX_FILES: u32 = 10_000;
+
+/// Check that `msg.binary` can be spawned, and warm the page cache with
+/// it and the files under `msg.paths`.
+pub fn preload(msg: &protocol::Preload) -> Result<protocol::PreloadOk, ServiceRunError> {
+    check_binary(Path::new(&msg.binary))?;
+    let mut reply = protocol::PreloadOk::default();
+    for path in std::iter::once(&msg.binary).chain(&msg.paths) {
+        visit(Path::new(path), &mut reply);
+    }
+    debug!("Preloaded {} files ({} bytes) for {}",
+           reply.files, reply.bytes, msg.binary);
+    Ok(reply)
+}
+
+fn check_binary(binary: &Path) -> Result<(), ServiceRunError> {
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+
+    match fs::metadata(binary) {
+        #[cfg(unix)]
+        Ok(metadata) if metadata.is_file() && metadata.permissions().mode() & 0o111 == 0 => {
+            Err(ServiceRunError::PermissionDenied(binary.display().to_string()))
+        }
+        Ok(metadata) if metadata.is_file() => Ok(()),
+        _ => Err(ServiceRunError::BinaryNotFound(binary.display().to_string())),
+    }
+}
+
+/// Preload `path` and, if it is a directory, everything under it.
+/// Symlinks are not followed, and anything unreadable is skipped.
+fn visit(path: &Path, reply: &mut protocol::PreloadOk) {
+    if reply.files >= MAX_FILES {
+        return;
+    }
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            debug!("Not preloading {}, {}", path.display(), err);
+            return;
+        }
+    };
+    if metadata.is_dir() {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(err) => {
+                debug!("Not preloading {}, {}", path.display(), err);
+                return;
+            }
+        };
+        for entry in entries.flatten() {
+            visit(&entry.path(), reply);
+        }
+    } else if metadata.is_file() && read_ahead(path) {
+        reply.files += 1;
+        reply.bytes += metadata.len();
+    }
+}
+
+/// Start reading `path` into the page cache without waiting for it.
+#[cfg(target_os = "linux")]
+fn read_ahead(path: &Path) -> bool {
+    use std::os::unix::io::AsRawFd;
+
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) => {
+            debug!("Not preloading {}, {}", path.display(), err);
+            return false;
+        }
+    };
+    let advice = unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_WILLNEED) };
+    advice == 0
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_ahead(path: &Path) -> bool { File::open(path).is_ok() }
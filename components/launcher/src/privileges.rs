@@ -0,0 +1,151 @@
+//! Supplementary groups and capabilities for spawned services.
+//!
+//! A service normally runs as its user with that user's groups and no
+//! capabilities at all. Some need a little more, e.g. binding a port
+//! below 1024, and some should be kept from ever regaining privileges
+//! through a setuid binary they run. The Launcher checks what was
+//! asked for before spawning, so that anything it can't grant is
+//! refused outright, and then sets it up between fork and exec, while
+//! the process is still privileged enough to do so.
+
+use crate::{core::os::process::can_run_services_as_svc_user,
+            error::ServiceRunError,
+            protocol};
+use caps::{CapSet,
+           Capability,
+           CapsHashSet};
+use nix::unistd::{getgrouplist,
+                  setgid,
+                  setgroups,
+                  setuid,
+                  Gid,
+                  Uid,
+                  User};
+use std::{ffi::CString,
+          io,
+          os::unix::process::CommandExt,
+          process::Command,
+          str::FromStr};
+
+/// What the process is to be given, resolved and checked against what
+/// the Launcher itself holds.
+#[derive(Debug)]
+pub struct Privileges {
+    groups: Vec<Gid>,
+    grant:  CapsHashSet,
+    drop:   CapsHashSet,
+}
+
+/// Resolve `privileges` for a process running as `uid` and `gid`,
+/// refusing anything the Launcher can't apply.
+pub fn check(uid: Uid,
+             gid: Gid,
+             privileges: &protocol::Privileges)
+             -> Result<Privileges, ServiceRunError> {
+    if !can_run_services_as_svc_user() {
+        return Err(invalid("the Launcher is not able to change the user services run as"));
+    }
+    let grant = parse_capabilities(&privileges.grant_capabilities)?;
+    let drop = parse_capabilities(&privileges.drop_capabilities)?;
+    if let Some(cap) = grant.intersection(&drop).next() {
+        return Err(invalid(&format!("{} is both granted and dropped", cap)));
+    }
+    if !grant.is_empty() {
+        if caps::runtime::ambient_set_supported().is_err() {
+            return Err(invalid("the kernel does not support ambient capabilities"));
+        }
+        let permitted = match caps::read(None, CapSet::Permitted) {
+            Ok(permitted) => permitted,
+            Err(err) => {
+                let reason = format!("unable to read the Launcher's capabilities: {}", err);
+                return Err(invalid(&reason));
+            }
+        };
+        if let Some(cap) = grant.difference(&permitted).next() {
+            return Err(invalid(&format!("the Launcher does not hold {} to grant", cap)));
+        }
+    }
+    Ok(Privileges { groups: groups(uid, gid, &privileges.supplementary_groups)?,
+                    grant,
+                    drop })
+}
+
+/// Have the process `cmd` spawns run as `uid` and `gid` with
+/// `privileges`. This takes the place of the user switch that
+/// `hook_command` would otherwise make, since groups and capabilities
+/// have to be set around it.
+pub fn apply(cmd: &mut Command, uid: Uid, gid: Gid, privileges: Privileges) {
+    let Privileges { groups,
+                     grant,
+                     drop, } = privileges;
+    // Only system calls are made here, everything that needs looking
+    // up having been done by `check`.
+    unsafe {
+        cmd.pre_exec(move || {
+               setgroups(&groups)?;
+               for &cap in &drop {
+                   caps::drop(None, CapSet::Bounding, cap).map_err(caps_error)?;
+               }
+               // Changing to a user other than root clears every
+               // capability, unless they are kept across it.
+               if !grant.is_empty() {
+                   caps::securebits::set_keepcaps(true).map_err(caps_error)?;
+               }
+               setgid(gid)?;
+               setuid(uid)?;
+               if !grant.is_empty() {
+                   caps::set(None, CapSet::Permitted, &grant).map_err(caps_error)?;
+                   caps::set(None, CapSet::Inheritable, &grant).map_err(caps_error)?;
+                   // Ambient capabilities are the ones that survive
+                   // running a binary without file capabilities.
+                   for &cap in &grant {
+                       caps::raise(None, CapSet::Ambient, cap).map_err(caps_error)?;
+                   }
+               }
+               Ok(())
+           });
+    }
+}
+
+/// Parse capability names, with or without the "CAP_" prefix and in
+/// either case.
+fn parse_capabilities(names: &[String]) -> Result<CapsHashSet, ServiceRunError> {
+    names.iter()
+         .map(|name| {
+             let upper = name.trim().to_uppercase();
+             let full = if upper.starts_with("CAP_") {
+                 upper
+             } else {
+                 format!("CAP_{}", upper)
+             };
+             Capability::from_str(&full).map_err(|_| {
+                                            invalid(&format!("unknown capability '{}'", name))
+                                        })
+         })
+         .collect()
+}
+
+/// The groups of the user `uid`, whose primary group is `gid`, along
+/// with the `extra` groups asked for.
+fn groups(uid: Uid, gid: Gid, extra: &[u32]) -> Result<Vec<Gid>, ServiceRunError> {
+    let user = User::from_uid(uid).ok()
+                                  .flatten()
+                                  .ok_or_else(|| ServiceRunError::UserNotFound(uid.to_string()))?;
+    let name = CString::new(user.name).map_err(|_| invalid("the user name is not valid"))?;
+    let mut groups = getgrouplist(&name, gid).unwrap_or_else(|_| vec![gid]);
+    for &group in extra {
+        let group = Gid::from_raw(group);
+        if !groups.contains(&group) {
+            groups.push(group);
+        }
+    }
+    Ok(groups)
+}
+
+fn invalid(reason: &str) -> ServiceRunError {
+    ServiceRunError::InvalidPrivileges(reason.to_string())
+}
+
+fn caps_error(err: caps::errors::CapsError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
@@ -0,0 +1,134 @@
+//! Startup readiness probes for spawned services.
+//!
+//! A probe is evaluated a little at a time from the Launcher's main
+//! loop, so a slow probe command never holds up handling of other
+//! messages; the command is started on one pass and checked for
+//! completion on later ones.
+
+use crate::{protocol::{self,
+                       ReadinessState},
+            service};
+use log::{debug,
+          warn};
+use std::{path::Path,
+          process::{Child,
+                    Stdio},
+          time::{Duration,
+                 Instant}};
+
+/// How long a probe may go without passing when the Supervisor does
+/// not say.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How long to wait between attempts when the Supervisor does not say.
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(1_000);
+
+pub struct Readiness {
+    probe:      protocol::ReadinessProbe,
+    deadline:   Instant,
+    interval:   Duration,
+    next_check: Instant,
+    /// The probe command currently being run, if any.
+    check:      Option<Child>,
+    state:      ReadinessState,
+}
+
+impl Readiness {
+    pub fn new(probe: protocol::ReadinessProbe) -> Self {
+        let now = Instant::now();
+        let timeout = probe.timeout_secs
+                           .map(|secs| Duration::from_secs(secs.into()))
+                           .unwrap_or(DEFAULT_TIMEOUT);
+        let interval = probe.interval_ms
+                            .map(|ms| Duration::from_millis(ms.into()))
+                            .unwrap_or(DEFAULT_INTERVAL);
+        Readiness { probe,
+                    deadline: now + timeout,
+                    interval,
+                    next_check: now,
+                    check: None,
+                    state: ReadinessState::Pending }
+    }
+
+    pub fn state(&self) -> ReadinessState { self.state }
+
+    /// Advance the probe for the service spawned with `spawn`,
+    /// returning the new state if it changed.
+    pub fn poll(&mut self, spawn: &protocol::Spawn) -> Option<ReadinessState> {
+        if self.state != ReadinessState::Pending {
+            return None;
+        }
+        let now = Instant::now();
+        if self.passed(spawn, now) {
+            self.state = ReadinessState::Ready;
+        } else if now >= self.deadline {
+            self.cancel();
+            self.state = ReadinessState::TimedOut;
+        } else {
+            return None;
+        }
+        Some(self.state)
+    }
+
+    fn passed(&mut self, spawn: &protocol::Spawn, now: Instant) -> bool {
+        if let Some(child) = self.check.as_mut() {
+            return match child.try_wait() {
+                Ok(None) => false,
+                Ok(Some(status)) => {
+                    self.check = None;
+                    status.success()
+                }
+                Err(err) => {
+                    // On Unix the Launcher may have reaped the probe
+                    // itself while collecting orphans; just try again.
+                    debug!("Error waiting for readiness probe of {}, {}", spawn.id, err);
+                    self.check = None;
+                    false
+                }
+            };
+        }
+        if now < self.next_check {
+            return false;
+        }
+        self.next_check = now + self.interval;
+        if let Some(file) = &self.probe.file {
+            return Path::new(file).exists();
+        }
+        let (program, args) = match self.probe.command.split_first() {
+            Some(command) => command,
+            None => return false,
+        };
+        // As the service's user, where the platform allows it
+        let mut cmd = match service::probe_command(spawn, program) {
+            Ok(cmd) => cmd,
+            Err(err) => {
+                warn!("Unable to run readiness probe of {}, {}", spawn.id, err);
+                return false;
+            }
+        };
+        cmd.args(args)
+           .stdin(Stdio::null())
+           .stdout(Stdio::null())
+           .stderr(Stdio::null());
+        if let Some(cwd) = &spawn.cwd {
+            cmd.current_dir(cwd);
+        }
+        match cmd.spawn() {
+            Ok(child) => self.check = Some(child),
+            Err(err) => warn!("Unable to run readiness probe of {}, {}", spawn.id, err),
+        }
+        false
+    }
+
+    /// Stop any probe command that is still running.
+    pub fn cancel(&mut self) {
+        if let Some(mut child) = self.check.take() {
+            child.kill().ok();
+            child.wait().ok();
+        }
+    }
+}
+
+impl Drop for Readiness {
+    fn drop(&mut self) { self.cancel(); }
+}
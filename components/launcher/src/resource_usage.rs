@@ -0,0 +1,81 @@
+//! The Launcher's own resource usage.
+//!
+//! The Launcher runs for as long as the host does, outliving many
+//! Supervisors, so a slow leak in it only shows over days. It reports
+//! what it is using itself, apart from the processes it supervises, so
+//! that the Supervisor can surface it. Only Unix platforms are
+//! measured; elsewhere every figure is absent.
+
+use crate::protocol;
+#[cfg(unix)]
+use log::debug;
+#[cfg(unix)]
+use std::{fs,
+          io,
+          mem};
+
+/// Measure the Launcher's current resource usage.
+#[cfg(unix)]
+pub fn measure() -> protocol::ResourceUsageReport {
+    let mut usage: libc::rusage = unsafe { mem::zeroed() };
+    let rusage = if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } == 0 {
+        Some(usage)
+    } else {
+        debug!("Unable to read the Launcher's resource usage: {}",
+               io::Error::last_os_error());
+        None
+    };
+    protocol::ResourceUsageReport { cpu_user_ms:    rusage.map(|usage| millis(usage.ru_utime)),
+                                    cpu_system_ms:  rusage.map(|usage| millis(usage.ru_stime)),
+                                    rss_bytes:      rss_bytes(),
+                                    peak_rss_bytes: rusage.map(|usage| peak_rss_bytes(&usage)),
+                                    open_fds:       open_fds(), }
+}
+
+#[cfg(not(unix))]
+pub fn measure() -> protocol::ResourceUsageReport { protocol::ResourceUsageReport::default() }
+
+#[cfg(unix)]
+fn millis(time: libc::timeval) -> u64 { time.tv_sec as u64 * 1_000 + time.tv_usec as u64 / 1_000 }
+
+/// macOS reports the peak in bytes, everything else in kilobytes.
+#[cfg(unix)]
+fn peak_rss_bytes(usage: &libc::rusage) -> u64 {
+    if cfg!(target_os = "macos") {
+        usage.ru_maxrss as u64
+    } else {
+        usage.ru_maxrss as u64 * 1024
+    }
+}
+
+/// The resident set, the second field of `/proc/self/statm`, in pages.
+#[cfg(target_os = "linux")]
+fn rss_bytes() -> Option<u64> {
+    let statm = fs::read_to_string("/proc/self/statm").ok()?;
+    let pages = statm.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        page_size if page_size > 0 => Some(pages * page_size as u64),
+        _ => None,
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn rss_bytes() -> Option<u64> { None }
+
+#[cfg(unix)]
+fn open_fds() -> Option<u32> {
+    // Assume we have /proc/self/fd unless we know we don't
+    #[cfg(not(any(target_os = "freebsd", target_os = "macos", target_os = "ios")))]
+    const FD_DIR: &str = "/proc/self/fd";
+    #[cfg(any(target_os = "freebsd", target_os = "macos", target_os = "ios"))]
+    const FD_DIR: &str = "/dev/fd";
+
+    match fs::read_dir(FD_DIR) {
+        Ok(entries) => Some(entries.count() as u32),
+        Err(err) => {
+            debug!("Unable to read the Launcher's open file descriptors: {}",
+                   err);
+            None
+        }
+    }
+}
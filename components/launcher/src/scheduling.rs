@@ -0,0 +1,111 @@
+//! Scheduling policies for spawned services.
+//!
+//! A service runs either under SCHED_OTHER with a nice value, or under
+//! SCHED_FIFO with a real-time priority. Either a real-time policy or a
+//! negative nice value lets a service starve the rest of the host, the
+//! Supervisor included, so they are only allowed for the services named
+//! in `TRUSTED_SERVICES_ENVVAR`, and real-time priorities are kept below
+//! those of the kernel's own threads.
+
+use crate::{core,
+            error::ServiceRunError,
+            protocol::{self,
+                       SchedulingPolicy}};
+use std::io;
+
+/// A comma-separated list of the service groups (e.g. "redis.default")
+/// that may be given real-time scheduling or a negative nice value.
+pub const TRUSTED_SERVICES_ENVVAR: &str = "HAB_LAUNCH_SCHED_TRUSTED_SERVICES";
+
+/// The highest SCHED_FIFO priority a service may have. The kernel runs
+/// its threaded interrupt handlers at 50.
+const MAX_FIFO_PRIORITY: u32 = 49;
+
+/// Check that the service `id` may be scheduled as `scheduling`.
+pub fn check(id: &str, scheduling: &protocol::Scheduling) -> Result<(), ServiceRunError> {
+    let trusted = core::env::var(TRUSTED_SERVICES_ENVVAR).unwrap_or_default();
+    check_trusted(trusted.split(',').map(str::trim), id, scheduling)
+}
+
+/// Have the process `pid` scheduled as `scheduling`. This has to be
+/// done by the Launcher itself, after the process has been spawned,
+/// since a service that has dropped its privileges can't raise its own
+/// priority.
+pub fn apply(pid: u32, scheduling: &protocol::Scheduling) -> Result<(), ServiceRunError> {
+    let (policy, priority) = match scheduling.policy {
+        SchedulingPolicy::Fifo => (libc::SCHED_FIFO, scheduling.priority as libc::c_int),
+        SchedulingPolicy::Other => (libc::SCHED_OTHER, 0),
+    };
+    let param = libc::sched_param { sched_priority: priority, };
+    if unsafe { libc::sched_setscheduler(pid as libc::pid_t, policy, &param) } != 0 {
+        return Err(os_error("sched_setscheduler"));
+    }
+    if scheduling.policy == SchedulingPolicy::Other
+       && unsafe { libc::setpriority(libc::PRIO_PROCESS as _, pid, scheduling.nice) } != 0
+    {
+        return Err(os_error("setpriority"));
+    }
+    Ok(())
+}
+
+/// How the process `pid` is scheduled. Policies other than SCHED_FIFO
+/// are all reported as `SchedulingPolicy::Other`.
+pub fn query(pid: u32) -> Result<protocol::Scheduling, ServiceRunError> {
+    let policy = unsafe { libc::sched_getscheduler(pid as libc::pid_t) };
+    if policy < 0 {
+        return Err(os_error("sched_getscheduler"));
+    }
+    if policy & !libc::SCHED_RESET_ON_FORK == libc::SCHED_FIFO {
+        let mut param = libc::sched_param { sched_priority: 0 };
+        if unsafe { libc::sched_getparam(pid as libc::pid_t, &mut param) } != 0 {
+            return Err(os_error("sched_getparam"));
+        }
+        return Ok(protocol::Scheduling { policy:   SchedulingPolicy::Fifo,
+                                         priority: param.sched_priority as u32,
+                                         nice:     0, });
+    }
+    // -1 is a valid nice value, so only errno tells a failure apart.
+    nix::errno::Errno::clear();
+    let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS as _, pid) };
+    if nice == -1 && io::Error::last_os_error().raw_os_error() != Some(0) {
+        return Err(os_error("getpriority"));
+    }
+    Ok(protocol::Scheduling { policy: SchedulingPolicy::Other,
+                              priority: 0,
+                              nice })
+}
+
+/// `check`, given the `trusted` services.
+fn check_trusted<'a>(mut trusted: impl Iterator<Item = &'a str>,
+                     id: &str,
+                     scheduling: &protocol::Scheduling)
+                     -> Result<(), ServiceRunError> {
+    let escalates = match scheduling.policy {
+        SchedulingPolicy::Fifo => {
+            if !(1..=MAX_FIFO_PRIORITY).contains(&scheduling.priority) {
+                let reason = format!("SCHED_FIFO priority {} is not between 1 and {}",
+                                     scheduling.priority, MAX_FIFO_PRIORITY);
+                return Err(ServiceRunError::InvalidScheduling(reason));
+            }
+            true
+        }
+        SchedulingPolicy::Other => {
+            if !(-20..=19).contains(&scheduling.nice) {
+                let reason = format!("nice value {} is not between -20 and 19", scheduling.nice);
+                return Err(ServiceRunError::InvalidScheduling(reason));
+            }
+            scheduling.nice < 0
+        }
+    };
+    if escalates && !trusted.any(|trusted| trusted == id) {
+        let reason = format!("{} is not listed in {}, so it may not have real-time scheduling or \
+                              a negative nice value",
+                             id, TRUSTED_SERVICES_ENVVAR);
+        return Err(ServiceRunError::InvalidScheduling(reason));
+    }
+    Ok(())
+}
+
+fn os_error(call: &str) -> ServiceRunError {
+    ServiceRunError::Scheduling(format!("{}: {}", call, io::Error::last_os_error()))
+}
@@ -31,10 +31,9 @@ use log::{debug,
           warn};
 use semver::{Version,
              VersionReq};
-#[cfg(unix)]
-use std::{cmp::Ordering,
-          os::unix::process::ExitStatusExt};
-use std::{collections::HashMap,
+use std::{cell::Cell,
+          collections::{HashMap,
+                        VecDeque},
           fs,
           io::Write,
           path::PathBuf,
@@ -47,7 +46,12 @@ use std::{collections::HashMap,
                  Condvar,
                  Mutex},
           thread,
-          time::Duration};
+          time::{Duration,
+                 SystemTime,
+                 UNIX_EPOCH}};
+#[cfg(unix)]
+use std::{cmp::Ordering,
+          os::unix::process::ExitStatusExt};
 
 const IPC_CONNECT_TIMEOUT_SECS: &str = "HAB_LAUNCH_SUP_CONNECT_TIMEOUT_SECS";
 const DEFAULT_IPC_CONNECT_TIMEOUT_SECS: u64 = 5;
@@ -61,7 +65,20 @@ const SUP_VERSION_CHECK_DISABLE: &str = "HAB_LAUNCH_NO_SUP_VERSION_CHECK";
 const SUP_VERSION_REQ: &str = ">= 0.56";
 
 type Receiver = IpcReceiver<Vec<u8>>;
-type Sender = IpcSender<Vec<u8>>;
+
+/// The connection to the Supervisor, along with the framing negotiated
+/// on it. A new connection always starts out unframed.
+pub struct Sender {
+    tx:      IpcSender<Vec<u8>>,
+    framing: Cell<protocol::Framing>,
+}
+
+impl Sender {
+    fn new(tx: IpcSender<Vec<u8>>) -> Self {
+        Sender { tx,
+                 framing: Cell::new(protocol::Framing::Unframed) }
+    }
+}
 
 enum TickState {
     Continue,
@@ -132,6 +149,9 @@ impl Server {
         self.tx = tx;
         self.rx = rx;
         self.supervisor = supervisor;
+        // The new Supervisor subscribes again if it wants exits
+        self.services.unsubscribe_exits();
+        self.services.supervisor_replaced();
         Ok(())
     }
 
@@ -212,7 +232,15 @@ impl Server {
         }
     }
 
-    fn reap_services(&mut self) { self.services.reap_services() }
+    fn reap_services(&mut self) {
+        self.services.reap_services();
+        for exit in self.services.take_exits() {
+            if let Err(err) = send(&self.tx, &exit) {
+                warn!("Unable to notify the Supervisor that PID {} exited: {}",
+                      exit.pid, err);
+            }
+        }
+    }
 
     fn shutdown(&mut self) {
         debug!("Shutting down launcher");
@@ -282,6 +310,7 @@ impl Server {
         // be done in a way that the basic functionality of process
         // tracking still works on Windows.
         self.reap_services();
+        self.services.check_readiness();
 
         if signals::pending_shutdown() {
             self.shutdown();
@@ -368,17 +397,75 @@ impl Server {
     }
 }
 
+/// The most problems `Diagnostics` keeps before discarding the oldest.
+const MAX_DIAGNOSTICS: usize = 100;
+
+/// Problems the Launcher ran into that the Supervisor may not have seen
+/// in a command's reply, kept until the Supervisor clears them.
 #[derive(Debug, Default)]
-pub struct ServiceTable(HashMap<u32, Service>);
+pub struct Diagnostics {
+    recent:  VecDeque<protocol::Diagnostic>,
+    dropped: u64,
+}
+
+impl Diagnostics {
+    pub fn record(&mut self,
+                  kind: protocol::DiagnosticKind,
+                  service_name: &str,
+                  msg: impl ToString) {
+        let at = SystemTime::now().duration_since(UNIX_EPOCH)
+                                  .unwrap_or_default();
+        if self.recent.len() >= MAX_DIAGNOSTICS {
+            self.recent.pop_front();
+            self.dropped += 1;
+        }
+        self.recent.push_back(protocol::Diagnostic { kind,
+                                                     service_name: service_name.to_string(),
+                                                     at: at.as_secs(),
+                                                     msg: msg.to_string() });
+    }
+
+    /// Everything recorded so far, forgetting it if `clear` is set.
+    pub fn report(&mut self, clear: bool) -> protocol::DiagnosticReport {
+        let report =
+            protocol::DiagnosticReport { diagnostics: self.recent.iter().cloned().collect(),
+                                         dropped:     self.dropped, };
+        if clear {
+            self.recent.clear();
+            self.dropped = 0;
+        }
+        report
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ServiceTable {
+    services:    HashMap<u32, Service>,
+    diagnostics: Diagnostics,
+    /// Processes reaped after being terminated or exiting successfully.
+    stopped:     u64,
+    /// Processes reaped after exiting any other way.
+    crashed:     u64,
+    /// Whether the Supervisor has subscribed to exits.
+    subscribed:  bool,
+    /// Exits not yet pushed to the subscribed Supervisor.
+    exits:       Vec<protocol::ProcessExited>,
+    /// How many times the Supervisor has been replaced since the
+    /// Launcher started.
+    supervisor:  u32,
+}
 
 impl ServiceTable {
-    pub fn get(&self, pid: u32) -> Option<&Service> { self.0.get(&pid) }
+    pub fn get(&self, pid: u32) -> Option<&Service> { self.services.get(&pid) }
 
-    pub fn get_mut(&mut self, pid: u32) -> Option<&mut Service> { self.0.get_mut(&pid) }
+    pub fn get_mut(&mut self, pid: u32) -> Option<&mut Service> { self.services.get_mut(&pid) }
 
-    pub fn insert(&mut self, service: Service) { self.0.insert(service.id(), service); }
+    pub fn insert(&mut self, mut service: Service) {
+        service.spawned_for(self.supervisor);
+        self.services.insert(service.id(), service);
+    }
 
-    pub fn remove(&mut self, pid: u32) -> Option<Service> { self.0.remove(&pid) }
+    pub fn remove(&mut self, pid: u32) -> Option<Service> { self.services.remove(&pid) }
 
     // Obviously this is not the most elegant implementation. However,
     // in practice we don't have a whole lot of processes per
@@ -402,17 +489,144 @@ impl ServiceTable {
     /// figure out if there are currently-running services to which it
     /// needs to re-attach itself.
     pub fn pid_of(&self, service_name: &str) -> Option<u32> {
-        self.0.iter().find_map(|(pid, service)| {
-                         if service_name == service.args().id {
-                             Some(*pid)
-                         } else {
-                             None
-                         }
-                     })
+        self.services.iter().find_map(|(pid, service)| {
+                                if service_name == service.args().id {
+                                    Some(*pid)
+                                } else {
+                                    None
+                                }
+                            })
+    }
+
+    /// The PID of the running process that was spawned with `key`, if
+    /// there is one.
+    pub fn pid_of_key(&self, key: &str) -> Option<u32> {
+        self.services
+            .iter()
+            .find(|(_, service)| service.args().key.as_deref() == Some(key))
+            .map(|(pid, _)| *pid)
+    }
+
+    /// The number of open file descriptors of each running service,
+    /// where the platform exposes it.
+    pub fn fd_counts(&self) -> Vec<protocol::FdCount> {
+        self.services
+            .values()
+            .map(|service| {
+                protocol::FdCount { service_name: service.name().to_string(),
+                                    pid:          service.id(),
+                                    count:        service.open_fd_count(), }
+            })
+            .collect()
+    }
+
+    pub fn uptimes(&self) -> Vec<protocol::ProcessUptime> {
+        self.services
+            .values()
+            .map(|service| {
+                let since_epoch = service.started()
+                                         .duration_since(UNIX_EPOCH)
+                                         .unwrap_or_default();
+                let uptime = service.started().elapsed().unwrap_or_default();
+                protocol::ProcessUptime { service_name: service.name().to_string(),
+                                          pid:          service.id(),
+                                          started_at:   since_epoch.as_secs(),
+                                          uptime_secs:  uptime.as_secs(),
+                                          restarts:     service.restarts(), }
+            })
+            .collect()
+    }
+
+    pub fn restart_history(&self) -> Vec<protocol::ProcessRestarts> {
+        self.services
+            .values()
+            .map(|service| {
+                protocol::ProcessRestarts { service_name: service.name().to_string(),
+                                            pid:          service.id(),
+                                            restarts:     service.restarts(),
+                                            recent:       service.restart_history()
+                                                                 .iter()
+                                                                 .cloned()
+                                                                 .collect(), }
+            })
+            .collect()
+    }
+
+    /// Every running service, with what a Supervisor needs to adopt
+    /// it.
+    pub fn list_services(&self) -> Vec<protocol::SupervisedService> {
+        self.services
+            .values()
+            .map(|service| {
+                let started_at = service.started()
+                                        .duration_since(UNIX_EPOCH)
+                                        .unwrap_or_default();
+                let spawn = protocol::Spawn { svc_password: None,
+                                              ..service.args().clone() };
+                let cgroup_path = service.cgroup()
+                                         .map(|path| path.to_string_lossy().into_owned());
+                protocol::SupervisedService { pid: service.id(),
+                                              spawn,
+                                              started_at: started_at.as_secs(),
+                                              cgroup_path,
+                                              readiness: service.readiness(),
+                                              prior_supervisor: service.owner() < self.supervisor }
+            })
+            .collect()
+    }
+
+    /// How many supervised processes are running, counted in one pass
+    /// over the table, and how many have stopped or crashed since the
+    /// Launcher started.
+    pub fn health_summary(&mut self) -> protocol::HealthSummaryReport {
+        let running = self.services
+                          .values_mut()
+                          .filter(|service| matches!(service.try_wait(), Ok(None)))
+                          .count();
+        protocol::HealthSummaryReport { running: running as u32,
+                                        stopped: self.stopped,
+                                        crashed: self.crashed, }
+    }
+
+    pub fn diagnostics(&mut self) -> &mut Diagnostics { &mut self.diagnostics }
+
+    /// Push every exit from now on to the Supervisor.
+    pub fn subscribe_exits(&mut self) { self.subscribed = true; }
+
+    fn unsubscribe_exits(&mut self) {
+        self.subscribed = false;
+        self.exits.clear();
+    }
+
+    /// Note that the Supervisor has been replaced, so that the services
+    /// spawned until now were spawned for a prior one.
+    fn supervisor_replaced(&mut self) { self.supervisor += 1; }
+
+    /// The exits to push to the Supervisor since they were last taken.
+    fn take_exits(&mut self) -> Vec<protocol::ProcessExited> { std::mem::take(&mut self.exits) }
+
+    /// Advance the readiness probes of services that are still
+    /// starting up.
+    fn check_readiness(&mut self) {
+        for service in self.services.values_mut() {
+            match service.check_readiness() {
+                Some(protocol::ReadinessState::Ready) => {
+                    outputln!(preamble service.name(), "Ready");
+                }
+                Some(protocol::ReadinessState::TimedOut) => {
+                    outputln!(preamble service.name(), "Readiness probe timed out");
+                    self.diagnostics
+                        .record(protocol::DiagnosticKind::ReadinessTimedOut,
+                                service.name(),
+                                "readiness probe did not pass before its timeout");
+                }
+                _ => (),
+            }
+        }
     }
 
     fn kill_all(&mut self) {
-        for service in self.0.values_mut() {
+        for service in self.services.values_mut() {
             outputln!(preamble service.name(), "Stopping...");
             let shutdown_method = service.kill();
             outputln!(preamble service.name(), "Shutdown OK: {}", shutdown_method);
@@ -421,7 +635,7 @@ impl ServiceTable {
 
     fn reap_services(&mut self) {
         let mut dead: Vec<u32> = vec![];
-        for service in self.0.values_mut() {
+        for service in self.services.values_mut() {
             match service.try_wait() {
                 Ok(None) => (),
                 Ok(Some(code)) => {
@@ -429,16 +643,29 @@ impl ServiceTable {
                               service.name(),
                               service.id(),
                               code);
+                    if service.killed() || code.success() {
+                        self.stopped += 1;
+                    } else {
+                        self.crashed += 1;
+                    }
+                    if self.subscribed {
+                        self.exits.push(service.exit_notification(&code));
+                    }
                     dead.push(service.id());
                 }
                 Err(err) => {
                     warn!("Error waiting for child, {}, {}", service.id(), err);
+                    self.diagnostics
+                        .record(protocol::DiagnosticKind::ReapFailed, service.name(), &err);
+                    self.crashed += 1;
                     dead.push(service.id());
                 }
             }
         }
         for pid in dead {
-            self.0.remove(&pid);
+            if let Some(service) = self.services.remove(&pid) {
+                service.remove_cgroup();
+            }
         }
     }
 }
@@ -481,12 +708,13 @@ pub fn send<T>(tx: &Sender, msg: &T) -> Result<()>
                                                        message: {0}",
                                                       err)
                                           })?;
-    let bytes = msg.to_bytes().map_err(|err| {
-                                   anyhow!("Failed to serialize launcher protocol message \
-                                            payload: {0}",
-                                           err)
-                               })?;
-    tx.send(bytes)
+    let bytes = msg.to_framed_bytes(tx.framing.get()).map_err(|err| {
+                                                          anyhow!("Failed to serialize launcher \
+                                                                   protocol message payload: {0}",
+                                                                  err)
+                                                      })?;
+    tx.tx
+      .send(bytes)
       .context("Failed to send IPC message to supervisor")?;
     Ok(())
 }
@@ -496,7 +724,7 @@ pub fn send<T>(tx: &Sender, msg: &T) -> Result<()>
 //
 
 fn dispatch(tx: &Sender, bytes: &[u8], services: &mut ServiceTable) {
-    let msg = match protocol::NetTxn::from_bytes(bytes) {
+    let msg = match protocol::NetTxn::from_framed_bytes(bytes, tx.framing.get()) {
         Ok(msg) => msg,
         Err(err) => {
             error!("Unable to decode NetTxn from Supervisor, {}", err);
@@ -504,11 +732,32 @@ fn dispatch(tx: &Sender, bytes: &[u8], services: &mut ServiceTable) {
         }
     };
     let func = match msg.message_id() {
+        "EnableFraming" => {
+            enable_framing(tx, &msg);
+            return;
+        }
         "Restart" => handlers::RestartHandler::run,
         "Spawn" => handlers::SpawnHandler::run,
         "Terminate" => handlers::TerminateHandler::run,
         "PidOf" => handlers::PidHandler::run,
         "Version" => handlers::VersionHandler::run,
+        "FdCounts" => handlers::FdCountsHandler::run,
+        "Capabilities" => handlers::CapabilitiesHandler::run,
+        "LauncherConfig" => handlers::ConfigHandler::run,
+        "ProcessUptimes" => handlers::UptimesHandler::run,
+        "RestartWith" => handlers::RestartWithHandler::run,
+        "Diagnostics" => handlers::DiagnosticsHandler::run,
+        "ReadinessOf" => handlers::ReadinessHandler::run,
+        "RestartHistory" => handlers::RestartHistoryHandler::run,
+        "Preload" => handlers::PreloadHandler::run,
+        "ValidatePid" => handlers::ValidatePidHandler::run,
+        "HealthSummary" => handlers::HealthSummaryHandler::run,
+        "SchedulingOf" => handlers::SchedulingHandler::run,
+        "SetScheduling" => handlers::SetSchedulingHandler::run,
+        "Ping" => handlers::PingHandler::run,
+        "SubscribeExits" => handlers::SubscribeExitsHandler::run,
+        "ResourceUsage" => handlers::ResourceUsageHandler::run,
+        "ListServices" => handlers::ListServicesHandler::run,
         unknown => {
             // This sucks a bit because it replicates some code from the
             // Handler trait, but manipulating an unknown message
@@ -526,6 +775,37 @@ fn dispatch(tx: &Sender, bytes: &[u8], services: &mut ServiceTable) {
     func(tx, msg, services);
 }
 
+/// Acknowledge an `EnableFraming` request unframed, then frame every
+/// later message on the connection.
+fn enable_framing(tx: &Sender, txn: &protocol::NetTxn) {
+    let msg = match txn.decode::<protocol::EnableFraming>() {
+        Ok(msg) => msg,
+        Err(err) => {
+            error!("{}: decoding, {}", txn.message_id(), err);
+            return;
+        }
+    };
+    if msg.version != protocol::FRAMING_VERSION {
+        let msg = format!("Unsupported framing version {}, expected {}",
+                          msg.version,
+                          protocol::FRAMING_VERSION);
+        warn!("{}", msg);
+        let reply = protocol::NetErr { code: protocol::ErrCode::InvalidVersionNumber,
+                                       msg };
+        if let Err(err) = send(tx, &reply) {
+            error!("{}: replying, {}", txn.message_id(), err);
+        }
+        return;
+    }
+    match send(tx, &protocol::NetOk::default()) {
+        Ok(()) => {
+            debug!("Switching Supervisor IPC to length-prefixed framing");
+            tx.framing.set(protocol::Framing::LengthPrefixed);
+        }
+        Err(err) => error!("{}: replying, {}", txn.message_id(), err),
+    }
+}
+
 #[allow(clippy::mutex_atomic)] // A Mutex is required for Condvar::wait_timeout
 fn setup_connection(server: IpcOneShotServer<Vec<u8>>) -> Result<(Receiver, Sender)> {
     let pair = Arc::new((Mutex::new(false), Condvar::new()));
@@ -553,8 +833,9 @@ fn setup_connection(server: IpcOneShotServer<Vec<u8>>) -> Result<(Receiver, Send
                                                                   payload from supervisor: {}",
                                                                  err)
                                                      })?;
-        let tx = IpcSender::connect(msg.pipe).context("Failed to establish IPC connection to \
-                                                       the supervisor")?;
+        let tx = IpcSender::connect(msg.pipe).map(Sender::new)
+                                             .context("Failed to establish IPC connection to the \
+                                                       supervisor")?;
         send(&tx, &protocol::NetOk::default())?;
         {
             let (_, ref cvar) = *pair2;
@@ -565,10 +846,7 @@ fn setup_connection(server: IpcOneShotServer<Vec<u8>>) -> Result<(Receiver, Send
     });
 
     let (ref lock, ref cvar) = *pair;
-    let timeout_secs =
-        core::env::var(IPC_CONNECT_TIMEOUT_SECS).unwrap_or_default()
-                                                .parse()
-                                                .unwrap_or(DEFAULT_IPC_CONNECT_TIMEOUT_SECS);
+    let timeout_secs = ipc_connect_timeout_secs();
 
     debug!("Waiting on connect thread for {} secs", timeout_secs);
     let (started, wait_result) = cvar.wait_timeout(lock.lock().expect("IPC connection startup \
@@ -585,6 +863,32 @@ fn setup_connection(server: IpcOneShotServer<Vec<u8>>) -> Result<(Receiver, Send
     }
 }
 
+/// How long to wait for a Supervisor to connect to the Launcher.
+fn ipc_connect_timeout_secs() -> u64 {
+    core::env::var(IPC_CONNECT_TIMEOUT_SECS).unwrap_or_default()
+                                            .parse()
+                                            .unwrap_or(DEFAULT_IPC_CONNECT_TIMEOUT_SECS)
+}
+
+/// The configuration this Launcher is running with.
+fn config() -> protocol::LauncherConfigIs {
+    let launcher_root = launcher_root_path(Some(&*FS_ROOT_PATH));
+    protocol::LauncherConfigIs { schema_version:
+                                     protocol::LauncherConfigIs::SCHEMA_VERSION,
+                                 version:                  crate::VERSION.map(str::to_string),
+                                 fs_root:                  FS_ROOT_PATH.display().to_string(),
+                                 pid_file:                 launcher_root.join("PID")
+                                                                        .display()
+                                                                        .to_string(),
+                                 launcher_root:            launcher_root.display().to_string(),
+                                 sup_binary:               core::env::var(SUP_CMD_ENVVAR).ok(),
+                                 sup_version_check:
+                                     core::env::var(SUP_VERSION_CHECK_DISABLE).is_err(),
+                                 sup_version_req:          SUP_VERSION_REQ.to_string(),
+                                 ipc_connect_timeout_secs: ipc_connect_timeout_secs(),
+                                 capabilities:             handlers::capabilities(), }
+}
+
 /// Return whether the given version string matches SUP_VERSION_REQ parsed as
 /// a semver::VersionReq.
 ///
@@ -1,16 +1,48 @@
+mod capabilities;
+mod config;
+mod diagnostics;
+mod fd_counts;
+mod health_summary;
+mod list_services;
 mod pid;
+mod ping;
+mod preload;
+mod readiness;
+mod resource_usage;
 mod restart;
+mod restart_history;
+mod restart_with;
+mod scheduling;
 mod spawn;
+mod subscribe_exits;
 mod terminate;
+mod uptimes;
+mod validate_pid;
 mod version;
 
 use log::{error,
           trace};
 
-pub use self::{pid::*,
+pub use self::{capabilities::*,
+               config::*,
+               diagnostics::*,
+               fd_counts::*,
+               health_summary::*,
+               list_services::*,
+               pid::*,
+               ping::*,
+               preload::*,
+               readiness::*,
+               resource_usage::*,
                restart::*,
+               restart_history::*,
+               restart_with::*,
+               scheduling::*,
                spawn::*,
+               subscribe_exits::*,
                terminate::*,
+               uptimes::*,
+               validate_pid::*,
                version::*};
 
 use crate::protocol;
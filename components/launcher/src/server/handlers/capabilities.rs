@@ -0,0 +1,52 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            server::ServiceTable};
+
+pub struct CapabilitiesHandler;
+
+impl Handler for CapabilitiesHandler {
+    type Message = protocol::Capabilities;
+    type Reply = protocol::CapabilityList;
+
+    fn handle(_msg: Self::Message, _services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        Ok(protocol::CapabilityList { capabilities:     capabilities(),
+                                      max_message_size: Some(protocol::MAX_FRAME_SIZE as u64),
+                                      protocol_version: Some(protocol::PROTOCOL_VERSION), })
+    }
+}
+
+/// The optional features this Launcher supports.
+pub fn capabilities() -> Vec<String> {
+    let mut capabilities = vec![protocol::capability::RESTART_WITH.to_string(),
+                                protocol::capability::FRAMING.to_string(),
+                                protocol::capability::DIAGNOSTICS.to_string(),
+                                protocol::capability::READINESS_PROBE.to_string(),
+                                protocol::capability::SPAWN_KEY.to_string(),
+                                protocol::capability::RESTART_HISTORY.to_string(),
+                                protocol::capability::PRELOAD.to_string(),
+                                protocol::capability::VALIDATE_PID.to_string(),
+                                protocol::capability::HEALTH_SUMMARY.to_string(),
+                                protocol::capability::EARLY_EXIT.to_string(),
+                                protocol::capability::PING.to_string(),
+                                protocol::capability::EXIT_NOTIFICATIONS.to_string(),
+                                protocol::capability::LIST_SERVICES.to_string(),
+                                protocol::capability::FD_COUNTS.to_string(),
+                                protocol::capability::UPTIMES.to_string(),
+                                protocol::capability::CONFIG.to_string()];
+    if cfg!(target_os = "linux") {
+        capabilities.push(protocol::capability::CPU_AFFINITY.to_string());
+        capabilities.push(protocol::capability::SCHEDULING.to_string());
+        capabilities.push(protocol::capability::PRIVILEGES.to_string());
+    }
+    if cfg!(unix) {
+        capabilities.push(protocol::capability::SPAWN_ARGS.to_string());
+        capabilities.push(protocol::capability::RESOURCE_USAGE.to_string());
+    }
+    // Which cgroup hierarchy is mounted is up to the host.
+    #[cfg(target_os = "linux")]
+    if crate::cgroup::supported() {
+        capabilities.push(protocol::capability::CGROUP.to_string());
+    }
+    capabilities
+}
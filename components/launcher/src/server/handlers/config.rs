@@ -0,0 +1,15 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            server::ServiceTable};
+
+pub struct ConfigHandler;
+
+impl Handler for ConfigHandler {
+    type Message = protocol::LauncherConfig;
+    type Reply = protocol::LauncherConfigIs;
+
+    fn handle(_msg: Self::Message, _services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        Ok(crate::server::config())
+    }
+}
@@ -0,0 +1,15 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            server::ServiceTable};
+
+pub struct DiagnosticsHandler;
+
+impl Handler for DiagnosticsHandler {
+    type Message = protocol::Diagnostics;
+    type Reply = protocol::DiagnosticReport;
+
+    fn handle(msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        Ok(services.diagnostics().report(msg.clear))
+    }
+}
@@ -0,0 +1,16 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            server::ServiceTable};
+
+pub struct FdCountsHandler;
+
+impl Handler for FdCountsHandler {
+    type Message = protocol::FdCounts;
+    type Reply = protocol::FdCountReport;
+
+    fn handle(_msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        let reply = protocol::FdCountReport { counts: services.fd_counts(), };
+        Ok(reply)
+    }
+}
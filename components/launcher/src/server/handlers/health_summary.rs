@@ -0,0 +1,15 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            server::ServiceTable};
+
+pub struct HealthSummaryHandler;
+
+impl Handler for HealthSummaryHandler {
+    type Message = protocol::HealthSummary;
+    type Reply = protocol::HealthSummaryReport;
+
+    fn handle(_msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        Ok(services.health_summary())
+    }
+}
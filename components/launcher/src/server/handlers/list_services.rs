@@ -0,0 +1,15 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            server::ServiceTable};
+
+pub struct ListServicesHandler;
+
+impl Handler for ListServicesHandler {
+    type Message = protocol::ListServices;
+    type Reply = protocol::ServiceList;
+
+    fn handle(_msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        Ok(protocol::ServiceList { services: services.list_services(), })
+    }
+}
@@ -0,0 +1,15 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            server::ServiceTable};
+
+pub struct PingHandler;
+
+impl Handler for PingHandler {
+    type Message = protocol::Ping;
+    type Reply = protocol::NetOk;
+
+    fn handle(_msg: Self::Message, _services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        Ok(protocol::NetOk::default())
+    }
+}
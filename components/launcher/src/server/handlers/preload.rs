@@ -0,0 +1,16 @@
+use super::{HandleResult,
+            Handler};
+use crate::{preload,
+            protocol,
+            server::ServiceTable};
+
+pub struct PreloadHandler;
+
+impl Handler for PreloadHandler {
+    type Message = protocol::Preload;
+    type Reply = protocol::PreloadOk;
+
+    fn handle(msg: Self::Message, _services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        preload::preload(&msg).map_err(protocol::error)
+    }
+}
@@ -0,0 +1,22 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            server::ServiceTable};
+
+pub struct ReadinessHandler;
+
+impl Handler for ReadinessHandler {
+    type Message = protocol::ReadinessOf;
+    type Reply = protocol::ReadinessIs;
+
+    fn handle(msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        match services.get(msg.pid as u32) {
+            Some(service) => Ok(protocol::ReadinessIs { readiness: service.readiness(), }),
+            None => {
+                let reply = protocol::NetErr { code: protocol::ErrCode::NoPid,
+                                               ..Default::default() };
+                Err(reply)
+            }
+        }
+    }
+}
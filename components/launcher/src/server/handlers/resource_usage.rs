@@ -0,0 +1,16 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            resource_usage,
+            server::ServiceTable};
+
+pub struct ResourceUsageHandler;
+
+impl Handler for ResourceUsageHandler {
+    type Message = protocol::ResourceUsage;
+    type Reply = protocol::ResourceUsageReport;
+
+    fn handle(_msg: Self::Message, _services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        Ok(resource_usage::measure())
+    }
+}
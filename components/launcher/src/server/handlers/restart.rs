@@ -19,16 +19,26 @@ impl Handler for RestartHandler {
                 return Err(reply);
             }
         };
+        let restarts = service.restarts();
+        let history = service.take_restart_history();
         service.kill();
         match service.wait() {
-            Ok(_status) => {
-                match service::run(service.take_args()) {
-                    Ok(new_service) => {
-                        let reply = protocol::SpawnOk { pid: new_service.id().into(), };
+            Ok(status) => {
+                let event = service.restart_event(protocol::RestartReason::Requested, &status);
+                let args = service.take_args();
+                let id = args.id.clone();
+                match service::run(args) {
+                    Ok(mut new_service) => {
+                        new_service.restarted_from(restarts, history, event);
+                        let reply = new_service.spawn_ok(false);
                         services.insert(new_service);
                         Ok(reply)
                     }
-                    Err(err) => Err(protocol::error(err)),
+                    Err(err) => {
+                        services.diagnostics()
+                                .record(protocol::DiagnosticKind::RestartFailed, &id, &err);
+                        Err(protocol::error(err))
+                    }
                 }
             }
             Err(_) => {
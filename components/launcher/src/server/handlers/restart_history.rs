@@ -0,0 +1,15 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            server::ServiceTable};
+
+pub struct RestartHistoryHandler;
+
+impl Handler for RestartHistoryHandler {
+    type Message = protocol::RestartHistory;
+    type Reply = protocol::RestartHistoryReport;
+
+    fn handle(_msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        Ok(protocol::RestartHistoryReport { processes: services.restart_history(), })
+    }
+}
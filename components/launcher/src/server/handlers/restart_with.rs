@@ -0,0 +1,63 @@
+use crate::protocol;
+
+use super::{HandleResult,
+            Handler};
+use crate::{server::ServiceTable,
+            service};
+
+pub struct RestartWithHandler;
+impl Handler for RestartWithHandler {
+    type Message = protocol::RestartWith;
+    type Reply = protocol::RestartWithResult;
+
+    fn handle(msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        if services.get(msg.pid as u32).is_none() {
+            let reply = protocol::NetErr { code: protocol::ErrCode::NoPid,
+                                           ..Default::default() };
+            return Err(reply);
+        }
+        // A spec that can't be started never costs the running process
+        if let Err(err) = service::validate(&msg.spawn) {
+            services.diagnostics()
+                    .record(protocol::DiagnosticKind::RestartFailed, &msg.spawn.id, &err);
+            return Ok(protocol::RestartWithResult::StartFailed(protocol::error(err)));
+        }
+        let mut service = services.remove(msg.pid as u32)
+                                  .expect("Service removed from the table while handling");
+        service.kill();
+        let status = match service.wait() {
+            Ok(status) => status,
+            Err(_) => {
+                // Left for the reaper, as if it had never been asked
+                // to restart
+                services.insert(service);
+                let reply = protocol::NetErr { code: protocol::ErrCode::ExecWait,
+                                               ..Default::default() };
+                return Err(reply);
+            }
+        };
+        let event = service.restart_event(protocol::RestartReason::Respecified, &status);
+        // The old process is gone, so from here on a failure is part of
+        // the reply rather than an error; the Supervisor has to know
+        // that nothing is running any more.
+        let id = msg.spawn.id.clone();
+        match service::run(msg.spawn) {
+            Ok(mut new_service) => {
+                new_service.restarted_from(service.restarts(),
+                                           service.take_restart_history(),
+                                           event);
+                let reply = protocol::RestartWithResult::Restarted(new_service.id().into());
+                services.insert(new_service);
+                Ok(reply)
+            }
+            Err(err) => {
+                services.diagnostics()
+                        .record(protocol::DiagnosticKind::RestartFailed, &id, &err);
+                // Kept in the table, so that its exit is reported like
+                // any other
+                services.insert(service);
+                Ok(protocol::RestartWithResult::StartFailed(protocol::error(err)))
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use super::{HandleResult,
+            Handler};
+use crate::{error::ServiceRunError,
+            protocol,
+            server::ServiceTable};
+
+pub struct SchedulingHandler;
+
+impl Handler for SchedulingHandler {
+    type Message = protocol::SchedulingOf;
+    type Reply = protocol::SchedulingIs;
+
+    fn handle(msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        match services.get(msg.pid as u32) {
+            Some(service) => {
+                let scheduling = query(service.id()).map_err(protocol::error)?;
+                Ok(protocol::SchedulingIs { scheduling })
+            }
+            None => {
+                let reply = protocol::NetErr { code: protocol::ErrCode::NoPid,
+                                               ..Default::default() };
+                Err(reply)
+            }
+        }
+    }
+}
+
+pub struct SetSchedulingHandler;
+
+impl Handler for SetSchedulingHandler {
+    type Message = protocol::SetScheduling;
+    type Reply = protocol::SchedulingIs;
+
+    fn handle(msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        match services.get(msg.pid as u32) {
+            Some(service) => {
+                apply(service.name(), service.id(), &msg.scheduling).map_err(protocol::error)?;
+                let scheduling = query(service.id()).map_err(protocol::error)?;
+                Ok(protocol::SchedulingIs { scheduling })
+            }
+            None => {
+                let reply = protocol::NetErr { code: protocol::ErrCode::NoPid,
+                                               ..Default::default() };
+                Err(reply)
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn query(pid: u32) -> Result<protocol::Scheduling, ServiceRunError> {
+    crate::scheduling::query(pid)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn query(_pid: u32) -> Result<protocol::Scheduling, ServiceRunError> {
+    Err(ServiceRunError::InvalidScheduling(String::from("not supported on this platform")))
+}
+
+/// Schedule the process `pid` of the service `id` as `scheduling`, if
+/// the service is allowed it.
+#[cfg(target_os = "linux")]
+fn apply(id: &str, pid: u32, scheduling: &protocol::Scheduling) -> Result<(), ServiceRunError> {
+    crate::scheduling::check(id, scheduling)?;
+    crate::scheduling::apply(pid, scheduling)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn apply(_id: &str, _pid: u32, _scheduling: &protocol::Scheduling) -> Result<(), ServiceRunError> {
+    Err(ServiceRunError::InvalidScheduling(String::from("not supported on this platform")))
+}
@@ -1,3 +1,5 @@
+use log::debug;
+
 use crate::protocol;
 
 use super::{HandleResult,
@@ -11,13 +13,37 @@ impl Handler for SpawnHandler {
     type Reply = protocol::SpawnOk;
 
     fn handle(msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
-        match service::run(msg) {
+        let id = msg.id.clone();
+        if let Some(service) = msg.key
+                                  .as_deref()
+                                  .and_then(|key| services.pid_of_key(key))
+                                  .and_then(|pid| services.get(pid))
+        {
+            debug!("Not spawning {} again; PID {} was spawned for the same key",
+                   id,
+                   service.id());
+            return Ok(service.spawn_ok(true));
+        }
+        let spawned = service::run(msg).and_then(|mut service| {
+                                           match service.exited_early() {
+                                               Some(err) => {
+                                                   service.remove_cgroup();
+                                                   Err(err)
+                                               }
+                                               None => Ok(service),
+                                           }
+                                       });
+        match spawned {
             Ok(service) => {
-                let reply = protocol::SpawnOk { pid: service.id().into(), };
+                let reply = service.spawn_ok(false);
                 services.insert(service);
                 Ok(reply)
             }
-            Err(err) => Err(protocol::error(err)),
+            Err(err) => {
+                services.diagnostics()
+                        .record(protocol::DiagnosticKind::SpawnFailed, &id, &err);
+                Err(protocol::error(err))
+            }
         }
     }
 }
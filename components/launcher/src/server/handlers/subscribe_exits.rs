@@ -0,0 +1,16 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            server::ServiceTable};
+
+pub struct SubscribeExitsHandler;
+
+impl Handler for SubscribeExitsHandler {
+    type Message = protocol::SubscribeExits;
+    type Reply = protocol::NetOk;
+
+    fn handle(_msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        services.subscribe_exits();
+        Ok(protocol::NetOk::default())
+    }
+}
@@ -0,0 +1,15 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            server::ServiceTable};
+
+pub struct UptimesHandler;
+
+impl Handler for UptimesHandler {
+    type Message = protocol::ProcessUptimes;
+    type Reply = protocol::ProcessUptimeReport;
+
+    fn handle(_msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        Ok(protocol::ProcessUptimeReport { uptimes: services.uptimes(), })
+    }
+}
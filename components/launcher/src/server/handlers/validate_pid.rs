@@ -0,0 +1,32 @@
+use super::{HandleResult,
+            Handler};
+use crate::{protocol,
+            server::ServiceTable};
+use std::time::UNIX_EPOCH;
+
+pub struct ValidatePidHandler;
+
+impl Handler for ValidatePidHandler {
+    type Message = protocol::ValidatePid;
+    type Reply = protocol::PidValidity;
+
+    fn handle(msg: Self::Message, services: &mut ServiceTable) -> HandleResult<Self::Reply> {
+        // The Launcher doesn't reap a process it spawned until it has
+        // removed it from the table, so a PID that is in the table
+        // can't have been reused.
+        let valid = match services.get_mut(msg.pid as u32) {
+            Some(service) => {
+                let started_at = service.started()
+                                        .duration_since(UNIX_EPOCH)
+                                        .unwrap_or_default()
+                                        .as_secs();
+                service.name() == msg.service_name
+                && msg.started_at
+                      .map_or(true, |expected| expected == started_at)
+                && matches!(service.try_wait(), Ok(None))
+            }
+            None => false,
+        };
+        Ok(protocol::PidValidity { valid })
+    }
+}
@@ -3,31 +3,63 @@ use crate::core::os::process::windows_child::{ChildStderr,
                                               ChildStdout,
                                               ExitStatus};
 use crate::{core::util::BufReadLossy,
-            protocol};
+            early_exit::{self,
+                         StderrTail},
+            error::ServiceRunError,
+            protocol,
+            readiness::Readiness};
 use habitat_common::output::{self,
                              StructuredOutput};
+use log::debug;
 #[cfg(unix)]
 use std::process::{ChildStderr,
                    ChildStdout,
                    ExitStatus};
-use std::{fmt,
+use std::{collections::VecDeque,
+          fmt,
           io::{self,
                BufReader,
                Read},
-          thread};
+          path::{Path,
+                 PathBuf},
+          sync::Arc,
+          thread,
+          time::{Duration,
+                 Instant,
+                 SystemTime,
+                 UNIX_EPOCH}};
 
 pub use crate::sys::service::*;
 
+/// The most restarts of a service that are kept in its history.
+const MAX_RESTART_HISTORY: usize = 20;
+
 pub struct Service {
-    args:    protocol::Spawn,
-    process: Process,
+    args:      protocol::Spawn,
+    process:   Process,
+    started:   SystemTime,
+    restarts:  u32,
+    /// The most recent restarts, oldest first.
+    history:   VecDeque<protocol::RestartEvent>,
+    readiness: Option<Readiness>,
+    /// The cgroup the process was placed in, if it was given limits.
+    cgroup:    Option<PathBuf>,
+    /// Whether the Launcher has asked this process to stop.
+    killed:    bool,
+    /// What the process writes to standard error, while it is watched
+    /// for exiting straight away.
+    stderr:    Option<Arc<StderrTail>>,
+    /// The Supervisor the process was spawned for, counting from the
+    /// first one the Launcher started.
+    owner:     u32,
 }
 
 impl Service {
     pub fn new(spawn: protocol::Spawn,
                process: Process,
                stdout: Option<ChildStdout>,
-               stderr: Option<ChildStderr>)
+               stderr: Option<ChildStderr>,
+               cgroup: Option<PathBuf>)
                -> Self {
         if let Some(stdout) = stdout {
             let id = spawn.id.to_string();
@@ -35,23 +67,186 @@ impl Service {
                                   .spawn(move || pipe_stdout(stdout, &id))
                                   .ok();
         }
+        let tail = spawn.early_exit_window_ms
+                        .map(|_| Arc::new(StderrTail::default()));
         if let Some(stderr) = stderr {
             let id = spawn.id.to_string();
+            let tail = tail.clone();
             thread::Builder::new().name(format!("{}-err", spawn.id))
-                                  .spawn(move || pipe_stderr(stderr, &id))
+                                  .spawn(move || pipe_stderr(stderr, &id, tail.as_deref()))
                                   .ok();
         }
+        let readiness = spawn.readiness.clone().map(Readiness::new);
         Service { args: spawn,
-                  process }
+                  process,
+                  started: SystemTime::now(),
+                  restarts: 0,
+                  history: VecDeque::new(),
+                  readiness,
+                  cgroup,
+                  killed: false,
+                  stderr: tail,
+                  owner: 0 }
     }
 
     pub fn args(&self) -> &protocol::Spawn { &self.args }
 
     pub fn id(&self) -> u32 { self.process.id() }
 
+    pub fn open_fd_count(&self) -> Option<u32> { self.process.open_fd_count() }
+
+    pub fn cgroup(&self) -> Option<&Path> { self.cgroup.as_deref() }
+
+    /// Remove this process's cgroup, once the process has exited.
+    pub fn remove_cgroup(&self) {
+        #[cfg(target_os = "linux")]
+        if let Some(cgroup) = &self.cgroup {
+            crate::cgroup::remove(cgroup);
+        }
+    }
+
+    /// The reply to a spawn that resulted in this process.
+    pub fn spawn_ok(&self, duplicate_spawn: bool) -> protocol::SpawnOk {
+        protocol::SpawnOk { pid: self.id().into(),
+                            duplicate_spawn,
+                            cgroup_path: self.cgroup
+                                             .as_ref()
+                                             .map(|path| path.to_string_lossy().into_owned()) }
+    }
+
+    /// When this process was started.
+    pub fn started(&self) -> SystemTime { self.started }
+
+    /// The Supervisor this process was spawned for.
+    pub fn owner(&self) -> u32 { self.owner }
+
+    /// Record that this process was spawned for the Supervisor `owner`.
+    pub fn spawned_for(&mut self, owner: u32) { self.owner = owner; }
+
+    /// How many times the Launcher has restarted this service.
+    pub fn restarts(&self) -> u32 { self.restarts }
+
+    /// The most recent restarts of this service, oldest first.
+    pub fn restart_history(&self) -> &VecDeque<protocol::RestartEvent> { &self.history }
+
+    /// Take the restart history of this process, to be carried over to
+    /// the process that replaces it.
+    pub fn take_restart_history(&mut self) -> VecDeque<protocol::RestartEvent> {
+        std::mem::take(&mut self.history)
+    }
+
+    /// Describe the restart of this process, which exited with
+    /// `status`.
+    pub fn restart_event(&self,
+                         reason: protocol::RestartReason,
+                         status: &ExitStatus)
+                         -> protocol::RestartEvent {
+        let at = SystemTime::now().duration_since(UNIX_EPOCH)
+                                  .unwrap_or_default();
+        protocol::RestartEvent { at: at.as_secs(),
+                                 reason,
+                                 previous_pid: self.id(),
+                                 exit_code: status.code() }
+    }
+
+    /// Describe the exit of this process with `status`, for a
+    /// Supervisor subscribed to exits.
+    pub fn exit_notification(&self, status: &ExitStatus) -> protocol::ProcessExited {
+        #[cfg(unix)]
+        let signal = std::os::unix::process::ExitStatusExt::signal(status);
+        #[cfg(not(unix))]
+        let signal = None;
+        protocol::ProcessExited { service_name: self.name().to_string(),
+                                  pid: self.id(),
+                                  exit_code: status.code(),
+                                  signal,
+                                  terminated: self.killed }
+    }
+
+    /// Record that this process is a restart of one that had itself
+    /// been restarted `restarts` times, with `history`, by `event`.
+    pub fn restarted_from(&mut self,
+                          restarts: u32,
+                          mut history: VecDeque<protocol::RestartEvent>,
+                          event: protocol::RestartEvent) {
+        while history.len() >= MAX_RESTART_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(event);
+        self.restarts = restarts + 1;
+        self.history = history;
+    }
+
+    /// Whether this process has passed the readiness probe it was
+    /// spawned with.
+    pub fn readiness(&self) -> protocol::ReadinessState {
+        self.readiness
+            .as_ref()
+            .map_or(protocol::ReadinessState::NoProbe, Readiness::state)
+    }
+
+    /// Advance this process's readiness probe, returning the new state
+    /// if it changed.
+    pub fn check_readiness(&mut self) -> Option<protocol::ReadinessState> {
+        let args = &self.args;
+        self.readiness
+            .as_mut()
+            .and_then(|readiness| readiness.poll(args))
+    }
+
+    /// Watch this newly spawned process for the early exit window it
+    /// was spawned with, returning why the spawn failed if it exited in
+    /// that time.
+    pub fn exited_early(&mut self) -> Option<ServiceRunError> {
+        let window = Duration::from_millis(self.args.early_exit_window_ms?.into());
+        let deadline = Instant::now() + window.min(early_exit::MAX_WINDOW);
+        let exited = loop {
+            match self.try_wait() {
+                Ok(Some(status)) => break Some(status),
+                Ok(None) if Instant::now() < deadline => thread::sleep(early_exit::POLL_INTERVAL),
+                Ok(None) => break None,
+                Err(err) => {
+                    debug!("Unable to watch {} for exiting early: {}", self.name(), err);
+                    break None;
+                }
+            }
+        };
+        let tail = self.stderr.take();
+        let status = match exited {
+            Some(status) => status,
+            None => {
+                if let Some(tail) = tail {
+                    tail.finish();
+                }
+                return None;
+            }
+        };
+        let how = match status.code() {
+            Some(code) => format!("with exit code {}", code),
+            None => String::from("after being killed by a signal"),
+        };
+        let stderr = tail.map(|tail| tail.drain()).unwrap_or_default();
+        let detail = if stderr.is_empty() {
+            how
+        } else {
+            format!("{}: {}", how, stderr.join("\n"))
+        };
+        Some(ServiceRunError::ExitedEarly(self.args.binary.clone(), detail))
+    }
+
     /// Attempt to gracefully terminate a proccess and then forcefully kill it after
     /// 8 seconds if it has not terminated.
-    pub fn kill(&mut self) -> protocol::ShutdownMethod { self.process.kill() }
+    pub fn kill(&mut self) -> protocol::ShutdownMethod {
+        if let Some(readiness) = self.readiness.as_mut() {
+            readiness.cancel();
+        }
+        self.killed = true;
+        self.process.kill()
+    }
+
+    /// Whether this process was stopped by the Launcher, rather than
+    /// exiting of its own accord.
+    pub fn killed(&self) -> bool { self.killed }
 
     pub fn name(&self) -> &str { &self.args.id }
 
@@ -89,13 +284,17 @@ fn pipe_stdout<T>(out: T, id: &str)
     }
 }
 
-/// Consume standard error from a child process until EOF, then finish
-fn pipe_stderr<T>(err: T, id: &str)
+/// Consume standard error from a child process until EOF, then finish.
+/// Lines are also kept in `tail`, if given.
+fn pipe_stderr<T>(err: T, id: &str, tail: Option<&StderrTail>)
     where T: Read
 {
     for line in BufReader::new(err).lines_lossy() {
         match line {
             Ok(line) => {
+                if let Some(tail) = tail {
+                    tail.push(&line);
+                }
                 let so = StructuredOutput::succinct(id, "E", output::get_format(), &line);
                 if let Err(e) = so.eprintln() {
                     println!("printing output: '{}' to stderr resulted in error: {}",
@@ -108,4 +307,7 @@ fn pipe_stderr<T>(err: T, id: &str)
             }
         }
     }
+    if let Some(tail) = tail {
+        tail.close();
+    }
 }
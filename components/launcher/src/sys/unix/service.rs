@@ -10,9 +10,13 @@ use habitat_core::os::{self,
 use log::debug;
 use nix::unistd::{Gid,
                   Uid};
-use std::{io,
+use std::{collections::BTreeMap,
+          io,
           ops::Neg,
+          path::{Path,
+                 PathBuf},
           process::{Child,
+                    Command,
                     ExitStatus},
           time::{Duration,
                  Instant}};
@@ -22,6 +26,24 @@ pub struct Process(Child);
 impl Process {
     pub fn id(&self) -> u32 { self.0.id() }
 
+    /// The number of file descriptors the process has open, where the
+    /// platform exposes it.
+    #[cfg(target_os = "linux")]
+    pub fn open_fd_count(&self) -> Option<u32> {
+        match std::fs::read_dir(format!("/proc/{}/fd", self.id())) {
+            Ok(entries) => Some(entries.count() as u32),
+            Err(err) => {
+                debug!("Unable to read open file descriptors of {}: {}",
+                       self.id(),
+                       err);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn open_fd_count(&self) -> Option<u32> { None }
+
     /// Attempt to gracefully terminate a process and then forcefully kill it after
     /// 8 seconds if it has not terminated.
     pub fn kill(&mut self) -> ShutdownMethod {
@@ -69,9 +91,75 @@ impl Process {
     pub fn wait(&mut self) -> io::Result<ExitStatus> { self.0.wait() }
 }
 
+/// Check what can be checked of `msg` without starting anything: that
+/// its binary is there, its user and group exist and its scheduling is
+/// allowed. A spawn can still fail after passing, but not for these.
+pub fn validate(msg: &protocol::Spawn) -> Result<(), ServiceRunError> {
+    let binary = Path::new(&msg.binary);
+    if binary.is_absolute() && !binary.is_file() {
+        return Err(ServiceRunError::BinaryNotFound(msg.binary.clone()));
+    }
+    ids(msg)?;
+    if let Some(scheduling) = &msg.scheduling {
+        check_scheduling(&msg.id, scheduling)?;
+    }
+    Ok(())
+}
+
 pub fn run(msg: protocol::Spawn) -> Result<Service, ServiceRunError> {
     debug!("launcher is spawning {}", msg.binary);
 
+    let (uid, gid) = ids(&msg)?;
+    let mut cmd = match &msg.privileges {
+        Some(privileges) => privileged_command(&msg.binary, &msg.env, uid, gid, privileges)?,
+        None => exec::unix::hook_command(&msg.binary, &msg.env, Some((uid, gid))),
+    };
+    cmd.args(&msg.args);
+    if let Some(cwd) = &msg.cwd {
+        cmd.current_dir(cwd);
+    }
+    if !msg.cpu_affinity.is_empty() {
+        set_cpu_affinity(&mut cmd, &msg.cpu_affinity)?;
+    }
+    if let Some(scheduling) = &msg.scheduling {
+        check_scheduling(&msg.id, scheduling)?;
+    }
+    // Last, so that nothing is left behind should anything above fail
+    let cgroup = match &msg.cgroup {
+        Some(limits) => Some(set_cgroup(&mut cmd, &msg.id, limits)?),
+        None => None,
+    };
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            if let Some(cgroup) = &cgroup {
+                remove_cgroup(cgroup);
+            }
+            return Err(ServiceRunError::spawn(err, &msg.binary));
+        }
+    };
+    if let Some(scheduling) = &msg.scheduling {
+        if let Err(err) = set_scheduling(child.id(), scheduling) {
+            // The service must not carry on with a scheduling policy it
+            // wasn't asked for.
+            child.kill().ok();
+            child.wait().ok();
+            if let Some(cgroup) = &cgroup {
+                remove_cgroup(cgroup);
+            }
+            return Err(err);
+        }
+    }
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let process = Process(child);
+    debug!(target: "pidfile_tracing", "Launcher spawned {} with PID = {}", msg.binary, process.id());
+    Ok(Service::new(msg, process, stdout, stderr, cgroup))
+}
+
+/// The user and group to run `msg`'s process as.
+fn ids(msg: &protocol::Spawn) -> Result<(Uid, Gid), ServiceRunError> {
     // Favor explicitly set UID/GID over names when present
     let user_id = if let Some(suid) = msg.svc_user_id {
         suid
@@ -99,14 +187,126 @@ pub fn run(msg: protocol::Spawn) -> Result<Service, ServiceRunError> {
     } else {
         return Err(ServiceRunError::GroupNotFound(String::from("")));
     };
-    let gid = Gid::from_raw(group_id);
+    Ok((uid, Gid::from_raw(group_id)))
+}
+
+/// The command running `program` as a readiness probe of `msg`'s
+/// process, as the same user and group.
+pub fn probe_command(msg: &protocol::Spawn, program: &str) -> Result<Command, ServiceRunError> {
+    Ok(exec::unix::hook_command(program, &msg.env, Some(ids(msg)?)))
+}
 
-    let mut cmd = exec::unix::hook_command(&msg.binary, &msg.env, Some((uid, gid)));
+/// Restrict the process `cmd` spawns to `cpus`, each of which must be
+/// available to the Launcher itself.
+#[cfg(target_os = "linux")]
+fn set_cpu_affinity(cmd: &mut Command, cpus: &[u32]) -> Result<(), ServiceRunError> {
+    use nix::{sched::{sched_getaffinity,
+                      sched_setaffinity,
+                      CpuSet},
+              unistd::Pid};
+    use std::os::unix::process::CommandExt;
 
-    let mut child = cmd.spawn().map_err(ServiceRunError::Spawn)?;
-    let stdout = child.stdout.take();
-    let stderr = child.stderr.take();
-    let process = Process(child);
-    debug!(target: "pidfile_tracing", "Launcher spawned {} with PID = {}", msg.binary, process.id());
-    Ok(Service::new(msg, process, stdout, stderr))
+    let available =
+        sched_getaffinity(Pid::from_raw(0)).map_err(|err| {
+            ServiceRunError::InvalidCpuAffinity(format!("unable to read available CPUs: {}", err))
+        })?;
+    let mut set = CpuSet::new();
+    for &cpu in cpus {
+        let cpu = cpu as usize;
+        if !available.is_set(cpu).unwrap_or(false) || set.set(cpu).is_err() {
+            return Err(ServiceRunError::InvalidCpuAffinity(format!("CPU {} is not available \
+                                                                    on this host",
+                                                                   cpu)));
+        }
+    }
+    // Setting the affinity between fork and exec means the service
+    // never runs outside of it. sched_setaffinity is a single system
+    // call, so it is safe to make there.
+    unsafe {
+        cmd.pre_exec(move || sched_setaffinity(Pid::from_raw(0), &set).map_err(io::Error::from));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_cpu_affinity(_cmd: &mut Command, _cpus: &[u32]) -> Result<(), ServiceRunError> {
+    Err(ServiceRunError::InvalidCpuAffinity(String::from("not supported on this platform")))
+}
+
+/// Run the process `cmd` spawns for the service `id` in a cgroup with
+/// `limits`, returning the cgroup's path.
+#[cfg(target_os = "linux")]
+fn set_cgroup(cmd: &mut Command,
+              id: &str,
+              limits: &protocol::CgroupLimits)
+              -> Result<PathBuf, ServiceRunError> {
+    let cgroup = crate::cgroup::create(id, limits)?;
+    if let Err(err) = crate::cgroup::join(cmd, &cgroup) {
+        crate::cgroup::remove(&cgroup);
+        return Err(err);
+    }
+    Ok(cgroup)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_cgroup(_cmd: &mut Command,
+              _id: &str,
+              _limits: &protocol::CgroupLimits)
+              -> Result<PathBuf, ServiceRunError> {
+    Err(ServiceRunError::CgroupUnsupported(String::from("not supported on this platform")))
+}
+
+/// Remove the cgroup of a process that failed to spawn, or that was
+/// killed straight after.
+#[cfg(target_os = "linux")]
+fn remove_cgroup(cgroup: &Path) { crate::cgroup::remove(cgroup) }
+
+#[cfg(not(target_os = "linux"))]
+fn remove_cgroup(_cgroup: &Path) {}
+
+/// The command running `binary` as `uid` and `gid`, with `privileges`.
+#[cfg(target_os = "linux")]
+fn privileged_command(binary: &str,
+                      env: &BTreeMap<String, String>,
+                      uid: Uid,
+                      gid: Gid,
+                      privileges: &protocol::Privileges)
+                      -> Result<Command, ServiceRunError> {
+    let privileges = crate::privileges::check(uid, gid, privileges)?;
+    let mut cmd = exec::unix::hook_command(binary, env, None);
+    crate::privileges::apply(&mut cmd, uid, gid, privileges);
+    Ok(cmd)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn privileged_command(_binary: &str,
+                      _env: &BTreeMap<String, String>,
+                      _uid: Uid,
+                      _gid: Gid,
+                      _privileges: &protocol::Privileges)
+                      -> Result<Command, ServiceRunError> {
+    Err(ServiceRunError::InvalidPrivileges(String::from("not supported on this platform")))
+}
+
+/// Check that the service `id` may be scheduled as `scheduling`, before
+/// it is spawned.
+#[cfg(target_os = "linux")]
+fn check_scheduling(id: &str, scheduling: &protocol::Scheduling) -> Result<(), ServiceRunError> {
+    crate::scheduling::check(id, scheduling)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn check_scheduling(_id: &str, _scheduling: &protocol::Scheduling) -> Result<(), ServiceRunError> {
+    Err(ServiceRunError::InvalidScheduling(String::from("not supported on this platform")))
+}
+
+/// Schedule the spawned process `pid` as `scheduling`.
+#[cfg(target_os = "linux")]
+fn set_scheduling(pid: u32, scheduling: &protocol::Scheduling) -> Result<(), ServiceRunError> {
+    crate::scheduling::apply(pid, scheduling)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_scheduling(_pid: u32, _scheduling: &protocol::Scheduling) -> Result<(), ServiceRunError> {
+    Err(ServiceRunError::InvalidScheduling(String::from("not supported on this platform")))
 }
@@ -14,6 +14,7 @@ use std::{collections::HashMap,
           env,
           io,
           mem,
+          process::Command,
           time::{Duration,
                  Instant}};
 use winapi::{shared::{minwindef::{DWORD,
@@ -48,6 +49,19 @@ impl Process {
 
     pub fn id(&self) -> u32 { unsafe { processthreadsapi::GetProcessId(self.handle.raw()) } }
 
+    /// The number of handles the process has open, which is the
+    /// closest Windows has to a count of open file descriptors.
+    pub fn open_fd_count(&self) -> Option<u32> {
+        let mut count: DWORD = 0;
+        if unsafe { processthreadsapi::GetProcessHandleCount(self.handle.raw(), &mut count) } == 0 {
+            debug!("Unable to count open handles of {}: {}",
+                   self.id(),
+                   io::Error::last_os_error());
+            return None;
+        }
+        Some(count)
+    }
+
     /// Attempt to gracefully terminate a process and then forcefully kill it after
     /// 8 seconds if it has not terminated.
     pub fn kill(&mut self) -> ShutdownMethod {
@@ -116,8 +130,47 @@ impl Process {
     }
 }
 
+/// Check what can be checked of `msg` without starting anything: that
+/// it asks for nothing this platform can't do and names a user to run
+/// as. A spawn can still fail after passing, but not for these.
+pub fn validate(msg: &protocol::Spawn) -> Result<(), ServiceRunError> {
+    if !msg.cpu_affinity.is_empty() {
+        return Err(ServiceRunError::InvalidCpuAffinity(String::from("not supported on this \
+                                                                     platform")));
+    }
+    if !msg.args.is_empty() || msg.cwd.is_some() {
+        return Err(ServiceRunError::Unsupported("spawn arguments and working directory"));
+    }
+    if msg.cgroup.is_some() {
+        return Err(ServiceRunError::CgroupUnsupported(String::from("not supported on this \
+                                                                    platform")));
+    }
+    if msg.scheduling.is_some() {
+        return Err(ServiceRunError::InvalidScheduling(String::from("not supported on this \
+                                                                    platform")));
+    }
+    if msg.privileges.is_some() {
+        return Err(ServiceRunError::InvalidPrivileges(String::from("not supported on this \
+                                                                    platform")));
+    }
+    if msg.svc_user.is_none() {
+        return Err(ServiceRunError::UserNotFound(String::from("")));
+    }
+    Ok(())
+}
+
+/// The command running `program` as a readiness probe of `msg`'s
+/// process. Without the service's password at hand, it runs as the
+/// Launcher's user.
+pub fn probe_command(msg: &protocol::Spawn, program: &str) -> Result<Command, ServiceRunError> {
+    let mut cmd = Command::new(program);
+    cmd.envs(&msg.env);
+    Ok(cmd)
+}
+
 pub fn run(msg: protocol::Spawn) -> Result<Service, ServiceRunError> {
     debug!("launcher is spawning {}", msg.binary);
+    validate(&msg)?;
     let ps_cmd = format!("iex $(gc {} | out-string)", &msg.binary);
     let password = msg.svc_password.clone();
 
@@ -155,9 +208,9 @@ pub fn run(msg: protocol::Spawn) -> Result<Service, ServiceRunError> {
     match util::spawn_pwsh(&ps_cmd, &new_env, user, password) {
         Ok(child) => {
             let process = Process::new(child.handle);
-            Ok(Service::new(msg, process, child.stdout, child.stderr))
+            Ok(Service::new(msg, process, child.stdout, child.stderr, None))
         }
-        Err(_) => Err(ServiceRunError::Spawn(io::Error::last_os_error())),
+        Err(_) => Err(ServiceRunError::spawn(io::Error::last_os_error(), &msg.binary)),
     }
 }
 
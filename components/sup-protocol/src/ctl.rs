@@ -28,6 +28,10 @@ impl message::MessageStatic for SupRestart {
     const MESSAGE_ID: &'static str = "SupRestart";
 }
 
+impl message::MessageStatic for SupUpdatePeriod {
+    const MESSAGE_ID: &'static str = "SupUpdatePeriod";
+}
+
 impl message::MessageStatic for SvcFilePut {
     const MESSAGE_ID: &'static str = "SvcFilePut";
 }
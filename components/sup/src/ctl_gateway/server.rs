@@ -342,6 +342,9 @@ impl SrvHandler {
             "SvcStatus" => util::to_command(msg, ctl_sender, commands::service_status_gsr),
             "SupDepart" => util::to_command(msg, ctl_sender, commands::supervisor_depart),
             "SupRestart" => util::to_command(msg, ctl_sender, commands::supervisor_restart),
+            "SupUpdatePeriod" => {
+                util::to_supervisor_command(msg, ctl_sender, commands::supervisor_update_period)
+            }
             _ => {
                 warn!("Unhandled message, {}", msg.message_id());
                 Err(HandlerError::from(io::Error::from(io::ErrorKind::InvalidData)))
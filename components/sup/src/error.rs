@@ -60,6 +60,7 @@ pub enum Error {
     TaskJoin(JoinError),
     LauncherIPCCommand(habitat_launcher_client::IPCCommandError),
     LauncherTryIPCCommand(habitat_launcher_client::TryIPCCommandError),
+    LauncherSpawn(habitat_launcher_client::SpawnError),
     LockFileError(crate::lock_file::Error),
     MissingRequiredBind(Vec<String>),
     MissingRequiredIdent,
@@ -105,6 +106,30 @@ impl Error {
     pub fn with_duration(self, duration: Duration) -> Self {
         Self::WithDuration(Box::new(self), duration)
     }
+
+    /// Whether this error is an HTTP proxy refusing our credentials,
+    /// as opposed to a general connection failure.
+    pub fn is_proxy_auth_failed(&self) -> bool {
+        self.api_client_error()
+            .map_or(false, habitat_api_client::Error::is_proxy_auth_failed)
+    }
+
+    /// If this error is the depot reporting that it is down for
+    /// maintenance, the window it asked clients to wait, if any.
+    pub fn maintenance_window(&self) -> Option<Option<Duration>> {
+        self.api_client_error()
+            .and_then(habitat_api_client::Error::maintenance_window)
+    }
+
+    fn api_client_error(&self) -> Option<&habitat_api_client::Error> {
+        match self {
+            Error::APIClient(err) | Error::HabitatCommon(habitat_common::Error::APIClient(err)) => {
+                Some(err)
+            }
+            Error::WithDuration(err, _) => err.api_client_error(),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -207,6 +232,17 @@ impl fmt::Display for Error {
                 format!("Supervisor failed to try executing launcher command via IPC: {}",
                         chain.join(", "))
             }
+            Error::LauncherSpawn(habitat_launcher_client::SpawnError::Command(err)) => {
+                let mut chain: Vec<String> = vec![format!("{}", err)];
+                let mut root = err.source();
+                while let Some(cause) = root {
+                    chain.push(format!("{}", cause));
+                    root = cause.source();
+                }
+                format!("Supervisor failed to execute launcher command via IPC: {}",
+                        chain.join(", "))
+            }
+            Error::LauncherSpawn(err) => format!("Launcher failed to spawn service: {}", err),
             Error::MissingRequiredBind(ref e) => {
                 format!("Missing required bind(s), {}", e.join(", "))
             }
@@ -361,6 +397,10 @@ impl From<habitat_launcher_client::IPCCommandError> for Error {
     }
 }
 
+impl From<habitat_launcher_client::SpawnError> for Error {
+    fn from(err: habitat_launcher_client::SpawnError) -> Error { Error::LauncherSpawn(err) }
+}
+
 impl From<string::FromUtf8Error> for Error {
     fn from(err: string::FromUtf8Error) -> Error { Error::StringFromUtf8Error(err) }
 }
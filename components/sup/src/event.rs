@@ -21,8 +21,16 @@ use self::types::{EventMessage,
                   HealthCheckEvent,
                   ServiceStartedEvent,
                   ServiceStoppedEvent,
-                  ServiceUpdateStartedEvent};
-use crate::manager::{service::{HealthCheckHookStatus,
+                  ServiceUpdateStartedEvent,
+                  SupervisorUpdateCandidateEvent,
+                  SupervisorUpdateCheckStartedEvent,
+                  SupervisorUpdateDeclineReason,
+                  SupervisorUpdateDeclinedEvent,
+                  SupervisorUpdateErrorEvent,
+                  SupervisorUpdateRecoveredEvent,
+                  SupervisorUpdateStagedEvent};
+use crate::manager::{self_updater::DeclineReason,
+                     service::{HealthCheckHookStatus,
                                HealthCheckResult,
                                ProcessOutput,
                                Service,
@@ -35,7 +43,8 @@ use habitat_common::types::{EventStreamConnectMethod,
                             EventStreamServerCertificate,
                             EventStreamToken};
 use habitat_core::{package::ident::PackageIdent,
-                   service::HealthCheckInterval};
+                   service::HealthCheckInterval,
+                   ChannelIdent};
 use lazy_static::lazy_static;
 use log::debug;
 use nats_message_stream::{NatsMessage,
@@ -61,6 +70,18 @@ lazy_static! {
         "habitat.event.service_update_started".parse().expect("valid NATS subject");
     static ref HEALTHCHECK_SUBJECT: Subject =
         "habitat.event.healthcheck".parse().expect("valid NATS subject");
+    static ref SUPERVISOR_UPDATE_DECLINED_SUBJECT: Subject =
+        "habitat.event.supervisor_update_declined".parse().expect("valid NATS subject");
+    static ref SUPERVISOR_UPDATE_CHECK_STARTED_SUBJECT: Subject =
+        "habitat.event.supervisor_update_check_started".parse().expect("valid NATS subject");
+    static ref SUPERVISOR_UPDATE_CANDIDATE_FOUND_SUBJECT: Subject =
+        "habitat.event.supervisor_update_candidate_found".parse().expect("valid NATS subject");
+    static ref SUPERVISOR_UPDATE_STAGED_SUBJECT: Subject =
+        "habitat.event.supervisor_update_staged".parse().expect("valid NATS subject");
+    static ref SUPERVISOR_UPDATE_ERROR_SUBJECT: Subject =
+        "habitat.event.supervisor_update_error".parse().expect("valid NATS subject");
+    static ref SUPERVISOR_UPDATE_RECOVERED_SUBJECT: Subject =
+        "habitat.event.supervisor_update_recovered".parse().expect("valid NATS subject");
 
     /// Reference to the event stream.
     static ref NATS_MESSAGE_STREAM: InitCell<NatsMessageStream> = InitCell::new();
@@ -130,6 +151,82 @@ pub fn service_update_started(service: &Service, update: &PackageIdent) {
     }
 }
 
+/// Send an event for the self updater declining to stage `candidate`
+/// while `current` is running.
+pub fn supervisor_update_declined(current: &PackageIdent,
+                                  candidate: &PackageIdent,
+                                  reason: DeclineReason,
+                                  detail: String) {
+    if initialized() {
+        let reason = SupervisorUpdateDeclineReason::from(reason);
+        publish(&SUPERVISOR_UPDATE_DECLINED_SUBJECT,
+                SupervisorUpdateDeclinedEvent { event_metadata: None,
+                                                current_package_ident: current.to_string(),
+                                                candidate_package_ident: candidate.to_string(),
+                                                reason: i32::from(reason),
+                                                detail });
+    }
+}
+
+/// Send an event for the self updater starting a check of `channel`
+/// while `current` is running.
+pub fn supervisor_update_check_started(current: &PackageIdent, channel: &ChannelIdent) {
+    if initialized() {
+        publish(&SUPERVISOR_UPDATE_CHECK_STARTED_SUBJECT,
+                SupervisorUpdateCheckStartedEvent { event_metadata:        None,
+                                                    current_package_ident: current.to_string(),
+                                                    channel:               channel.to_string(), });
+    }
+}
+
+/// Send an event for the self updater finding `candidate` in `channel`,
+/// newer than the running `current`.
+pub fn supervisor_update_candidate_found(current: &PackageIdent,
+                                         candidate: &PackageIdent,
+                                         channel: &ChannelIdent) {
+    if initialized() {
+        publish(&SUPERVISOR_UPDATE_CANDIDATE_FOUND_SUBJECT,
+                SupervisorUpdateCandidateEvent { event_metadata:          None,
+                                                 current_package_ident:   current.to_string(),
+                                                 candidate_package_ident: candidate.to_string(),
+                                                 channel:                 channel.to_string(), });
+    }
+}
+
+/// Send an event for the self updater staging `candidate` to replace
+/// `current`.
+pub fn supervisor_update_staged(current: &PackageIdent, candidate: &PackageIdent) {
+    if initialized() {
+        publish(&SUPERVISOR_UPDATE_STAGED_SUBJECT,
+                SupervisorUpdateStagedEvent { event_metadata:          None,
+                                              current_package_ident:   current.to_string(),
+                                              candidate_package_ident: candidate.to_string(), });
+    }
+}
+
+/// Send an event for a failed self updater check of `channel`.
+pub fn supervisor_update_error(current: &PackageIdent, channel: &ChannelIdent, error: String) {
+    if initialized() {
+        publish(&SUPERVISOR_UPDATE_ERROR_SUBJECT,
+                SupervisorUpdateErrorEvent { event_metadata: None,
+                                             current_package_ident: current.to_string(),
+                                             channel: channel.to_string(),
+                                             error });
+    }
+}
+
+/// Send an event for a self updater check of `channel` succeeding after
+/// `failures` checks in a row failed.
+pub fn supervisor_update_recovered(current: &PackageIdent, channel: &ChannelIdent, failures: u32) {
+    if initialized() {
+        publish(&SUPERVISOR_UPDATE_RECOVERED_SUBJECT,
+                SupervisorUpdateRecoveredEvent { event_metadata: None,
+                                                 current_package_ident: current.to_string(),
+                                                 channel: channel.to_string(),
+                                                 failures });
+    }
+}
+
 // Takes metadata directly, rather than a `&Service` like other event
 // functions, because of how the asynchronous health checking
 // currently works. Revisit when async/await + Pin is all stabilized.
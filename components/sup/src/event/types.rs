@@ -2,9 +2,10 @@
 //! Supervisor.
 
 use super::EventCore;
-use crate::manager::service::{HealthCheckResult as DomainHealthCheckResult,
-                              Service,
-                              UpdateStrategy as DomainUpdateStrategy};
+use crate::manager::{self_updater::DeclineReason,
+                     service::{HealthCheckResult as DomainHealthCheckResult,
+                               Service,
+                               UpdateStrategy as DomainUpdateStrategy}};
 use prost::Message;
 
 include!(concat!(env!("OUT_DIR"), "/chef.habitat.supervisor.event.rs"));
@@ -24,6 +25,24 @@ impl Into<HealthCheckResult> for DomainHealthCheckResult {
     }
 }
 
+impl From<DeclineReason> for SupervisorUpdateDeclineReason {
+    fn from(reason: DeclineReason) -> Self {
+        match reason {
+            DeclineReason::Quarantined => SupervisorUpdateDeclineReason::Quarantined,
+            DeclineReason::WarmingUp => SupervisorUpdateDeclineReason::WarmingUp,
+            DeclineReason::HostIncompatible => SupervisorUpdateDeclineReason::HostIncompatible,
+            DeclineReason::IncompleteInstall => SupervisorUpdateDeclineReason::IncompleteInstall,
+            DeclineReason::ForkTestFailed => SupervisorUpdateDeclineReason::ForkTestFailed,
+            DeclineReason::BackupFailed => SupervisorUpdateDeclineReason::BackupFailed,
+            DeclineReason::MigrationFailed => SupervisorUpdateDeclineReason::MigrationFailed,
+            DeclineReason::Embargoed => SupervisorUpdateDeclineReason::Embargoed,
+            DeclineReason::Yanked => SupervisorUpdateDeclineReason::Yanked,
+            DeclineReason::NotApproved => SupervisorUpdateDeclineReason::NotApproved,
+            DeclineReason::RolledBack => SupervisorUpdateDeclineReason::RolledBack,
+        }
+    }
+}
+
 impl Service {
     /// Create a protobuf metadata struct for Service-related event
     /// messages.
@@ -105,3 +124,9 @@ event_msg_impl!(ServiceStartedEvent);
 event_msg_impl!(ServiceStoppedEvent);
 event_msg_impl!(ServiceUpdateStartedEvent);
 event_msg_impl!(HealthCheckEvent);
+event_msg_impl!(SupervisorUpdateDeclinedEvent);
+event_msg_impl!(SupervisorUpdateCheckStartedEvent);
+event_msg_impl!(SupervisorUpdateCandidateEvent);
+event_msg_impl!(SupervisorUpdateStagedEvent);
+event_msg_impl!(SupervisorUpdateErrorEvent);
+event_msg_impl!(SupervisorUpdateRecoveredEvent);
@@ -252,6 +252,24 @@ impl Butterfly {
     }
 }
 
+struct Health {}
+
+impl Health {
+    // Route registration
+    pub fn register(cfg: &mut ServiceConfig) {
+        cfg.route("/health", web::get().to(health_summary_gsr));
+    }
+}
+
+struct SelfUpdate {}
+
+impl SelfUpdate {
+    // Route registration
+    pub fn register(cfg: &mut ServiceConfig) {
+        cfg.route("/self_update", web::get().to(self_update_gsr));
+    }
+}
+
 struct Census {}
 
 impl Census {
@@ -295,6 +313,8 @@ impl Server {
                               .configure(Services::register)
                               .configure(Butterfly::register)
                               .configure(Census::register)
+                              .configure(Health::register)
+                              .configure(SelfUpdate::register)
                               .service(web::resource("/metrics").route(web::get().to(metrics)))
                              }).workers(thread_count);
 
@@ -351,6 +371,30 @@ async fn census_gsr(state: Data<AppState>) -> HttpResponse {
     json_response(data)
 }
 
+/// # Locking (see locking.md)
+/// * `GatewayState::inner` (read)
+#[allow(clippy::needless_pass_by_value)]
+async fn health_summary_gsr(state: Data<AppState>) -> HttpResponse {
+    let data = state.gateway_state.lock_gsr().health_data().to_string();
+    json_response(data)
+}
+
+/// # Locking (see locking.md)
+/// * `GatewayState::inner` (read)
+#[allow(clippy::needless_pass_by_value)]
+async fn self_update_gsr(state: Data<AppState>) -> HttpResponse {
+    let status = state.gateway_state
+                      .lock_gsr()
+                      .self_update_status()
+                      .to_string();
+    if status.is_empty() {
+        // The Supervisor isn't updating itself
+        HttpResponse::NotFound().finish()
+    } else {
+        HttpResponse::Ok().content_type("text/plain").body(status)
+    }
+}
+
 /// # Locking (see locking.md)
 /// * `GatewayState::inner` (read)
 #[allow(clippy::needless_pass_by_value)]
@@ -521,19 +565,28 @@ async fn doc() -> HttpResponse { HttpResponse::Ok().content_type("text/html").bo
 
 #[cfg(test)]
 mod tests {
-    use crate::test_helpers::*;
+    use super::{self_update_gsr,
+                AppState};
+    use crate::{manager::sync::GatewayState,
+                test_helpers::*};
+    use actix_web::{body,
+                    http::StatusCode,
+                    web::Data};
     use habitat_butterfly::{member::Member,
                             server::{Server,
                                      ServerProxy,
                                      Suitability}};
+    use habitat_common::FeatureFlag;
     use lazy_static::lazy_static;
-    use std::{fs::File,
+    use std::{cell::Cell,
+              fs::File,
               io::Read,
               net::{IpAddr,
                     Ipv4Addr,
                     SocketAddr},
               path::PathBuf,
-              sync::Mutex};
+              sync::{Arc,
+                     Mutex}};
 
     fn validate_sample_file_against_schema(name: &str, schema: &str) {
         let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests")
@@ -641,4 +694,40 @@ mod tests {
         assert!(!failure.is_valid(),
                 "Expected schema validation to fail, but it succeeded");
     }
+
+    #[test]
+    fn sample_health_file_is_valid() {
+        validate_sample_file_against_schema("sample-health-output.json",
+                                            "http_gateway_health_schema.json");
+    }
+
+    #[test]
+    fn trivial_health_failure() {
+        let failure = validate_string(r#"{"launcher": {"state": "Connected"}, "resources": {}}"#,
+                                      "http_gateway_health_schema.json");
+        assert!(!failure.is_valid(),
+                "Expected schema validation to fail, but it succeeded");
+    }
+
+    #[tokio::test]
+    async fn self_update_status_is_served_as_text() {
+        let gateway_state = Arc::<GatewayState>::default();
+        let state = || {
+            Data::new(AppState { gateway_state:        Arc::clone(&gateway_state),
+                                 authentication_token: None,
+                                 timer:                Cell::new(None),
+                                 feature_flags:        FeatureFlag::empty(), })
+        };
+        let response = self_update_gsr(state()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let status = "Current:       core/hab-sup/1.0.0/20200101000000\n";
+        gateway_state.lock_gsw()
+                     .set_self_update_status(status.to_string());
+        let response = self_update_gsr(state()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/plain");
+        let body = body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(body, status);
+    }
 }
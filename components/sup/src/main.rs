@@ -285,6 +285,7 @@ async fn split_apart_sup_run(sup_run: SupRun,
     let cfg =
         ManagerConfig { auto_update: sup_run.auto_update,
                         auto_update_period: sup_run.auto_update_period.into(),
+                        auto_update_bandwidth: sup_run.auto_update_bandwidth_limit,
                         service_update_period: sup_run.service_update_period.into(),
                         service_restart_config:
                             ServiceRestartConfig::new(sup_run.service_min_backoff_period.into(),
@@ -728,6 +729,7 @@ gpoVMSncu2jMIDZX63IkQII=
             let config = config_from_cmd_str("hab-sup run");
             assert_eq!(ManagerConfig { auto_update:                false,
                                        auto_update_period:         Duration::from_secs(60),
+                                       auto_update_bandwidth:      None,
                                        service_update_period:      Duration::from_secs(60),
                                        service_restart_config:     ServiceRestartConfig::default(),
                                        custom_state_path:          None,
@@ -802,6 +804,7 @@ gpoVMSncu2jMIDZX63IkQII=
             let config = config_from_cmd_str(&args);
             assert_eq!(ManagerConfig { auto_update: true,
                                        auto_update_period: Duration::from_secs(90),
+                                       auto_update_bandwidth: None,
                                        service_update_period: Duration::from_secs(30),
                                        service_restart_config: ServiceRestartConfig::default(),
                                        custom_state_path: None,
@@ -844,6 +847,7 @@ gpoVMSncu2jMIDZX63IkQII=
             let config = config_from_cmd_str(args);
             assert_eq!(ManagerConfig { auto_update:                false,
                                        auto_update_period:         Duration::from_secs(60),
+                                       auto_update_bandwidth:      None,
                                        service_update_period:      Duration::from_secs(60),
                                        service_restart_config:     ServiceRestartConfig::default(),
                                        custom_state_path:          None,
@@ -883,6 +887,7 @@ gpoVMSncu2jMIDZX63IkQII=
             let config = config_from_cmd_str(args);
             assert_eq!(ManagerConfig { auto_update:                false,
                                        auto_update_period:         Duration::from_secs(60),
+                                       auto_update_bandwidth:      None,
                                        service_update_period:      Duration::from_secs(60),
                                        service_restart_config:     ServiceRestartConfig::default(),
                                        custom_state_path:          None,
@@ -956,6 +961,7 @@ gpoVMSncu2jMIDZX63IkQII=
                 ManagerConfig {
                     auto_update: false,
                     auto_update_period: Duration::from_secs(60),
+                    auto_update_bandwidth: None,
                     service_update_period: Duration::from_secs(60),
                     service_restart_config: ServiceRestartConfig::default(),
                     custom_state_path: None,
@@ -1110,6 +1116,7 @@ ring = "tester"
 cache_key_path = "{}"
 auto_update = true
 auto_update_period = 3600
+auto_update_bandwidth_limit = 1_048_576
 service_update_period = 1_000
 key_file = "{}"
 cert_file = "{}"
@@ -1137,6 +1144,7 @@ sys_ip_address = "7.8.9.0"
             let config = config_from_cmd_str(&args);
             assert_eq!(ManagerConfig { auto_update: true,
                                        auto_update_period: Duration::from_secs(3600),
+                                       auto_update_bandwidth: Some(1_048_576),
                                        service_update_period: Duration::from_secs(1_000),
                                        service_restart_config: ServiceRestartConfig::default(),
                                        custom_state_path: None,
@@ -1188,6 +1196,7 @@ sys_ip_address = "7.8.9.0"
             let config = config_from_cmd_str(&args);
             assert_eq!(ManagerConfig { auto_update:                false,
                                        auto_update_period:         Duration::from_secs(60),
+                                       auto_update_bandwidth:      None,
                                        service_update_period:      Duration::from_secs(60),
                                        service_restart_config:     ServiceRestartConfig::default(),
                                        custom_state_path:          None,
@@ -1236,6 +1245,7 @@ sys_ip_address = "7.8.9.0"
             let config = config_from_cmd_str(&args);
             assert_eq!(ManagerConfig { auto_update:                false,
                                        auto_update_period:         Duration::from_secs(60),
+                                       auto_update_bandwidth:      None,
                                        service_update_period:      Duration::from_secs(60),
                                        service_restart_config:     ServiceRestartConfig::default(),
                                        custom_state_path:          None,
@@ -1289,6 +1299,7 @@ sys_ip_address = "7.8.9.0"
             let config = config_from_cmd_str(&args);
             assert_eq!(ManagerConfig { auto_update: false,
                                        auto_update_period: Duration::from_secs(60),
+                                       auto_update_bandwidth: None,
                                        service_update_period: Duration::from_secs(60),
                                        service_restart_config: ServiceRestartConfig::default(),
                                        custom_state_path: None,
@@ -1398,6 +1409,7 @@ event_stream_server_certificate = "{}"
                 ManagerConfig {
                     auto_update: false,
                     auto_update_period: Duration::from_secs(60),
+                    auto_update_bandwidth: None,
                     service_update_period: Duration::from_secs(60),
                     service_restart_config: ServiceRestartConfig::default(),
                     custom_state_path: None,
@@ -1587,6 +1599,7 @@ organization = "MY_ORG_FROM_SECOND_CONFG"
             let config = config_from_cmd_str(&args);
             assert_eq!(ManagerConfig { auto_update:                false,
                                        auto_update_period:         Duration::from_secs(60),
+                                       auto_update_bandwidth:      None,
                                        service_update_period:      Duration::from_secs(60),
                                        service_restart_config:     ServiceRestartConfig::default(),
                                        custom_state_path:          None,
@@ -2,7 +2,7 @@ pub(crate) mod action;
 pub mod commands;
 mod file_watcher;
 mod peer_watcher;
-mod self_updater;
+pub mod self_updater;
 pub mod service;
 mod service_updater;
 mod spec_dir;
@@ -14,7 +14,11 @@ mod user_config_watcher;
 use self::{action::{ShutdownInput,
                     SupervisorAction},
            peer_watcher::PeerWatcher,
-           self_updater::{SelfUpdater,
+           self_updater::{RollbackWindow,
+                          SelfUpdater,
+                          SelfUpdaterOptions,
+                          UpdateLag,
+                          UpgradePlan,
                           SUP_PKG_IDENT},
            service::{spec::{RefreshOperation,
                             ServiceOperation},
@@ -80,7 +84,10 @@ use habitat_core::{crypto::keys::{KeyCache,
                    util::ToI64,
                    ChannelIdent};
 use habitat_launcher_client::{LauncherCli,
-                              LauncherStatus};
+                              LauncherResourceUsage,
+                              LauncherStatus,
+                              LauncherSummary,
+                              TryIPCCommandError};
 use habitat_sup_protocol::{self};
 use lazy_static::lazy_static;
 use log::{debug,
@@ -136,6 +143,12 @@ pub const PROC_LOCK_FILE: &str = "LOCK";
 
 static LOGKEY: &str = "MR";
 
+/// How often the Launcher is asked for what `/health` reports of it,
+/// and pinged to keep its average latency current.
+/// Each ask waits up to the interaction timeout for a reply, so it
+/// isn't made on every pass through the main loop.
+const LAUNCHER_HEALTH_PERIOD: Duration = Duration::from_secs(10);
+
 lazy_static! {
     static ref RUN_LOOP_DURATION: HistogramVec =
         register_histogram_vec!("hab_sup_run_loop_duration_seconds",
@@ -260,6 +273,106 @@ impl FsCfg {
     }
 }
 
+/// The Supervisor's overall health, as served from the HTTP gateway's
+/// /health endpoint.
+#[derive(Debug, Serialize)]
+struct HealthSummary {
+    /// The connection to the Launcher, with the capabilities in use on
+    /// it.
+    launcher:       LauncherSummary,
+    /// The Launcher's counts of supervised processes, if it could be
+    /// asked for them.
+    processes:      Option<ProcessHealth>,
+    /// Why `processes` is missing, if it is.
+    launcher_error: Option<String>,
+    /// Only present when the Supervisor updates itself.
+    self_update:    Option<SelfUpdateHealth>,
+    resources:      ResourceHealth,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct ProcessHealth {
+    running: u32,
+    stopped: u64,
+    crashed: u64,
+}
+
+/// What the Supervisor and the Launcher are using of the host's
+/// resources themselves, apart from the services they run.
+#[derive(Debug, Default, Serialize)]
+struct ResourceHealth {
+    /// As of the last pass through the main loop.
+    supervisor_open_fds:    i64,
+    /// The CPU time the Supervisor used over the last second measured.
+    supervisor_cpu_time_ns: i64,
+    /// The Launcher's usage, if it could be asked for it.
+    launcher:               Option<LauncherResourceUsage>,
+    /// Why `launcher` is missing, unless the Launcher doesn't report
+    /// its usage at all.
+    launcher_error:         Option<String>,
+}
+
+/// What the Launcher last reported for the `HealthSummary`, kept
+/// between refreshes.
+#[derive(Default)]
+struct LauncherHealth {
+    processes:       Option<ProcessHealth>,
+    processes_error: Option<String>,
+    resources:       Option<LauncherResourceUsage>,
+    resources_error: Option<String>,
+    refreshed_at:    Option<Instant>,
+    /// Whether the Launcher is being asked again right now.
+    refreshing:      bool,
+}
+
+impl LauncherHealth {
+    /// Ask `launcher` for its part of the health summary. Each question
+    /// may take up to the interaction timeout to be answered.
+    fn ask(launcher: &LauncherCli) -> Self {
+        let mut health = LauncherHealth { refreshed_at: Some(Instant::now()),
+                                          ..Default::default() };
+        match launcher.health_summary() {
+            Ok(report) => {
+                health.processes = Some(ProcessHealth { running: report.running,
+                                                        stopped: report.stopped,
+                                                        crashed: report.crashed, })
+            }
+            Err(err) => {
+                debug!("Unable to get a health summary from the launcher, {}", err);
+                health.processes_error = Some(err.to_string());
+            }
+        }
+        match launcher.resource_usage() {
+            Ok(usage) => health.resources = Some(usage),
+            Err(TryIPCCommandError::Unsupported(..)) => {}
+            Err(err) => {
+                debug!("Unable to get the launcher's resource usage, {}", err);
+                health.resources_error = Some(err.to_string());
+            }
+        }
+        // Each ping adds to the average latency reported with the
+        // connection's summary
+        match launcher.ping() {
+            Ok(_) | Err(TryIPCCommandError::Unsupported(..)) => {}
+            Err(err) => debug!("Unable to ping the launcher, {}", err),
+        }
+        health
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SelfUpdateHealth {
+    /// A newer Supervisor waiting to be installed.
+    available:       Option<String>,
+    rollback_window: Option<RollbackWindow>,
+    /// How far behind the channel the running Supervisor was at the
+    /// last check.
+    lag:             Option<UpdateLag>,
+    /// What applying `available`, or an update held for approval, would
+    /// entail.
+    plan:            Option<UpgradePlan>,
+}
+
 /// Configuration parameters that control the behaviour of restarts for services
 /// that fail to startup successfully
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -297,6 +410,9 @@ impl Default for ServiceRestartConfig {
 pub struct ManagerConfig {
     pub auto_update:                bool,
     pub auto_update_period:         Duration,
+    /// Download Supervisor updates no faster than this many bytes per
+    /// second. `None` or zero leaves them unlimited.
+    pub auto_update_bandwidth:      Option<u64>,
     pub service_update_period:      Duration,
     pub service_restart_config:     ServiceRestartConfig,
     pub custom_state_path:          Option<PathBuf>,
@@ -449,6 +565,10 @@ pub(crate) mod sync {
 
         pub fn census_data(&self) -> &str { &self.0.census_data }
 
+        pub fn health_data(&self) -> &str { &self.0.health_data }
+
+        pub fn self_update_status(&self) -> &str { &self.0.self_update_status }
+
         pub fn services_data(&self) -> &[ServiceQueryModel] { self.0.services_data.as_slice() }
     }
 
@@ -461,6 +581,12 @@ pub(crate) mod sync {
 
         pub fn set_butterfly_data(&mut self, new_data: String) { self.0.butterfly_data = new_data }
 
+        pub fn set_health_data(&mut self, new_data: String) { self.0.health_data = new_data }
+
+        pub fn set_self_update_status(&mut self, new_status: String) {
+            self.0.self_update_status = new_status
+        }
+
         pub fn set_services_data(&mut self, new_data: Vec<ServiceQueryModel>) {
             self.0.services_data = new_data
         }
@@ -490,11 +616,16 @@ pub(crate) mod sync {
     #[derive(Debug, Default)]
     struct GatewayStateInner {
         /// JSON returned by the /census endpoint
-        census_data:    String,
+        census_data:        String,
         /// JSON returned by the /butterfly endpoint
-        butterfly_data: String,
+        butterfly_data:     String,
+        /// JSON returned by the /health endpoint
+        health_data:        String,
+        /// Text returned by the /self_update endpoint; empty when the
+        /// Supervisor isn't updating itself
+        self_update_status: String,
         /// JSON returned by the /services endpoint
-        services_data:  Vec<ServiceQueryModel>,
+        services_data:      Vec<ServiceQueryModel>,
     }
 
     type ManagerServicesInner = HashMap<PackageIdent, PersistentServiceWrapper>;
@@ -602,7 +733,7 @@ pub struct Manager {
     butterfly:           habitat_butterfly::Server,
     census_ring:         Arc<RwLock<CensusRing>>,
     fs_cfg:              Arc<FsCfg>,
-    launcher:            LauncherCli,
+    launcher:            Arc<LauncherCli>,
     service_updater:     Arc<Mutex<ServiceUpdater>>,
     peer_watcher:        Option<PeerWatcher>,
     spec_watcher:        SpecWatcher,
@@ -647,6 +778,10 @@ pub struct Manager {
     busy_services: Arc<Mutex<HashSet<PackageIdent>>>,
     updated_service_pkg_incarnations: Arc<Mutex<HashMap<ServiceGroup, u64>>>,
     services_need_reconciliation:     ReconciliationFlag,
+    launcher_health:                  Arc<Mutex<LauncherHealth>>,
+    /// The health summary and self update status last served, to only
+    /// replace them when they change.
+    persisted_health:                 Option<(String, String)>,
 
     feature_flags: FeatureFlag,
     pid_source:    ServicePidSource,
@@ -703,10 +838,15 @@ impl Manager {
         let cfg_static = cfg.clone();
         let self_updater = if cfg.auto_update {
             if THIS_SUPERVISOR_IDENT.fully_qualified() {
-                Some(SelfUpdater::new(&THIS_SUPERVISOR_IDENT,
-                                      cfg.update_url,
-                                      cfg.update_channel,
-                                      cfg.auto_update_period))
+                let options = SelfUpdaterOptions { launcher_state:
+                                                       Some(launcher.subscribe_state()),
+                                                   bandwidth_limit: cfg.auto_update_bandwidth,
+                                                   ..Default::default() };
+                Some(SelfUpdater::with_options(&THIS_SUPERVISOR_IDENT,
+                                               cfg.update_url,
+                                               cfg.update_channel,
+                                               cfg.auto_update_period,
+                                               options))
             } else {
                 warn!("Supervisor version not fully qualified, unable to start self-updater");
                 None
@@ -773,7 +913,7 @@ impl Manager {
                                                                  cfg.service_update_period))),
                      census_ring,
                      butterfly: server,
-                     launcher,
+                     launcher: Arc::new(launcher),
                      peer_watcher,
                      spec_watcher,
                      user_config_watcher: UserConfigWatcher::new(),
@@ -788,6 +928,8 @@ impl Manager {
                      services_need_reconciliation: ReconciliationFlag::new(false),
                      feature_flags: cfg.feature_flags,
                      pid_source,
+                     launcher_health: Arc::default(),
+                     persisted_health: None,
                      _lock_file: lock_file })
     }
 
@@ -1241,6 +1383,11 @@ impl Manager {
                                   service_spec.ident, err);
                         }
                     }
+                    SetTemporaryUpdatePeriod { period, duration } => {
+                        if let Some(ref self_updater) = self.self_updater {
+                            self_updater.with_temporary_period(period, duration);
+                        }
+                    }
                 }
             }
 
@@ -1292,6 +1439,8 @@ impl Manager {
             if self.check_for_changed_services_msr() || self.census_ring.read().changed() {
                 self.persist_state_rsr_mlr_gsw_msr().await;
             }
+            self.refresh_launcher_health();
+            self.persist_health_state_gsw();
 
             // we do not want to register the services for updating until the
             // census is updated from the rumors above. Otherwise the updater
@@ -1557,6 +1706,81 @@ impl Manager {
         self.persist_services_state_gsw_msr().await;
     }
 
+    /// Ask the Launcher for its part of the health summary again, once
+    /// `LAUNCHER_HEALTH_PERIOD` has passed since it was last asked. It
+    /// is asked on a blocking thread, so that waiting on its answers
+    /// never holds up the main loop.
+    fn refresh_launcher_health(&self) {
+        {
+            let mut health = self.launcher_health.lock();
+            let due = !health.refreshing
+                      && health.refreshed_at
+                               .map_or(true, |at| at.elapsed() >= LAUNCHER_HEALTH_PERIOD);
+            if !due {
+                return;
+            }
+            health.refreshing = true;
+        }
+        let launcher = Arc::clone(&self.launcher);
+        let launcher_health = Arc::clone(&self.launcher_health);
+        tokio::task::spawn_blocking(move || {
+            let health = LauncherHealth::ask(&launcher);
+            *launcher_health.lock() = health;
+        });
+    }
+
+    /// Gather the Launcher's process counts, the self updater's state
+    /// and what the Supervisor and the Launcher are using themselves into
+    /// the summary served from the /health endpoint. This is
+    /// gathered on every pass through the main loop, since it has to
+    /// reflect processes that crash between service changes, and only
+    /// replaces what is served when it changed. What the Launcher
+    /// reports is as of `refresh_launcher_health`, so that a slow
+    /// Launcher doesn't hold up the loop.
+    ///
+    /// # Locking (see locking.md)
+    /// * `GatewayState::inner` (write)
+    fn persist_health_state_gsw(&mut self) {
+        let launcher = self.launcher_health.lock();
+        let resources = ResourceHealth { supervisor_open_fds:    FILE_DESCRIPTORS.get(),
+                                         supervisor_cpu_time_ns: CPU_TIME.get(),
+                                         launcher:               launcher.resources,
+                                         launcher_error:         launcher.resources_error.clone(), };
+        let self_update =
+            self.self_updater.as_ref().map(|self_updater| {
+                                          let available =
+                                              self_updater.available()
+                                                          .map(|ident| ident.to_string());
+                                          let plan = Some(self_updater.plan()).filter(|plan| {
+                                                                                  plan.to.is_some()
+                                                                              });
+                                          SelfUpdateHealth { available,
+                                                             rollback_window:
+                                                                 self_updater.rollback_window(),
+                                                             lag: self_updater.update_lag(),
+                                                             plan }
+                                      });
+        let health = HealthSummary { launcher: self.launcher.summary(),
+                                     processes: launcher.processes.clone(),
+                                     launcher_error: launcher.processes_error.clone(),
+                                     self_update,
+                                     resources };
+        drop(launcher);
+        let json = serde_json::to_string(&health).expect("HealthSummary::serialize failure");
+        let status = self.self_updater
+                         .as_ref()
+                         .map(SelfUpdater::status_report)
+                         .unwrap_or_default();
+        let persisted = (json, status);
+        if self.persisted_health.as_ref() == Some(&persisted) {
+            return;
+        }
+        let mut gateway_state = self.state.gateway_state.lock_gsw();
+        gateway_state.set_health_data(persisted.0.clone());
+        gateway_state.set_self_update_status(persisted.1.clone());
+        self.persisted_health = Some(persisted);
+    }
+
     /// # Locking (see locking.md)
     /// * `GatewayState::inner` (write)
     fn persist_census_state_gsw(&self) {
@@ -2098,7 +2322,10 @@ fn get_fd_count() -> std::io::Result<usize> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_helpers::assert_valid;
     use habitat_core::fs::CACHE_KEY_PATH;
+    use habitat_launcher_client::{Capabilities,
+                                  ConnectionState};
     use habitat_sup_protocol::STATE_PATH_PREFIX;
     use std::{net::Ipv4Addr,
               path::PathBuf};
@@ -2125,6 +2352,7 @@ mod test {
         fn default() -> Self {
             ManagerConfig { auto_update:                false,
                             auto_update_period:         Duration::from_secs(60),
+                            auto_update_bandwidth:      None,
                             service_update_period:      Duration::from_secs(60),
                             service_restart_config:     ServiceRestartConfig::default(),
                             custom_state_path:          None,
@@ -2178,4 +2406,27 @@ mod test {
 
         assert_eq!(PathBuf::from("/tmp/partay"), path);
     }
+
+    #[test]
+    fn health_summary_reports_the_launcher_latency_and_capabilities() {
+        let capabilities = Capabilities { ping: true,
+                                          health_summary: true,
+                                          ..Default::default() };
+        let launcher = LauncherSummary { state: ConnectionState::Connected,
+                                         capabilities,
+                                         protocol_version: 1,
+                                         average_latency: Some(Duration::from_micros(412)) };
+        let health = HealthSummary { launcher,
+                                     processes: None,
+                                     launcher_error: Some(String::from("timed out")),
+                                     self_update: None,
+                                     resources: ResourceHealth::default() };
+        let json = serde_json::to_string(&health).unwrap();
+        assert_valid(&json, "http_gateway_health_schema.json");
+
+        let health: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(health["launcher"]["average_latency_us"], 412);
+        assert_eq!(health["launcher"]["capabilities"]["ping"], true);
+        assert_eq!(health["launcher"]["capabilities"]["cgroup"], false);
+    }
 }
@@ -3,7 +3,8 @@
 
 use super::service::ServiceSpec;
 use habitat_core::os::process::ShutdownTimeout;
-use std::sync::mpsc;
+use std::{sync::mpsc,
+          time::Duration};
 
 /// Defines the parameters by which a service process is to be shut
 /// down cleanly.
@@ -32,6 +33,10 @@ pub enum SupervisorAction {
     UpdateService {
         service_spec: ServiceSpec,
     },
+    SetTemporaryUpdatePeriod {
+        period:   Duration,
+        duration: Duration,
+    },
 }
 
 pub type ActionSender = mpsc::Sender<SupervisorAction>;
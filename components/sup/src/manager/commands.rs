@@ -22,7 +22,8 @@ use habitat_sup_protocol::{self as protocol,
                                  NetResult}};
 use std::{convert::TryFrom,
           str,
-          sync::atomic::Ordering};
+          sync::atomic::Ordering,
+          time::Duration};
 
 static LOGKEY: &str = "CMD";
 
@@ -336,6 +337,27 @@ pub fn supervisor_restart(mgr: &ManagerState,
     Ok(())
 }
 
+pub fn supervisor_update_period(mgr: &ManagerState,
+                                req: &mut CtlRequest,
+                                opts: protocol::ctl::SupUpdatePeriod,
+                                action_sender: &ActionSender)
+                                -> NetResult<()> {
+    if !mgr.cfg.auto_update {
+        return Err(net::err(ErrCode::NotSupported,
+                            "Supervisor is not running with --auto-update."));
+    }
+    let period = Duration::from_secs(opts.period_secs.ok_or_else(err_update_client)?);
+    let duration = Duration::from_secs(opts.duration_secs.ok_or_else(err_update_client)?);
+    let action = SupervisorAction::SetTemporaryUpdatePeriod { period, duration };
+    send_action(action, action_sender)?;
+    req.info(format!("Supervisor asked to check for updates every {}s for the next {}s. See \
+                      the Supervisor output for the period in effect.",
+                     period.as_secs(),
+                     duration.as_secs()))?;
+    req.reply_complete(net::ok());
+    Ok(())
+}
+
 /// # Locking (see locking.md)
 /// * `GatewayState::inner` (read)
 pub fn service_status_gsr(mgr: &ManagerState,
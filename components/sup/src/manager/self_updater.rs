@@ -1,26 +1,115 @@
 //! Encapsulates logic required for updating the Habitat Supervisor
 //! itself.
 
-use crate::util;
+mod approval;
+mod artifact_cache;
+mod backup;
+mod behind;
+mod drain;
+mod embargo;
+mod fork_test;
+mod host_check;
+mod install_check;
+mod install_limit;
+mod install_thread;
+mod lag;
+mod plan;
+mod policy;
+mod quarantine;
+mod restart_lock;
+mod rollback;
+mod schedule;
+mod stage;
+mod status;
+mod telemetry;
+#[cfg(test)]
+mod test_helpers;
+mod yank;
+
+pub use self::{drain::{Drain,
+                       Drains},
+               fork_test::ForkTest,
+               host_check::{default_checks,
+                            Candidate,
+                            CoreDepsPresent,
+                            GlibcCompatible,
+                            HostCompatibilityCheck,
+                            TargetMatches},
+               install_limit::InstallLimit,
+               lag::UpdateLag,
+               plan::{DependencyChange,
+                      UpgradePlan,
+                      UpgradeStep},
+               restart_lock::RestartLock,
+               rollback::RollbackWindow,
+               schedule::CronSchedule};
+use self::{install_thread::InstallThread,
+           policy::{Decision,
+                    Policy},
+           quarantine::Quarantine,
+           status::{Noted,
+                    Status},
+           telemetry::CheckSpan};
+use crate::{error::Error as SupError,
+            event,
+            util};
+use chrono::{DateTime,
+             Utc};
+use habitat_api_client::TransferStats;
 use habitat_common::command::package::install::InstallSource;
-use habitat_core::{package::{PackageIdent,
-                             PackageInstall},
+use habitat_core::{crypto::keys::KeyCache,
+                   fs::{self as hfs,
+                        CACHE_KEY_PATH,
+                        FS_ROOT_PATH},
+                   package::{PackageIdent,
+                             PackageInstall,
+                             PackageTarget},
                    ChannelIdent};
+use habitat_http_client::ProxyAuth;
+use habitat_launcher_client::ConnectionState;
 use log::{debug,
+          info,
           trace,
           warn};
+use parking_lot::Mutex;
 use rand::Rng;
 use std::{borrow::Borrow,
-          time::Duration};
+          collections::VecDeque,
+          path::{Path,
+                 PathBuf},
+          sync::Arc,
+          time::{Duration,
+                 Instant}};
+use thiserror::Error;
 use tokio::{self,
-            sync::oneshot::{self,
-                            error::TryRecvError,
-                            Receiver,
-                            Sender},
+            sync::{oneshot::{self,
+                             error::TryRecvError,
+                             Receiver,
+                             Sender},
+                   watch,
+                   Notify},
             time as tokiotime};
 
 pub const SUP_PKG_IDENT: &str = "core/hab-sup";
 
+/// How many staging decisions `SelfUpdater::recent_updates` keeps by
+/// default.
+pub const DEFAULT_RECENT_UPDATES: usize = 10;
+
+/// How long a verified update waits for approval by default. See
+/// `SelfUpdaterOptions::approval_timeout`.
+pub const DEFAULT_APPROVAL_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How many failed checks `SelfUpdater::status_report` lists.
+const RECENT_ERRORS: usize = 5;
+
+/// The shortest temporary period `SelfUpdater::with_temporary_period`
+/// accepts.
+const MIN_TEMPORARY_PERIOD: Duration = Duration::from_secs(1);
+
+/// The longest a temporary period stays in effect.
+const MAX_TEMPORARY_DURATION: Duration = Duration::from_secs(60 * 60);
+
 // TODO (DM): Remove this deprecated env var
 const DEFAULT_PERIOD: Duration = Duration::from_secs(60);
 habitat_core::env_config_duration!(
@@ -41,12 +130,372 @@ impl SelfUpdatePeriod {
     }
 }
 
+/// Reasons the self updater refuses to stage a candidate Supervisor.
+#[derive(Debug, Error)]
+pub enum SelfUpdateError {
+    #[error("Supervisor {ident} is not compatible with this host ({check} check): {reason}")]
+    HostIncompatible {
+        ident:  PackageIdent,
+        check:  String,
+        reason: String,
+    },
+    #[error("Supervisor {ident} is not completely installed: {reason}")]
+    IncompleteInstall {
+        ident:  PackageIdent,
+        reason: String,
+    },
+    #[error("Supervisor {ident} could not be verified: {reason}")]
+    Unverified {
+        ident:  PackageIdent,
+        reason: String,
+    },
+    #[error("Invalid self-update schedule '{expression}': {reason}")]
+    InvalidSchedule {
+        expression: String,
+        reason:     String,
+    },
+    #[error("Supervisor {ident} failed its trial run: {reason}")]
+    ForkTestFailed {
+        ident:  PackageIdent,
+        reason: String,
+    },
+    #[error("Migration from Supervisor {from} to {to} failed: {reason}")]
+    MigrationFailed {
+        from:   PackageIdent,
+        to:     PackageIdent,
+        reason: String,
+    },
+    #[error("Unable to back up the running Supervisor {ident}: {reason}")]
+    Backup {
+        ident:  PackageIdent,
+        reason: String,
+    },
+    #[error("Unable to stage Supervisor {ident}: {reason}")]
+    Stage {
+        ident:  PackageIdent,
+        reason: String,
+    },
+    #[error("Unable to roll back to Supervisor {ident}: {reason}")]
+    Rollback {
+        ident:  PackageIdent,
+        reason: String,
+    },
+    #[error("No rollback window is open")]
+    NoRollbackWindow,
+}
+
+impl SelfUpdateError {
+    /// The reason to publish for declining a candidate because of this
+    /// error, if it is one that stops a candidate being staged.
+    fn decline_reason(&self) -> Option<DeclineReason> {
+        match self {
+            SelfUpdateError::HostIncompatible { .. } => Some(DeclineReason::HostIncompatible),
+            SelfUpdateError::IncompleteInstall { .. } => Some(DeclineReason::IncompleteInstall),
+            SelfUpdateError::ForkTestFailed { .. } => Some(DeclineReason::ForkTestFailed),
+            SelfUpdateError::MigrationFailed { .. } => Some(DeclineReason::MigrationFailed),
+            SelfUpdateError::Backup { .. } => Some(DeclineReason::BackupFailed),
+            SelfUpdateError::InvalidSchedule { .. }
+            | SelfUpdateError::Unverified { .. }
+            | SelfUpdateError::Stage { .. }
+            | SelfUpdateError::Rollback { .. }
+            | SelfUpdateError::NoRollbackWindow => None,
+        }
+    }
+}
+
+/// Why the self updater declined a newer Supervisor, as published to the
+/// event stream when `SelfUpdaterOptions::decline_events` is set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeclineReason {
+    Quarantined,
+    WarmingUp,
+    HostIncompatible,
+    IncompleteInstall,
+    ForkTestFailed,
+    BackupFailed,
+    MigrationFailed,
+    Embargoed,
+    Yanked,
+    NotApproved,
+    RolledBack,
+}
+
+/// Prepares the host to run a candidate Supervisor, given the ident of
+/// the running one. See `SelfUpdaterOptions::migration`.
+pub type Migration =
+    Arc<dyn Fn(&PackageIdent, &PackageInstall) -> Result<(), String> + Send + Sync>;
+
+/// What became of a newer Supervisor the self updater considered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UpdateOutcome {
+    /// It was handed over to be installed.
+    Applied,
+    /// It was declined, for the given reason.
+    Cancelled(String),
+    /// A different Supervisor became the one to apply before its
+    /// quarantine was over, or while it awaited approval.
+    Expired,
+}
+
+/// A staging decision made by the self updater.
+#[derive(Clone, Debug)]
+pub struct RecentUpdate {
+    pub ident:   PackageIdent,
+    pub channel: ChannelIdent,
+    pub at:      DateTime<Utc>,
+    pub outcome: UpdateOutcome,
+}
+
+/// Optional behavior for the `SelfUpdater`. The defaults preserve the
+/// basic "stage the newest Supervisor in the channel" behavior.
+#[derive(Clone, Default)]
+pub struct SelfUpdaterOptions {
+    /// Host compatibility checks to run in addition to
+    /// `default_checks()` before a candidate is installed.
+    pub host_checks:           Vec<Arc<dyn HostCompatibilityCheck>>,
+    /// The state of the connection to the Launcher. When given, a
+    /// verified update is held until the connection is `Connected`,
+    /// since the Launcher has to respawn the new Supervisor.
+    pub launcher_state:        Option<watch::Receiver<ConnectionState>>,
+    /// When given, the running Supervisor package is copied into this
+    /// directory before an update is staged, so it can be restored
+    /// later. See `SelfUpdater::backup`.
+    pub backup_dir:            Option<PathBuf>,
+    /// Only check the channel for a newer Supervisor, using its
+    /// metadata, and never download or stage it. A newer Supervisor is
+    /// reported by `SelfUpdater::available`.
+    pub check_only:            bool,
+    /// Check at the times given by this schedule rather than every
+    /// `period`.
+    pub schedule:              Option<CronSchedule>,
+    /// How long a newer Supervisor must remain the newest in the
+    /// channel before it is staged.
+    pub quarantine:            Option<Duration>,
+    /// How many staging decisions to keep for `recent_updates`,
+    /// defaulting to `DEFAULT_RECENT_UPDATES`.
+    pub recent_updates:        Option<usize>,
+    /// Download and install updates on a dedicated thread with lowered
+    /// CPU and IO priority, so they don't compete with services.
+    pub low_priority_install:  bool,
+    /// An artifact cache shared with other Supervisors on this host, so
+    /// that only one of them downloads each Supervisor release.
+    pub shared_artifact_cache: Option<PathBuf>,
+    /// Record each check as an OpenTelemetry span, through the global
+    /// tracer provider.
+    pub trace_checks:          bool,
+    /// Run the candidate's binary this way before staging it, and
+    /// decline it if that fails.
+    pub fork_test:             Option<ForkTest>,
+    /// Migrate data or state for the candidate once everything else
+    /// holding an update back has cleared, just before it is handed
+    /// over. If it fails the update is declined, the candidate discarded
+    /// and the running Supervisor carries on.
+    pub migration:             Option<Migration>,
+    /// How long after the Supervisor starts to hold off staging any
+    /// update, so that a freshly started Supervisor can settle. Checks
+    /// and installs still happen in the meantime.
+    pub warmup:                Option<Duration>,
+    /// Download updates no faster than this many bytes per second. Zero
+    /// or `None` leaves downloads unlimited.
+    pub bandwidth_limit:       Option<u64>,
+    /// Take this lock once an update is otherwise ready to be handed
+    /// over, and hold it until the restart has been initiated, so that
+    /// only a bounded number of Supervisors in a fleet restart at once.
+    pub restart_lock:          Option<Arc<dyn RestartLock>>,
+    /// How long after restarting into an update `SelfUpdater::rollback`
+    /// can go back to the Supervisor it replaced. The window is kept in
+    /// `backup_dir`, so it only applies when that is set. A release
+    /// rolled back from is recorded there too, and isn't updated to
+    /// again.
+    pub rollback_window:       Option<Duration>,
+    /// Publish an event to the Supervisor's event stream each time a
+    /// newer Supervisor is declined, giving the reason.
+    pub decline_events:        bool,
+    /// The Supervisor package to update from, instead of
+    /// `SUP_PKG_IDENT`. Only its origin and name are used. Updaters
+    /// tracking different packages can share a host, and even a backup
+    /// directory or artifact cache, without getting in each other's way.
+    pub sup_ident:             Option<PackageIdent>,
+    /// Hold a verified update until this file contains the candidate's
+    /// fully qualified ident, so that each self update has to be
+    /// approved out of band before the Supervisor restarts into it.
+    pub approval_file:         Option<PathBuf>,
+    /// How long a verified update waits for approval before it is
+    /// declined, defaulting to `DEFAULT_APPROVAL_TIMEOUT`. A declined
+    /// release isn't staged again unless it comes back after a
+    /// different one.
+    pub approval_timeout:      Option<Duration>,
+    /// Decline a candidate the channel still has under a publish
+    /// embargo, checking again once the embargo lifts. Depots that don't
+    /// report embargoes leave every candidate unembargoed.
+    pub honor_embargo:         bool,
+    /// Take a turn under this limit for each install, so that updaters
+    /// for different Supervisor packages on one host don't all install
+    /// at once. Give each of them a clone of the same limit. `None`
+    /// leaves installs unlimited.
+    pub install_limit:         Option<InstallLimit>,
+    /// Never stage a release the depot has yanked from the channel,
+    /// falling back to the newest one that hasn't been. Depots that
+    /// don't report yanked releases leave every release eligible.
+    pub skip_yanked:           bool,
+    /// Hold an update that is ready to be handed over while any drain
+    /// registered here is in progress, so that restarting doesn't cut
+    /// off a service letting its connections finish.
+    pub drains:                Option<Drains>,
+    /// Stage the release this many releases behind the newest in the
+    /// channel rather than the newest itself, staying put while the
+    /// channel has no more releases than this. With `skip_yanked`,
+    /// yanked releases are passed over before counting.
+    pub releases_behind:       Option<usize>,
+    /// Authenticate with the proxy to the depot using this. `None`
+    /// takes it from `HAB_PROXY_AUTH_SCHEME` and its credentials in the
+    /// environment, as other depot clients do.
+    pub proxy_auth:            Option<ProxyAuth>,
+}
+
+impl SelfUpdaterOptions {
+    /// The origin and name of the Supervisor package to update from.
+    fn tracked_ident(&self) -> PackageIdent {
+        match &self.sup_ident {
+            Some(ident) => {
+                PackageIdent::new(ident.origin.as_str(), ident.name.as_str(), None, None)
+            }
+            // SUP_PKG_IDENT will always parse as a valid PackageIdent
+            None => SUP_PKG_IDENT.parse().unwrap(),
+        }
+    }
+}
+
+/// State shared between a `SelfUpdater` and its running task. This
+/// survives the task being restarted.
+#[derive(Default)]
+struct Shared {
+    /// A temporary check period, and the instant at which it lapses.
+    temporary_period: Mutex<Option<(Duration, Instant)>>,
+    /// Wakes the task from its delay when the period is changed.
+    period_changed:   Notify,
+    /// Where the running Supervisor was backed up before the last
+    /// update was staged.
+    backup:           Mutex<Option<PathBuf>>,
+    /// The newest Supervisor seen in the channel in check-only mode, as
+    /// of the last successful check.
+    available:        Mutex<Option<PackageIdent>>,
+    /// The most recent staging decisions, oldest first.
+    recent_updates:   Mutex<VecDeque<RecentUpdate>>,
+    /// The totals of the most recent download of an update.
+    last_transfer:    Mutex<Option<TransferStats>>,
+    /// How far behind the channel the running Supervisor was at the
+    /// last check.
+    lag:              Mutex<Option<UpdateLag>>,
+    /// The rollback window still open from the last update.
+    rollback:         Mutex<Option<RollbackWindow>>,
+    /// The Supervisor to restart into on the next `updated`, after a
+    /// rollback.
+    rolled_back:      Mutex<Option<PackageInstall>>,
+    /// When the next check is due, until an update is handed over.
+    next_check:       Mutex<Option<DateTime<Utc>>>,
+    /// The outcome of the most recent check.
+    last_check:       Mutex<Option<Noted>>,
+    /// A newer Supervisor found in the channel that hasn't been handed
+    /// over yet.
+    pending:          Mutex<Option<PackageIdent>>,
+    /// Why an update ready to be handed over is being held back.
+    held:             Mutex<Option<String>>,
+    /// The plan of the update to `pending`, or `available`, as of when
+    /// that last changed.
+    plan:             Mutex<Option<UpgradePlan>>,
+    /// The most recent failed checks, oldest first.
+    recent_errors:    Mutex<VecDeque<Noted>>,
+}
+
+impl Shared {
+    /// The period to wait before the next check, given the
+    /// configured period.
+    fn period(&self, configured: Duration) -> Duration {
+        let mut temporary_period = self.temporary_period.lock();
+        match *temporary_period {
+            Some((period, until)) if Instant::now() < until => period,
+            Some(_) => {
+                info!("Self updater temporary period elapsed, reverting to {}s",
+                      configured.as_secs());
+                *temporary_period = None;
+                configured
+            }
+            None => configured,
+        }
+    }
+
+    /// Use `period`, no shorter than `MIN_TEMPORARY_PERIOD` nor longer
+    /// than `configured`, for at most `MAX_TEMPORARY_DURATION` from
+    /// `now`. Returns the period and duration actually used.
+    fn set_temporary_period(&self,
+                            configured: Duration,
+                            period: Duration,
+                            duration: Duration,
+                            now: Instant)
+                            -> (Duration, Duration) {
+        let period = period.max(MIN_TEMPORARY_PERIOD)
+                           .min(configured.max(MIN_TEMPORARY_PERIOD));
+        let duration = duration.min(MAX_TEMPORARY_DURATION);
+        *self.temporary_period.lock() = now.checked_add(duration).map(|until| (period, until));
+        (period, duration)
+    }
+
+    /// Record a staging decision, keeping at most `capacity` of them.
+    fn record(&self, update: RecentUpdate, capacity: usize) {
+        let mut recent_updates = self.recent_updates.lock();
+        while recent_updates.len() >= capacity.max(1) {
+            recent_updates.pop_front();
+        }
+        recent_updates.push_back(update);
+    }
+
+    /// Note the outcome of a check.
+    fn checked(&self, outcome: String) { *self.last_check.lock() = Some(Noted::now(outcome)); }
+
+    /// Note a failed check, keeping the most recent failures.
+    fn failed(&self, err: &SupError) {
+        let mut recent_errors = self.recent_errors.lock();
+        while recent_errors.len() >= RECENT_ERRORS {
+            recent_errors.pop_front();
+        }
+        recent_errors.push_back(Noted::now(err.to_string()));
+        self.checked(format!("failed, {}", err));
+    }
+
+    /// Note why an update is being held back, or that it no longer is.
+    fn hold(&self, reason: Option<String>) { *self.held.lock() = reason; }
+
+    /// Have the plan worked out again, once the pending update has been
+    /// downloaded and staged.
+    fn replan(&self) { *self.plan.lock() = None; }
+
+    /// Sleep for `delay`, returning early if the period is changed.
+    async fn delay(&self, delay: Duration) {
+        let next_check =
+            chrono::Duration::from_std(delay).ok().and_then(|delay| {
+                                                      Utc::now().checked_add_signed(delay)
+                                                  });
+        *self.next_check.lock() = next_check;
+        tokio::select! {
+            _ = tokiotime::sleep(delay) => {}
+            _ = self.period_changed.notified() => {
+                trace!("Self updater period changed, checking now");
+            }
+        }
+    }
+}
+
 pub struct SelfUpdater {
     rx:             Receiver<PackageInstall>,
     current:        PackageIdent,
     update_url:     String,
     update_channel: ChannelIdent,
     period:         Duration,
+    options:        SelfUpdaterOptions,
+    shared:         Arc<Shared>,
+    /// When this updater was created, which the warmup is counted from.
+    started:        Instant,
 }
 
 /// The subset of data from `SelfUpdater` needed to spawn the updater task.
@@ -55,6 +504,9 @@ struct Runner {
     update_url:     String,
     update_channel: ChannelIdent,
     period:         Duration,
+    options:        SelfUpdaterOptions,
+    shared:         Arc<Shared>,
+    started:        Instant,
 }
 
 impl<T: Borrow<SelfUpdater>> From<T> for Runner {
@@ -63,7 +515,269 @@ impl<T: Borrow<SelfUpdater>> From<T> for Runner {
         Self { current:        other.current.clone(),
                update_url:     other.update_url.clone(),
                update_channel: other.update_channel.clone(),
-               period:         other.period, }
+               period:         other.period,
+               options:        other.options.clone(),
+               shared:         Arc::clone(&other.shared),
+               started:        other.started, }
+    }
+}
+
+impl Runner {
+    /// Record a staging decision about `ident`.
+    fn record(&self, ident: &PackageIdent, outcome: UpdateOutcome) {
+        let capacity = self.options
+                           .recent_updates
+                           .unwrap_or(DEFAULT_RECENT_UPDATES);
+        self.shared.record(RecentUpdate { ident: ident.clone(),
+                                          channel: self.update_channel.clone(),
+                                          at: Utc::now(),
+                                          outcome },
+                           capacity)
+    }
+
+    /// Publish that `candidate` was declined, if there is a reason to
+    /// give and decline events are enabled.
+    fn decline(&self, candidate: &PackageIdent, reason: Option<DeclineReason>, detail: String) {
+        if let Some(reason) = reason.filter(|_| self.options.decline_events) {
+            event::supervisor_update_declined(&self.current, candidate, reason, detail);
+        }
+    }
+
+    /// Decline `candidate` for good, because of `err`.
+    fn declined(&self, candidate: &PackageIdent, err: &SelfUpdateError, span: &CheckSpan) {
+        warn!("Self updater declining update: {}", err);
+        span.decision("declined");
+        self.decline(candidate, err.decline_reason(), err.to_string());
+        self.record(candidate, UpdateOutcome::Cancelled(err.to_string()));
+        self.shared
+            .checked(format!("declined {}, {}", candidate, err));
+    }
+
+    /// The release to stage: the newest in the channel, or the one
+    /// `releases_behind` it, passing over yanked releases with
+    /// `skip_yanked`. This is the running Supervisor when there is
+    /// nothing to stage.
+    async fn select_target(&self, sup_ident: &PackageIdent) -> Result<PackageIdent, SupError> {
+        let url = &self.update_url;
+        let channel = &self.update_channel;
+        let proxy_auth = self.options.proxy_auth.as_ref();
+        if let Some(behind) = self.options.releases_behind {
+            let target = behind::target(url,
+                                        sup_ident,
+                                        channel,
+                                        behind,
+                                        self.options.skip_yanked,
+                                        proxy_auth).await?;
+            Ok(target.unwrap_or_else(|| {
+                         debug!("Self updater staying put, {} has no more than {} releases",
+                                channel, behind);
+                         self.current.clone()
+                     }))
+        } else if self.options.skip_yanked {
+            let unyanked =
+                yank::newest_unyanked(url, sup_ident, channel, &self.current, proxy_auth).await?;
+            for yanked in &unyanked.yanked {
+                info!("Self updater passing over {}, which was yanked from the channel",
+                      yanked);
+                self.decline(yanked,
+                             Some(DeclineReason::Yanked),
+                             String::from("yanked from the channel"));
+            }
+            // With nothing newer left, the running Supervisor is not
+            // newer than itself
+            Ok(unyanked.newest.unwrap_or_else(|| self.current.clone()))
+        } else {
+            util::pkg::channel_head(url, sup_ident, channel, proxy_auth).await
+        }
+    }
+
+    /// Measure how far the running Supervisor is behind the head of the
+    /// channel. That is the `target` unless it was chosen to stay behind
+    /// the head or to pass over yanked releases, which only decides what
+    /// is staged.
+    async fn measure_lag(&self, sup_ident: &PackageIdent, target: &PackageIdent) {
+        let url = &self.update_url;
+        let channel = &self.update_channel;
+        let proxy_auth = self.options.proxy_auth.as_ref();
+        let head = if self.options.releases_behind.is_some() || self.options.skip_yanked {
+            match util::pkg::channel_head(url, sup_ident, channel, proxy_auth).await {
+                Ok(head) => head,
+                Err(err) => {
+                    debug!("Unable to find the head of {} to measure the lag, {}",
+                           channel, err);
+                    return;
+                }
+            }
+        } else {
+            target.clone()
+        };
+        let lag = lag::measure(url, sup_ident, channel, &self.current, &head, proxy_auth).await;
+        *self.shared.lag.lock() = Some(lag);
+    }
+
+    /// Hold `candidate` back if `decision` says it has something to wait
+    /// out, returning how long that takes.
+    fn hold_back(&self,
+                 candidate: &PackageIdent,
+                 decision: Decision,
+                 span: &CheckSpan)
+                 -> Option<Duration> {
+        let hold = decision.hold()?;
+        let detail = hold.detail();
+        info!("Self updater holding {}, {}", candidate, detail);
+        span.decision(hold.name);
+        self.shared
+            .checked(format!("holding {}, {}", candidate, detail));
+        self.decline(candidate, decision.decline_reason(), detail);
+        Some(hold.remaining)
+    }
+
+    /// Check that this host can run `candidate`, then install, verify
+    /// and stage it, returning what was staged, or `None` if the
+    /// candidate was declined. The error is a failure to reach the
+    /// depot or to install.
+    async fn install_and_stage(&self,
+                               candidate: &PackageIdent,
+                               sup_ident: &PackageIdent,
+                               host_checks: &[Arc<dyn HostCompatibilityCheck>],
+                               install_thread: Option<&InstallThread>,
+                               span: &CheckSpan,
+                               failures: &mut u32)
+                               -> Result<Option<PackageIdent>, SupError> {
+        let current = &self.current;
+        let channel = &self.update_channel;
+        let compatible = SelfUpdater::check_host(&self.update_url,
+                                                 candidate,
+                                                 channel,
+                                                 host_checks,
+                                                 self.options.proxy_auth.as_ref()).await;
+        SelfUpdater::publish_outcome(current, channel, &compatible, failures);
+        if let Err(err) = compatible? {
+            self.declined(candidate, &err, span);
+            return Ok(None);
+        }
+        let package = self.install(candidate, install_thread, span).await;
+        SelfUpdater::publish_outcome(current, channel, &package, failures);
+        let package = package?;
+        let verify_span = span.step("verify");
+        let mut ready = SelfUpdater::verify_candidate(&package);
+        if let Some(fork_test) = self.options.fork_test.as_ref().filter(|_| ready.is_ok()) {
+            ready = SelfUpdater::fork_test(&package, fork_test).await;
+        }
+        let backup_dir = self.options.backup_dir.as_deref();
+        let ready =
+            ready.and_then(|_| SelfUpdater::back_up_current(current, backup_dir, &self.shared))
+                 .and_then(|_| SelfUpdater::stage(package.ident(), sup_ident));
+        verify_span.end(&ready);
+        if let Err(err) = ready {
+            self.declined(package.ident(), &err, span);
+            SelfUpdater::discard(package.ident(), sup_ident);
+            return Ok(None);
+        }
+        span.decision("staged");
+        event::supervisor_update_staged(current, package.ident());
+        self.shared.checked(format!("staged {}", package.ident()));
+        if let Some(approval_file) = self.options.approval_file.as_deref() {
+            info!("Self updater holding {} until it is approved in {}",
+                  package.ident(),
+                  approval_file.display());
+        }
+        self.shared.replan();
+        Ok(Some(package.ident().clone()))
+    }
+
+    /// Install `candidate`, on the low priority install thread if there
+    /// is one, taking a turn under the install limit.
+    async fn install(&self,
+                     candidate: &PackageIdent,
+                     install_thread: Option<&InstallThread>,
+                     span: &CheckSpan)
+                     -> Result<PackageInstall, SupError> {
+        let install_span = span.step("install");
+        let permit = match &self.options.install_limit {
+            Some(install_limit) => Some(install_limit.acquire().await),
+            None => None,
+        };
+        let url = &self.update_url;
+        let source = InstallSource::from(candidate.clone());
+        let channel = &self.update_channel;
+        let shared_cache = self.options.shared_artifact_cache.as_deref();
+        let bandwidth_limit = self.options.bandwidth_limit;
+        let proxy_auth = self.options.proxy_auth.as_ref();
+        let installed = match install_thread {
+            Some(install_thread) => {
+                install_thread.install(url,
+                                       &source,
+                                       channel,
+                                       shared_cache,
+                                       bandwidth_limit,
+                                       proxy_auth)
+                              .await
+            }
+            None => None,
+        };
+        let installed = match installed {
+            Some(installed) => installed,
+            None => {
+                artifact_cache::install(url,
+                                        &source,
+                                        channel,
+                                        shared_cache,
+                                        bandwidth_limit,
+                                        proxy_auth).await
+            }
+        };
+        let installed =
+            installed.map(|(package, transfer)| {
+                         SelfUpdater::record_transfer(package.ident(), transfer, &self.shared);
+                         package
+                     });
+        drop(permit);
+        install_span.end(&installed);
+        installed
+    }
+
+    /// Wait out everything that holds an approved update back, then
+    /// hand `ident` over to be restarted into.
+    async fn restart_into(&self,
+                          ident: &PackageIdent,
+                          sup_ident: &PackageIdent,
+                          launcher_state: Option<&mut watch::Receiver<ConnectionState>>)
+                          -> Result<PackageInstall, SelfUpdateError> {
+        let shared = &self.shared;
+        if let Some(launcher_state) = launcher_state {
+            shared.hold(Some(String::from("waiting for the Launcher connection")));
+            SelfUpdater::wait_for_launcher(launcher_state).await;
+        }
+        // Before the restart lock, so that it isn't held, keeping other
+        // Supervisors from restarting, while connections drain
+        if let Some(drains) = self.options.drains.as_ref() {
+            shared.hold(Some(String::from("waiting for connection drains")));
+            drains.wait().await;
+        }
+        let restart_lock = self.options.restart_lock.as_deref();
+        if let Some(lock) = restart_lock {
+            shared.hold(Some(String::from("waiting for the restart lock")));
+            restart_lock::acquire(lock, ident, restart_lock::RETRY_INTERVAL).await;
+        }
+        let rollback = self.options
+                           .rollback_window
+                           .zip(self.options.backup_dir.as_deref());
+        // Only now may the Launcher start it
+        let handed_over = SelfUpdater::hand_over(&self.current,
+                                                 ident,
+                                                 sup_ident,
+                                                 self.options.migration.as_ref(),
+                                                 rollback,
+                                                 shared,
+                                                 &FS_ROOT_PATH);
+        if handed_over.is_err() {
+            if let Some(lock) = restart_lock {
+                lock.release(ident);
+            }
+            shared.hold(None);
+        }
+        handed_over
     }
 }
 
@@ -73,16 +787,157 @@ impl SelfUpdater {
                update_channel: ChannelIdent,
                period: Duration)
                -> Self {
+        Self::with_options(current,
+                           update_url,
+                           update_channel,
+                           period,
+                           SelfUpdaterOptions::default())
+    }
+
+    pub fn with_options(current: &PackageIdent,
+                        update_url: String,
+                        update_channel: ChannelIdent,
+                        period: Duration,
+                        options: SelfUpdaterOptions)
+                        -> Self {
+        let shared = Arc::<Shared>::default();
+        if let (Some(_), Some(backup_dir)) = (options.rollback_window, &options.backup_dir) {
+            *shared.rollback.lock() =
+                RollbackWindow::load(backup_dir, &options.tracked_ident(), Utc::now());
+        }
+        let started = Instant::now();
         let runner = Runner { current: current.clone(),
                               update_url: update_url.clone(),
                               update_channel: update_channel.clone(),
-                              period };
+                              period,
+                              options: options.clone(),
+                              shared: Arc::clone(&shared),
+                              started };
         let rx = Self::init(runner);
         SelfUpdater { rx,
                       current: current.clone(),
                       update_url,
                       update_channel,
-                      period }
+                      period,
+                      options,
+                      shared,
+                      started }
+    }
+
+    /// Check for updates every `period` for the next `duration`, after
+    /// which the configured period is used again. The new period takes
+    /// effect immediately, rather than after the current delay.
+    ///
+    /// Calling this again replaces any temporary period still in
+    /// effect. The period is clamped to at most the configured one and
+    /// the duration to `MAX_TEMPORARY_DURATION`.
+    pub fn with_temporary_period(&self, period: Duration, duration: Duration) {
+        let (period, duration) =
+            self.shared
+                .set_temporary_period(self.period, period, duration, Instant::now());
+        info!("Self updater checking every {}s for the next {}s",
+              period.as_secs(),
+              duration.as_secs());
+        self.shared.period_changed.notify_one();
+    }
+
+    /// The snapshot of the running Supervisor taken before the most
+    /// recent update was staged, if a backup directory is configured.
+    pub fn backup(&self) -> Option<PathBuf> { self.shared.backup.lock().clone() }
+
+    /// A Supervisor newer than the running one that was found in the
+    /// channel, when running in check-only mode.
+    pub fn available(&self) -> Option<PackageIdent> { self.shared.available.lock().clone() }
+
+    /// The most recent staging decisions, oldest first. This is bounded
+    /// by the `recent_updates` option.
+    pub fn recent_updates(&self) -> Vec<RecentUpdate> {
+        self.shared.recent_updates.lock().iter().cloned().collect()
+    }
+
+    /// How much was downloaded for the most recent update, and how
+    /// fast. This shows whether the `bandwidth_limit` is honored.
+    pub fn last_transfer(&self) -> Option<TransferStats> { *self.shared.last_transfer.lock() }
+
+    /// How far the running Supervisor was behind the newest one in the
+    /// channel at the last successful check.
+    pub fn update_lag(&self) -> Option<UpdateLag> { self.shared.lag.lock().clone() }
+
+    /// A short human-readable summary of the self updater, a line per
+    /// item, served by the HTTP gateway's `/self_update` endpoint.
+    pub fn status_report(&self) -> String {
+        let shared = &self.shared;
+        let pending = shared.pending.lock().clone().or_else(|| self.available());
+        let status =
+            Status { current: &self.current,
+                     channel: &self.update_channel,
+                     url: &self.update_url,
+                     check_only: self.options.check_only,
+                     next_check: *shared.next_check.lock(),
+                     last_check: shared.last_check.lock().clone(),
+                     lag: self.update_lag(),
+                     pending,
+                     held: shared.held.lock().clone(),
+                     recent_errors: shared.recent_errors.lock().iter().cloned().collect() };
+        status::render(&status, Utc::now())
+    }
+
+    /// What applying the next update would entail, for review before
+    /// approving it. See `SelfUpdaterOptions::approval_file`.
+    ///
+    /// This is only worked out when the update changes, so it is cheap
+    /// to ask for often.
+    pub fn plan(&self) -> UpgradePlan {
+        let to = self.shared
+                     .pending
+                     .lock()
+                     .clone()
+                     .or_else(|| self.available());
+        let mut plan = self.shared.plan.lock();
+        match plan.as_ref() {
+            Some(plan) if plan.to == to => plan.clone(),
+            _ => {
+                let planned = self.work_out_plan(to);
+                *plan = Some(planned.clone());
+                planned
+            }
+        }
+    }
+
+    fn work_out_plan(&self, to: Option<PackageIdent>) -> UpgradePlan {
+        let caches = self.artifact_caches();
+        let download_bytes = to.as_ref()
+                               .and_then(|to| plan::artifact_bytes(to, &caches))
+                               .or_else(|| self.last_transfer().map(|transfer| transfer.bytes));
+        // A verified candidate is kept out of the package tree until it
+        // is handed over
+        let sup_ident = self.options.tracked_ident();
+        let deps = |ident: &PackageIdent| {
+            util::pkg::installed(ident).or_else(|| stage::staged(ident, &FS_ROOT_PATH, &sup_ident))
+                                       .and_then(|install| install.deps().ok())
+        };
+        let dependency_changes = match to.as_ref().and_then(deps) {
+            Some(to) => deps(&self.current).map(|from| plan::dependency_changes(&from, &to)),
+            None => None,
+        };
+        UpgradePlan { from: self.current.clone(),
+                      to,
+                      channel: self.update_channel.clone(),
+                      url: self.update_url.clone(),
+                      download_bytes,
+                      dependency_changes,
+                      steps: plan::steps(&self.options) }
+    }
+
+    /// Where downloaded artifacts are kept, the shared cache first since
+    /// that is what installs go through.
+    fn artifact_caches(&self) -> Vec<PathBuf> {
+        self.options
+            .shared_artifact_cache
+            .iter()
+            .cloned()
+            .chain(Some(hfs::cache_artifact_path(None::<String>)))
+            .collect()
     }
 
     /// Spawn a new Supervisor updater task.
@@ -92,44 +947,614 @@ impl SelfUpdater {
         rx
     }
 
-    async fn run(tx: Sender<PackageInstall>, runner: Runner) {
-        // SUP_PKG_IDENT will always parse as a valid PackageIdent,
-        // and thus a valid InstallSource
-        let install_source: InstallSource = SUP_PKG_IDENT.parse().unwrap();
+    async fn run(tx: Sender<PackageInstall>, mut runner: Runner) {
+        let sup_ident = runner.options.tracked_ident();
+        let mut launcher_state = runner.options.launcher_state.take();
         let Runner { current,
                      update_url,
                      update_channel,
-                     period, } = runner;
-        let period = SelfUpdatePeriod::get().unwrap_or(period);
-        let splay = Duration::from_secs(rand::thread_rng().gen_range(0..period.as_secs()));
-        debug!("Starting self updater with current package {} in {}s",
+                     period,
+                     options,
+                     shared,
+                     started, } = &runner;
+        let proxy_auth = options.proxy_auth.as_ref();
+        let mut quarantine = options.quarantine.map(Quarantine::new);
+        let host_checks = default_checks().into_iter()
+                                          .chain(options.host_checks.iter().cloned())
+                                          .collect::<Vec<_>>();
+        let period = SelfUpdatePeriod::get().unwrap_or(*period);
+        let schedule = options.schedule.as_ref();
+        let first = match schedule {
+            Some(schedule) => schedule.until_next(),
+            None => Duration::from_secs(rand::thread_rng().gen_range(0..period.as_secs())),
+        };
+        debug!("Starting self updater for {} with current package {} in {}s",
+               sup_ident,
                current,
-               splay.as_secs());
-        tokiotime::sleep(splay).await;
+               first.as_secs());
+        shared.delay(first).await;
+        if options.check_only {
+            Self::check(current,
+                        &sup_ident,
+                        update_url,
+                        update_channel,
+                        period,
+                        schedule,
+                        proxy_auth,
+                        shared).await;
+            return;
+        }
+        // Nothing staged before a restart has been approved or locked for
+        stage::clear(&FS_ROOT_PATH, &sup_ident);
+        let install_thread = if options.low_priority_install {
+            InstallThread::start()
+        } else {
+            None
+        };
+        let mut failures = 0;
+        let mut found = None;
+        // The verified candidate out of the package tree awaiting approval,
+        // and since when
+        let mut staged: Option<(PackageIdent, Instant)> = None;
+        // The candidate that wasn't approved in time, passed over until the
+        // channel moves on from it
+        let mut unapproved = None;
+        let approval_timeout = options.approval_timeout.unwrap_or(DEFAULT_APPROVAL_TIMEOUT);
+        // Not updated to again until the channel moves past them
+        let rolled_back = options.backup_dir
+                                 .as_deref()
+                                 .map(|backup_dir| rollback::rolled_back(backup_dir, &sup_ident))
+                                 .unwrap_or_default();
         loop {
-            match util::pkg::install_no_ui(&update_url, &install_source, &update_channel).await {
-                Ok(package) => {
-                    if &current < package.ident() {
-                        debug!("Self updater installing newer Supervisor, {}",
-                               package.ident());
-                        tx.send(package).expect("Main thread has gone away!");
-                        break;
+            let mut retry_after = None;
+            let mut recheck_after = None;
+            event::supervisor_update_check_started(current, update_channel);
+            let span = CheckSpan::start(options.trace_checks);
+            // Everything that can hold a candidate back is decided from
+            // the channel's metadata, before anything is installed
+            let target = runner.select_target(&sup_ident).await;
+            Self::publish_outcome(current, update_channel, &target, &mut failures);
+            if let Ok(target) = &target {
+                runner.measure_lag(&sup_ident, target).await;
+            }
+            let embargo =
+                match &target {
+                    Ok(target) if options.honor_embargo && current < target => {
+                        embargo::remaining(update_url,
+                                           target,
+                                           update_channel,
+                                           proxy_auth,
+                                           Utc::now()).await
+                    }
+                    _ => None,
+                };
+            let now = Instant::now();
+            let decided = target.map(|target| {
+                                    let policy = Policy { now,
+                                                          started: *started,
+                                                          warmup: options.warmup,
+                                                          quarantine: quarantine.as_ref(),
+                                                          embargo,
+                                                          rolled_back: &rolled_back };
+                                    let decision = policy::decide(current, &target, &policy);
+                                    (target, decision)
+                                });
+            if let Ok((target, _)) = &decided {
+                // A candidate awaiting approval that is no longer the one to
+                // apply, since a newer release appeared or it was yanked, is
+                // dropped
+                match staged.take() {
+                    Some((superseded, _)) if superseded != *target => {
+                        info!("Self updater dropping {}, {} is now the release to apply",
+                              superseded, target);
+                        runner.record(&superseded, UpdateOutcome::Expired);
+                        Self::discard(&superseded, &sup_ident);
+                        shared.hold(None);
+                    }
+                    still_staged => staged = still_staged,
+                }
+                if unapproved.as_ref() != Some(target) {
+                    unapproved = None;
+                }
+            }
+            match decided {
+                Ok((_, Decision::NotNewer)) => {
+                    debug!("{} package found is not newer than ours", sup_ident);
+                    span.decision("not_newer");
+                    shared.checked(String::from("up to date"));
+                    *shared.pending.lock() = None;
+                }
+                Ok((candidate, Decision::RolledBack)) => {
+                    debug!("Self updater passing over {}, which it rolled back from",
+                           candidate);
+                    span.decision("rolled_back");
+                    let detail = String::from("rolled back from before");
+                    shared.checked(format!("passing over {}, {}", candidate, detail));
+                    runner.decline(&candidate, Decision::RolledBack.decline_reason(), detail);
+                    *shared.pending.lock() = None;
+                }
+                Ok((candidate, decision)) => {
+                    span.candidate(&candidate);
+                    if found.as_ref() != Some(&candidate) {
+                        event::supervisor_update_candidate_found(current,
+                                                                 &candidate,
+                                                                 update_channel);
+                        found = Some(candidate.clone());
+                    }
+                    *shared.pending.lock() = Some(candidate.clone());
+                    if let Some(quarantine) = quarantine.as_mut() {
+                        if let Some(expired) = quarantine.quarantined()
+                                                         .filter(|ident| **ident != candidate)
+                        {
+                            runner.record(expired, UpdateOutcome::Expired);
+                        }
+                        quarantine.observe(&candidate, now);
+                    }
+                    if let Some(remaining) = runner.hold_back(&candidate, decision, &span) {
+                        recheck_after = Some(remaining);
+                    } else if unapproved.as_ref() == Some(&candidate) {
+                        debug!("Self updater passing over {}, which was not approved in time",
+                               candidate);
+                        span.decision("not_approved");
+                        shared.checked(format!("passing over {}, not approved in time", candidate));
                     } else {
-                        debug!("Supervisor package found is not newer than ours");
+                        if staged.is_none() {
+                            let ready = runner.install_and_stage(&candidate,
+                                                                 &sup_ident,
+                                                                 &host_checks,
+                                                                 install_thread.as_ref(),
+                                                                 &span,
+                                                                 &mut failures)
+                                              .await;
+                            match ready {
+                                Ok(Some(ident)) => staged = Some((ident, Instant::now())),
+                                Ok(None) => {}
+                                Err(err) => {
+                                    retry_after = Self::report_failure(&err);
+                                    span.decision("failed");
+                                    shared.failed(&err);
+                                }
+                            }
+                        }
+                        if let Some((ident, since)) = staged.clone() {
+                            let approval = match options.approval_file.as_deref() {
+                                Some(approval_file) => {
+                                    approval::check(approval_file,
+                                                    &ident,
+                                                    since,
+                                                    Instant::now(),
+                                                    approval_timeout)
+                                }
+                                None => approval::Approval::Approved,
+                            };
+                            match approval {
+                                approval::Approval::Approved => {
+                                    staged = None;
+                                    let handed_over = runner.restart_into(&ident,
+                                                                          &sup_ident,
+                                                                          launcher_state.as_mut())
+                                                            .await;
+                                    match handed_over {
+                                        Ok(package) => {
+                                            shared.hold(Some(format!("restarting into {}",
+                                                                     package.ident())));
+                                            *shared.next_check.lock() = None;
+                                            debug!("Self updater installing newer Supervisor, {}",
+                                                   package.ident());
+                                            runner.record(package.ident(), UpdateOutcome::Applied);
+                                            tx.send(package).expect("Main thread has gone away!");
+                                            break;
+                                        }
+                                        Err(err) => runner.declined(&ident, &err, &span),
+                                    }
+                                }
+                                approval::Approval::Pending(reason) => {
+                                    debug!("Self updater holding {}, {}", ident, reason);
+                                    span.decision("awaiting_approval");
+                                    shared.hold(Some(format!("holding {}, {}", ident, reason)));
+                                    recheck_after = Some(approval::POLL_INTERVAL);
+                                }
+                                approval::Approval::TimedOut => {
+                                    let detail = format!("not approved within {}s",
+                                                         approval_timeout.as_secs());
+                                    warn!("Self updater declining {}, {}", ident, detail);
+                                    span.decision("declined");
+                                    shared.hold(None);
+                                    runner.decline(&ident,
+                                                   Some(DeclineReason::NotApproved),
+                                                   detail.clone());
+                                    runner.record(&ident, UpdateOutcome::Cancelled(detail.clone()));
+                                    shared.checked(format!("declined {}, {}", ident, detail));
+                                    Self::discard(&ident, &sup_ident);
+                                    unapproved = Some(ident);
+                                }
+                            }
+                        }
                     }
                 }
                 Err(err) => {
-                    warn!("Self updater failed to get latest, {}", err);
+                    retry_after = Self::report_failure(&err);
+                    span.decision("failed");
+                    shared.failed(&err);
+                }
+            }
+            // The check is over; don't count the delay
+            drop(span);
+            let delay = shared.period(Self::next_delay(period, schedule));
+            // Look again as soon as whatever holds the candidate back
+            // lifts, unless the depot has asked us to wait longer
+            let delay = recheck_after.map_or(delay, |recheck| delay.min(recheck))
+                                     .max(retry_after.unwrap_or_default());
+            trace!("Self updater delaying for {}s", delay.as_secs());
+            shared.delay(delay).await;
+        }
+    }
+
+    /// Report newer Supervisors in the channel without ever installing
+    /// them. This never returns.
+    async fn check(current: &PackageIdent,
+                   sup_ident: &PackageIdent,
+                   update_url: &str,
+                   update_channel: &ChannelIdent,
+                   period: Duration,
+                   schedule: Option<&CronSchedule>,
+                   proxy_auth: Option<&ProxyAuth>,
+                   shared: &Shared) {
+        let mut failures = 0;
+        loop {
+            let mut retry_after = None;
+            event::supervisor_update_check_started(current, update_channel);
+            let latest =
+                util::pkg::channel_head(update_url, sup_ident, update_channel, proxy_auth).await;
+            Self::publish_outcome(current, update_channel, &latest, &mut failures);
+            if let Ok(latest) = &latest {
+                let lag = lag::measure(update_url,
+                                       sup_ident,
+                                       update_channel,
+                                       current,
+                                       latest,
+                                       proxy_auth).await;
+                *shared.lag.lock() = Some(lag);
+            }
+            match latest {
+                Ok(latest) if current < &latest => {
+                    let mut available = shared.available.lock();
+                    if available.as_ref() != Some(&latest) {
+                        info!("Self updater found newer Supervisor {} (check only)",
+                              latest);
+                        event::supervisor_update_candidate_found(current, &latest, update_channel);
+                        *available = Some(latest.clone());
+                    }
+                    shared.checked(format!("found {}", latest));
+                }
+                Ok(_) => {
+                    debug!("{} package found is not newer than ours", sup_ident);
+                    shared.checked(String::from("up to date"));
+                    // Whatever was newer has been demoted or yanked since
+                    *shared.available.lock() = None;
+                }
+                Err(err) => {
+                    retry_after = Self::report_failure(&err);
+                    shared.failed(&err);
+                }
+            }
+            let delay = shared.period(Self::next_delay(period, schedule))
+                              .max(retry_after.unwrap_or_default());
+            trace!("Self updater delaying for {}s", delay.as_secs());
+            shared.delay(delay).await;
+        }
+    }
+
+    /// Publish an event for a failed check, or for the first one to
+    /// succeed after `failures` failed in a row, keeping the count.
+    fn publish_outcome<T>(current: &PackageIdent,
+                          channel: &ChannelIdent,
+                          outcome: &Result<T, SupError>,
+                          failures: &mut u32) {
+        match outcome {
+            Ok(_) if *failures > 0 => {
+                event::supervisor_update_recovered(current, channel, *failures);
+                *failures = 0;
+            }
+            Ok(_) => {}
+            Err(err) => {
+                *failures = failures.saturating_add(1);
+                event::supervisor_update_error(current, channel, err.to_string());
+            }
+        }
+    }
+
+    /// Log and keep the totals of a download, if anything was
+    /// downloaded.
+    fn record_transfer(ident: &PackageIdent, transfer: TransferStats, shared: &Shared) {
+        if transfer.bytes == 0 {
+            return;
+        }
+        info!("Self updater downloaded {} bytes for {} in {}s ({} bytes/s)",
+              transfer.bytes,
+              ident,
+              transfer.elapsed.as_secs(),
+              transfer.bytes_per_sec());
+        *shared.last_transfer.lock() = Some(transfer);
+    }
+
+    /// The delay until the next check, unless a temporary period is in
+    /// effect.
+    fn next_delay(period: Duration, schedule: Option<&CronSchedule>) -> Duration {
+        schedule.map_or(period, CronSchedule::until_next)
+    }
+
+    /// Log a failure to reach the depot, returning how long the depot
+    /// asked us to wait before trying again, if it did.
+    fn report_failure(err: &SupError) -> Option<Duration> {
+        if let Some(window) = err.maintenance_window() {
+            match window {
+                Some(window) => {
+                    info!("Self updater found depot in maintenance, next check in {}s",
+                          window.as_secs())
                 }
+                None => info!("Self updater found depot in maintenance"),
             }
-            trace!("Self updater delaying for {}s", period.as_secs());
-            tokiotime::sleep(period).await;
+            window
+        } else if err.is_proxy_auth_failed() {
+            warn!("Self updater could not authenticate with the HTTP proxy, {}",
+                  err);
+            None
+        } else {
+            warn!("Self updater failed to get latest, {}", err);
+            None
         }
     }
 
+    /// Wait until the connection to the Launcher is healthy. If the
+    /// Launcher client has gone away there is nothing to wait for.
+    async fn wait_for_launcher(launcher_state: &mut watch::Receiver<ConnectionState>) {
+        let state = *launcher_state.borrow();
+        if state != ConnectionState::Connected {
+            info!("Self updater holding update while the Launcher connection is {:?}",
+                  state);
+            if launcher_state.wait_for(|state| *state == ConnectionState::Connected)
+                             .await
+                             .is_err()
+            {
+                debug!("Launcher connection state is no longer tracked");
+            }
+        }
+    }
+
+    /// Snapshot the running Supervisor into `backup_dir`, if one is
+    /// configured, and record where it went.
+    fn back_up_current(current: &PackageIdent,
+                       backup_dir: Option<&Path>,
+                       shared: &Shared)
+                       -> Result<(), SelfUpdateError> {
+        let backup_dir = match backup_dir {
+            Some(backup_dir) => backup_dir,
+            None => return Ok(()),
+        };
+        let path = backup::snapshot(current, &FS_ROOT_PATH, backup_dir).map_err(|reason| {
+                       SelfUpdateError::Backup { ident: current.clone(),
+                                                 reason }
+                   })?;
+        debug!("Self updater backed up {} to {}", current, path.display());
+        *shared.backup.lock() = Some(path);
+        Ok(())
+    }
+
+    /// Move a verified candidate out of the package tree until every
+    /// gate has passed, so that a restart in the meantime doesn't start
+    /// it.
+    fn stage(candidate: &PackageIdent, sup_ident: &PackageIdent) -> Result<(), SelfUpdateError> {
+        stage::stage(candidate, &FS_ROOT_PATH, sup_ident).map_err(|reason| {
+            SelfUpdateError::Stage { ident: candidate.clone(),
+                                     reason }
+        })
+    }
+
+    /// Move a declined candidate out of the package tree, so that it
+    /// isn't started when the Supervisor next restarts.
+    fn discard(candidate: &PackageIdent, sup_ident: &PackageIdent) {
+        if let Err(err) = stage::discard(candidate, &FS_ROOT_PATH, sup_ident) {
+            warn!("Self updater unable to discard {}, {}", candidate, err);
+        }
+    }
+
+    /// Record that `current` can be rolled back to for `window` after
+    /// restarting into `candidate`.
+    fn open_rollback_window(current: &PackageIdent,
+                            candidate: &PackageIdent,
+                            window: Duration,
+                            backup_dir: &Path,
+                            sup_ident: &PackageIdent,
+                            shared: &Shared) {
+        let snapshot = shared.backup.lock().clone();
+        let window = RollbackWindow::open(current, candidate, snapshot, window, Utc::now());
+        if let Err(err) = window.save(backup_dir, sup_ident) {
+            warn!("Self updater unable to record the rollback window to {}, {}",
+                  current, err);
+        }
+    }
+
+    /// Hand the staged `candidate` over to be restarted into: put it
+    /// back in the package tree under `fs_root`, run the migration and
+    /// open the rollback window, if one is configured. Should putting it
+    /// back or the migration fail, the candidate is discarded again and
+    /// the rollback window left as it was.
+    fn hand_over(current: &PackageIdent,
+                 candidate: &PackageIdent,
+                 sup_ident: &PackageIdent,
+                 migration: Option<&Migration>,
+                 rollback: Option<(Duration, &Path)>,
+                 shared: &Shared,
+                 fs_root: &Path)
+                 -> Result<PackageInstall, SelfUpdateError> {
+        let promoted = stage::promote(candidate, fs_root, sup_ident).map_err(|reason| {
+                           SelfUpdateError::Stage { ident: candidate.clone(),
+                                                    reason }
+                       });
+        let handed_over = promoted.and_then(|package| {
+                                      Self::migrate(current, &package, migration).map(|_| package)
+                                  });
+        match handed_over {
+            Ok(package) => {
+                if let Some((window, backup_dir)) = rollback {
+                    Self::open_rollback_window(current, candidate, window, backup_dir, sup_ident,
+                                               shared);
+                }
+                Ok(package)
+            }
+            Err(err) => {
+                if let Err(reason) = stage::discard(candidate, fs_root, sup_ident) {
+                    warn!("Self updater unable to discard {}, {}", candidate, reason);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Run the configured migration from `current` to `candidate`.
+    fn migrate(current: &PackageIdent,
+               candidate: &PackageInstall,
+               migration: Option<&Migration>)
+               -> Result<(), SelfUpdateError> {
+        let migration = match migration {
+            Some(migration) => migration,
+            None => return Ok(()),
+        };
+        debug!("Self updater migrating from {} to {}",
+               current,
+               candidate.ident());
+        migration(current, candidate).map_err(|reason| {
+                                         let to = candidate.ident().clone();
+                                         SelfUpdateError::MigrationFailed { from: current.clone(),
+                                                                            to,
+                                                                            reason }
+                                     })
+    }
+
+    /// Check that this host can run `candidate`, as the channel
+    /// describes it, before anything is downloaded. The outer error is a
+    /// failure to reach the depot.
+    async fn check_host(update_url: &str,
+                        candidate: &PackageIdent,
+                        update_channel: &ChannelIdent,
+                        host_checks: &[Arc<dyn HostCompatibilityCheck>],
+                        proxy_auth: Option<&ProxyAuth>)
+                        -> Result<Result<(), SelfUpdateError>, SupError> {
+        let package =
+            util::pkg::release_metadata(update_url, candidate, update_channel, proxy_auth).await?;
+        let candidate = Candidate::from_metadata(package, PackageTarget::active_target());
+        Ok(Self::check_compatible(&candidate, host_checks))
+    }
+
+    /// Run `host_checks` against `candidate`.
+    fn check_compatible(candidate: &Candidate,
+                        host_checks: &[Arc<dyn HostCompatibilityCheck>])
+                        -> Result<(), SelfUpdateError> {
+        host_check::check_host(candidate, host_checks).map_err(|(check, reason)| {
+            SelfUpdateError::HostIncompatible { ident: candidate.ident.clone(),
+                                                check,
+                                                reason }
+        })
+    }
+
+    /// Ensure a newer candidate is completely installed before it is
+    /// staged.
+    fn verify_candidate(candidate: &PackageInstall) -> Result<(), SelfUpdateError> {
+        install_check::check_installable(candidate, &FS_ROOT_PATH).map_err(|reason| {
+            SelfUpdateError::IncompleteInstall { ident: candidate.ident().clone(),
+                                                 reason }
+        })
+    }
+
+    /// Run the candidate's binary as configured by `fork_test`.
+    async fn fork_test(candidate: &PackageInstall,
+                       fork_test: &ForkTest)
+                       -> Result<(), SelfUpdateError> {
+        fork_test.run(candidate, &FS_ROOT_PATH)
+                 .await
+                 .map_err(|reason| {
+                     SelfUpdateError::ForkTestFailed { ident: candidate.ident().clone(),
+                                                       reason }
+                 })
+    }
+
+    /// The window, still open after restarting into an update, during
+    /// which `rollback` can go back to the Supervisor it replaced.
+    pub fn rollback_window(&self) -> Option<RollbackWindow> {
+        self.shared
+            .rollback
+            .lock()
+            .clone()
+            .filter(|window| window.is_open(Utc::now()))
+    }
+
+    /// Go back to the Supervisor the running one replaced, if the
+    /// rollback window is still open. The rolled back Supervisor is
+    /// returned by the next call to `updated`, just like an update.
+    pub fn rollback(&self) -> Result<PackageIdent, SelfUpdateError> {
+        let (window, backup_dir) = match (self.rollback_window(), &self.options.backup_dir) {
+            (Some(window), Some(backup_dir)) => (window, backup_dir),
+            _ => return Err(SelfUpdateError::NoRollbackWindow),
+        };
+        let install = window.roll_back(&FS_ROOT_PATH, backup_dir)
+                            .map_err(|reason| {
+                                SelfUpdateError::Rollback { ident: window.previous.clone(),
+                                                            reason }
+                            })?;
+        info!("Self updater rolling back from {} to {}",
+              window.updated_to, window.previous);
+        let sup_ident = self.options.tracked_ident();
+        if let Err(err) = rollback::record_rolled_back(backup_dir, &sup_ident, &window.updated_to) {
+            warn!("Self updater unable to record rolling back from {}, {}",
+                  window.updated_to, err);
+        }
+        RollbackWindow::close(backup_dir, &sup_ident);
+        *self.shared.rollback.lock() = None;
+        *self.shared.rolled_back.lock() = Some(install);
+        Ok(window.previous)
+    }
+
+    /// Check the running Supervisor's own install the way a candidate
+    /// is checked before it is staged, so that an install damaged on
+    /// disk is found before the Supervisor next has to restart from it.
+    /// The cached artifact it was installed from is verified again
+    /// against its origin's signing key, then the install's completeness
+    /// and compatibility with the host are checked.
+    pub fn verify_current(&self) -> Result<(), SelfUpdateError> {
+        let key_cache = KeyCache::new(&*CACHE_KEY_PATH);
+        let verified =
+            install_check::verify_artifact(&self.current, &self.artifact_caches(), &key_cache);
+        verified.map_err(|reason| {
+                    SelfUpdateError::Unverified { ident: self.current.clone(),
+                                                  reason }
+                })?;
+        let incomplete = |reason| {
+            SelfUpdateError::IncompleteInstall { ident: self.current.clone(),
+                                                 reason }
+        };
+        let install = PackageInstall::load(&self.current, Some(FS_ROOT_PATH.as_path()))
+            .map_err(|err| incomplete(err.to_string()))?;
+        Self::verify_candidate(&install)?;
+        let candidate = Candidate::from_install(&install).map_err(incomplete)?;
+        let host_checks = default_checks().into_iter()
+                                          .chain(self.options.host_checks.iter().cloned())
+                                          .collect::<Vec<_>>();
+        Self::check_compatible(&candidate, &host_checks)
+    }
+
     pub async fn updated(&mut self) -> Option<PackageInstall> {
+        if let Some(install) = self.shared.rolled_back.lock().take() {
+            return Some(install);
+        }
         match self.rx.try_recv() {
-            Ok(package) => Some(package),
+            Ok(package) => {
+                // Receiving the package is what initiates the restart
+                if let Some(restart_lock) = &self.options.restart_lock {
+                    restart_lock.release(package.ident());
+                }
+                Some(package)
+            }
             Err(TryRecvError::Empty) => None,
             Err(TryRecvError::Closed) => {
                 debug!("Self updater has died, restarting...");
@@ -139,3 +1564,207 @@ impl SelfUpdater {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{test_helpers::install,
+                *};
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    #[test]
+    fn temporary_period_is_used_until_it_lapses() {
+        let shared = Shared::default();
+        let configured = Duration::from_secs(60);
+        assert_eq!(shared.period(configured), configured);
+
+        *shared.temporary_period.lock() =
+            Some((Duration::from_secs(5), Instant::now() + Duration::from_secs(600)));
+        assert_eq!(shared.period(configured), Duration::from_secs(5));
+
+        *shared.temporary_period.lock() = Some((Duration::from_secs(5), Instant::now()));
+        assert_eq!(shared.period(configured), configured);
+        assert!(shared.temporary_period.lock().is_none());
+    }
+
+    #[test]
+    fn temporary_period_is_clamped() {
+        let shared = Shared::default();
+        let configured = Duration::from_secs(60);
+        let now = Instant::now();
+
+        assert_eq!(shared.set_temporary_period(configured,
+                                               Duration::from_secs(600),
+                                               Duration::MAX,
+                                               now),
+                   (configured, MAX_TEMPORARY_DURATION));
+        assert_eq!(*shared.temporary_period.lock(),
+                   Some((configured, now + MAX_TEMPORARY_DURATION)));
+
+        assert_eq!(shared.set_temporary_period(configured,
+                                               Duration::from_secs(0),
+                                               Duration::from_secs(30),
+                                               now),
+                   (MIN_TEMPORARY_PERIOD, Duration::from_secs(30)));
+        assert_eq!(shared.period(configured), MIN_TEMPORARY_PERIOD);
+    }
+
+    #[test]
+    fn staging_waits_for_the_warmup() {
+        let started = Instant::now();
+        let warmup = Duration::from_secs(300);
+        assert_eq!(policy::warmup_remaining(started, warmup, started + Duration::from_secs(120)),
+                   Some(Duration::from_secs(180)));
+        assert_eq!(policy::warmup_remaining(started, warmup, started + warmup),
+                   None);
+        assert_eq!(policy::warmup_remaining(started, warmup, started + Duration::from_secs(600)),
+                   None);
+    }
+
+    #[test]
+    fn only_the_origin_and_name_of_sup_ident_are_tracked() {
+        let options = SelfUpdaterOptions::default();
+        assert_eq!(options.tracked_ident().to_string(), SUP_PKG_IDENT);
+
+        let sup_ident = "acme/hab-sup/1.0.0/20200101000000".parse().unwrap();
+        let options = SelfUpdaterOptions { sup_ident: Some(sup_ident),
+                                           ..Default::default() };
+        assert_eq!(options.tracked_ident().to_string(), "acme/hab-sup");
+    }
+
+    #[test]
+    fn recent_updates_are_bounded() {
+        let shared = Shared::default();
+        for release in 0..5 {
+            let ident = format!("core/hab-sup/1.0.{}/20200101000000", release).parse()
+                                                                              .unwrap();
+            shared.record(RecentUpdate { ident,
+                                         channel: ChannelIdent::stable(),
+                                         at: Utc::now(),
+                                         outcome: UpdateOutcome::Applied },
+                          3);
+        }
+        let recent_updates = shared.recent_updates.lock();
+        assert_eq!(recent_updates.len(), 3);
+        assert_eq!(recent_updates[0].ident.version.as_deref(), Some("1.0.2"));
+        assert_eq!(recent_updates[2].ident.version.as_deref(), Some("1.0.4"));
+    }
+
+    #[test]
+    fn a_failed_migration_leaves_everything_as_it_was() {
+        let root = TempDir::new().unwrap();
+        let backup_dir = TempDir::new().unwrap();
+        let sup_ident = PackageIdent::from_str(SUP_PKG_IDENT).unwrap();
+        let current = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        let candidate = PackageIdent::from_str("core/hab-sup/1.0.1/20200102000000").unwrap();
+        install(root.path(), &current);
+        install(root.path(), &candidate);
+        stage::stage(&candidate, root.path(), &sup_ident).unwrap();
+        let migration: Migration = Arc::new(|_, _| Err(String::from("schema is too new")));
+
+        let err = SelfUpdater::hand_over(&current,
+                                         &candidate,
+                                         &sup_ident,
+                                         Some(&migration),
+                                         Some((Duration::from_secs(600), backup_dir.path())),
+                                         &Shared::default(),
+                                         root.path()).unwrap_err();
+        assert!(matches!(err, SelfUpdateError::MigrationFailed { .. }));
+        // Nothing for the Launcher to restart into, and nothing to roll
+        // back from
+        let started = PackageInstall::load_at_least(&sup_ident, Some(root.path())).unwrap();
+        assert_eq!(started.ident(), &current);
+        assert!(RollbackWindow::load(backup_dir.path(), &sup_ident, Utc::now()).is_none());
+    }
+
+    #[test]
+    fn a_handed_over_candidate_can_be_rolled_back() {
+        let root = TempDir::new().unwrap();
+        let backup_dir = TempDir::new().unwrap();
+        let sup_ident = PackageIdent::from_str(SUP_PKG_IDENT).unwrap();
+        let current = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        let candidate = PackageIdent::from_str("core/hab-sup/1.0.1/20200102000000").unwrap();
+        install(root.path(), &current);
+        install(root.path(), &candidate);
+        stage::stage(&candidate, root.path(), &sup_ident).unwrap();
+        let migration: Migration = Arc::new(|_, _| Ok(()));
+
+        let package = SelfUpdater::hand_over(&current,
+                                             &candidate,
+                                             &sup_ident,
+                                             Some(&migration),
+                                             Some((Duration::from_secs(600), backup_dir.path())),
+                                             &Shared::default(),
+                                             root.path()).unwrap();
+        assert_eq!(package.ident(), &candidate);
+        let started = PackageInstall::load_at_least(&sup_ident, Some(root.path())).unwrap();
+        assert_eq!(started.ident(), &candidate);
+        assert!(RollbackWindow::load(backup_dir.path(), &sup_ident, Utc::now()).is_some());
+    }
+
+    /// Hand `sup_ident`'s staged 1.0.1 over from 1.0.0 under `fs_root`,
+    /// as the updater tracking it would.
+    fn hand_over_release(sup_ident: PackageIdent,
+                         fs_root: PathBuf,
+                         backup_dir: PathBuf)
+                         -> PackageInstall {
+        let current = format!("{}/1.0.0/20200101000000", sup_ident).parse()
+                                                                   .unwrap();
+        let candidate = format!("{}/1.0.1/20200102000000", sup_ident).parse()
+                                                                     .unwrap();
+        install(&fs_root, &current);
+        install(&fs_root, &candidate);
+        stage::stage(&candidate, &fs_root, &sup_ident).unwrap();
+        SelfUpdater::hand_over(&current,
+                               &candidate,
+                               &sup_ident,
+                               None,
+                               Some((Duration::from_secs(600), &backup_dir)),
+                               &Shared::default(),
+                               &fs_root).unwrap()
+    }
+
+    #[tokio::test]
+    async fn updaters_sharing_a_host_keep_their_windows_apart() {
+        let root = TempDir::new().unwrap();
+        let backup_dir = TempDir::new().unwrap();
+        let sup_idents = [PackageIdent::from_str("core/hab-sup").unwrap(),
+                          PackageIdent::from_str("acme/hab-sup").unwrap()];
+        let threads = sup_idents.iter()
+                                .map(|sup_ident| {
+                                    let sup_ident = sup_ident.clone();
+                                    let fs_root = root.path().to_path_buf();
+                                    let backup_dir = backup_dir.path().to_path_buf();
+                                    std::thread::spawn(move || {
+                                        hand_over_release(sup_ident, fs_root, backup_dir)
+                                    })
+                                })
+                                .collect::<Vec<_>>();
+        for (thread, sup_ident) in threads.into_iter().zip(&sup_idents) {
+            let package = thread.join().unwrap();
+            let started = PackageInstall::load_at_least(sup_ident, Some(root.path())).unwrap();
+            assert_eq!(started.ident(), package.ident());
+        }
+
+        // Each updater, started again after its restart, only sees its
+        // own window
+        for sup_ident in &sup_idents {
+            let current: PackageIdent = format!("{}/1.0.1/20200102000000", sup_ident).parse()
+                                                                                     .unwrap();
+            let options = SelfUpdaterOptions { sup_ident: Some(current.clone()),
+                                               backup_dir: Some(backup_dir.path().to_path_buf()),
+                                               rollback_window: Some(Duration::from_secs(600)),
+                                               check_only: true,
+                                               ..Default::default() };
+            let updater = SelfUpdater::with_options(&current,
+                                                    String::from("http://127.0.0.1:1"),
+                                                    ChannelIdent::stable(),
+                                                    Duration::from_secs(3600),
+                                                    options);
+            let window = updater.rollback_window().unwrap();
+            assert_eq!(window.previous.to_string(),
+                       format!("{}/1.0.0/20200101000000", sup_ident));
+            assert_eq!(window.updated_to, current);
+        }
+    }
+}
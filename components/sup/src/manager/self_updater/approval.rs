@@ -0,0 +1,122 @@
+//! Operator approval of self updates.
+//!
+//! With an approval file configured, an update that has been verified
+//! is held until the file names the candidate being staged, so that
+//! the Supervisor only restarts into a release an operator, or some
+//! out-of-band approval process, has signed off on. The file holds a
+//! single fully qualified package ident; a token for any other release
+//! doesn't approve this one.
+//!
+//! The file is looked at as part of the self updater's regular checks,
+//! so that a newer release or a yank is still noticed while an update
+//! waits. One that isn't approved in time is declined.
+
+use habitat_core::package::{Identifiable,
+                            PackageIdent};
+use std::{fs,
+          io,
+          path::Path,
+          time::{Duration,
+                 Instant}};
+
+/// How often to look for an approval.
+pub(super) const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Where the approval of a staged candidate stands.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum Approval {
+    Approved,
+    /// Not approved yet, for the given reason.
+    Pending(String),
+    /// Not approved within the timeout, so it never will be.
+    TimedOut,
+}
+
+/// Whether the approval file at `path` approves `candidate`, staged
+/// `since`, as of `now`. A candidate that isn't approved within
+/// `timeout` of being staged has timed out.
+pub(super) fn check(path: &Path,
+                    candidate: &PackageIdent,
+                    since: Instant,
+                    now: Instant,
+                    timeout: Duration)
+                    -> Approval {
+    match approved(path) {
+        Ok(Some(ident)) if ident == *candidate => Approval::Approved,
+        _ if now.saturating_duration_since(since) >= timeout => Approval::TimedOut,
+        Ok(Some(ident)) => {
+            Approval::Pending(format!("{} approves {} instead", path.display(), ident))
+        }
+        Ok(None) => Approval::Pending(format!("awaiting approval in {}", path.display())),
+        Err(err) => Approval::Pending(format!("unable to read approval, {}", err)),
+    }
+}
+
+/// The release the approval file at `path` approves, if it exists.
+fn approved(path: &Path) -> Result<Option<PackageIdent>, String> {
+    let token = match fs::read_to_string(path) {
+        Ok(token) => token,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("{}: {}", path.display(), err)),
+    };
+    let ident = token.trim()
+                     .parse::<PackageIdent>()
+                     .map_err(|err| format!("{}: {}", path.display(), err))?;
+    if ident.fully_qualified() {
+        Ok(Some(ident))
+    } else {
+        Err(format!("{}: {} is not a fully qualified package ident",
+                    path.display(),
+                    ident))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    fn candidate() -> PackageIdent {
+        PackageIdent::from_str("core/hab-sup/1.0.1/20200102000000").unwrap()
+    }
+
+    #[test]
+    fn only_a_fully_qualified_ident_is_an_approval() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("APPROVED");
+        assert_eq!(approved(&path), Ok(None));
+
+        fs::write(&path, "core/hab-sup/1.0.1/20200102000000\n").unwrap();
+        assert_eq!(approved(&path), Ok(Some(candidate())));
+
+        fs::write(&path, "core/hab-sup/1.0.1").unwrap();
+        assert!(approved(&path).is_err());
+        fs::write(&path, "not an ident").unwrap();
+        assert!(approved(&path).is_err());
+    }
+
+    #[test]
+    fn a_candidate_is_pending_until_approved_or_timed_out() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("APPROVED");
+        let since = Instant::now();
+        let timeout = Duration::from_secs(600);
+        let during = since + Duration::from_secs(60);
+        assert!(matches!(check(&path, &candidate(), since, during, timeout),
+                         Approval::Pending(_)));
+
+        fs::write(&path, "core/hab-sup/1.0.0/20200101000000").unwrap();
+        assert!(matches!(check(&path, &candidate(), since, during, timeout),
+                         Approval::Pending(_)));
+        assert_eq!(check(&path, &candidate(), since, since + timeout, timeout),
+                   Approval::TimedOut);
+
+        fs::write(&path, "core/hab-sup/1.0.1/20200102000000").unwrap();
+        assert_eq!(check(&path, &candidate(), since, during, timeout),
+                   Approval::Approved);
+        // An approval that arrives late still counts
+        assert_eq!(check(&path, &candidate(), since, since + timeout, timeout),
+                   Approval::Approved);
+    }
+}
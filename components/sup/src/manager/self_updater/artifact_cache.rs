@@ -0,0 +1,128 @@
+//! Downloads of the Supervisor package coordinated between the
+//! Supervisors on a host.
+//!
+//! Without coordination, every Supervisor on a host downloads the same
+//! `core/hab-sup` artifact. With a shared artifact cache configured,
+//! the self updater first resolves the newest Supervisor in the
+//! channel and takes an exclusive lock on a lock file named for it in
+//! the cache. Whichever Supervisor gets the lock first downloads the
+//! artifact; the others wait for the lock and then install from the
+//! cached copy. If the lock can't be taken, the Supervisor downloads
+//! the package on its own, as it would without a shared cache.
+
+use crate::{error::Error as SupError,
+            util};
+use fs2::FileExt;
+use habitat_api_client::TransferStats;
+use habitat_common::command::package::install::InstallSource;
+use habitat_core::{fs as hfs,
+                   package::{PackageIdent,
+                             PackageInstall},
+                   ChannelIdent};
+use habitat_http_client::ProxyAuth;
+use log::{debug,
+          warn};
+use std::{fs::{self,
+               File,
+               OpenOptions},
+          io,
+          path::Path,
+          time::{Duration,
+                 Instant}};
+use tokio::time as tokiotime;
+
+/// How long to wait for another Supervisor to finish downloading.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// How often to try the lock while waiting.
+const LOCK_POLL: Duration = Duration::from_secs(1);
+
+/// Install the newest Supervisor from `update_channel`, through
+/// `shared_cache` if one is given, downloading no faster than
+/// `bandwidth_limit` bytes per second and authenticating with any proxy
+/// using `proxy_auth`. Also returns the totals of what was downloaded.
+pub(super) async fn install(update_url: &str,
+                            install_source: &InstallSource,
+                            update_channel: &ChannelIdent,
+                            shared_cache: Option<&Path>,
+                            bandwidth_limit: Option<u64>,
+                            proxy_auth: Option<&ProxyAuth>)
+                            -> Result<(PackageInstall, TransferStats), SupError> {
+    let default_cache = hfs::cache_artifact_path(None::<String>);
+    let independently = || {
+        util::pkg::install_no_ui_with_limit(update_url,
+                                            install_source,
+                                            update_channel,
+                                            &default_cache,
+                                            bandwidth_limit,
+                                            proxy_auth)
+    };
+    let (shared_cache, ident, target) = match (shared_cache, install_source) {
+        (Some(shared_cache), InstallSource::Ident(ident, target)) => (shared_cache, ident, *target),
+        _ => return independently().await,
+    };
+    let latest = util::pkg::channel_head(update_url, ident, update_channel, proxy_auth).await?;
+    match lock(shared_cache, &latest, LOCK_TIMEOUT).await {
+        Ok(_lock) => {
+            debug!("Self updater installing {} through the shared artifact cache {}",
+                   latest,
+                   shared_cache.display());
+            let install_source = InstallSource::Ident(latest, target);
+            util::pkg::install_no_ui_with_limit(update_url,
+                                                &install_source,
+                                                update_channel,
+                                                shared_cache,
+                                                bandwidth_limit,
+                                                proxy_auth).await
+        }
+        Err(err) => {
+            warn!("Self updater unable to lock {} in the shared artifact cache {}, downloading \
+                   it independently: {}",
+                  latest,
+                  shared_cache.display(),
+                  err);
+            independently().await
+        }
+    }
+}
+
+/// Take the lock for `ident` in `cache_dir`, waiting up to `timeout`
+/// for another holder to release it. The lock is released when the
+/// returned file is dropped.
+async fn lock(cache_dir: &Path, ident: &PackageIdent, timeout: Duration) -> io::Result<File> {
+    fs::create_dir_all(cache_dir)?;
+    let path = cache_dir.join(format!("{}.lock", ident.to_string().replace('/', "-")));
+    let file = OpenOptions::new().write(true)
+                                 .create(true)
+                                 .truncate(false)
+                                 .open(&path)?;
+    let start = Instant::now();
+    loop {
+        match file.try_lock_exclusive() {
+            Ok(()) => return Ok(file),
+            Err(err) if start.elapsed() >= timeout => return Err(err),
+            Err(_) => tokiotime::sleep(LOCK_POLL).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn lock_is_exclusive_per_ident() {
+        let cache = TempDir::new().unwrap();
+        let first = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        let second = PackageIdent::from_str("core/hab-sup/1.0.1/20200102000000").unwrap();
+
+        let held = lock(cache.path(), &first, Duration::ZERO).await.unwrap();
+        assert!(lock(cache.path(), &first, Duration::ZERO).await.is_err());
+        assert!(lock(cache.path(), &second, Duration::ZERO).await.is_ok());
+
+        drop(held);
+        assert!(lock(cache.path(), &first, Duration::ZERO).await.is_ok());
+    }
+}
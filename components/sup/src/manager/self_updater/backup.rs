@@ -0,0 +1,135 @@
+//! Snapshots of the running Supervisor package, taken before an update
+//! is staged so that it can be restored if the new Supervisor
+//! misbehaves.
+//!
+//! A snapshot is a copy of the package's install directory. It is
+//! assembled next to its final location and renamed into place, so a
+//! snapshot that exists is always complete.
+
+use habitat_core::package::{PackageIdent,
+                            PackageInstall};
+use std::{fs,
+          io,
+          path::{Path,
+                 PathBuf}};
+
+/// Copy the install of `current` into `backup_dir`, returning the path
+/// of the snapshot. If a snapshot of `current` already exists it is
+/// reused rather than copied again.
+pub(super) fn snapshot(current: &PackageIdent,
+                       fs_root_path: &Path,
+                       backup_dir: &Path)
+                       -> Result<PathBuf, String> {
+    let name = current.to_string().replace('/', "-");
+    let dest = backup_dir.join(&name);
+    if dest.is_dir() {
+        return Ok(dest);
+    }
+    let install = match PackageInstall::load(current, Some(fs_root_path)) {
+        Ok(install) => install,
+        Err(e) => return Err(format!("unable to load the running Supervisor: {}", e)),
+    };
+    let src = install.installed_path();
+    let partial = backup_dir.join(format!(".{}.partial", name));
+    if let Err(e) = copy_into_place(src, &partial, &dest) {
+        let _ = fs::remove_dir_all(&partial);
+        return Err(format!("unable to copy {} to {}: {}",
+                           src.display(),
+                           dest.display(),
+                           e));
+    }
+    Ok(dest)
+}
+
+/// Copy the snapshot at `snapshot` back to `install_path`, where the
+/// package it was taken of is installed.
+pub(super) fn restore(snapshot: &Path, install_path: &Path) -> Result<(), String> {
+    let name = install_path.file_name()
+                           .map(|name| name.to_string_lossy().into_owned())
+                           .unwrap_or_default();
+    let partial = install_path.with_file_name(format!(".{}.partial", name));
+    copy_into_place(snapshot, &partial, install_path).map_err(|e| {
+                                                         let _ = fs::remove_dir_all(&partial);
+                                                         format!("unable to copy {} to {}: {}",
+                                                                 snapshot.display(),
+                                                                 install_path.display(),
+                                                                 e)
+                                                     })
+}
+
+/// Copy `src` to `partial`, discarding any leftovers of an earlier
+/// attempt, then rename it to `dest`.
+fn copy_into_place(src: &Path, partial: &Path, dest: &Path) -> io::Result<()> {
+    if partial.exists() {
+        fs::remove_dir_all(partial)?;
+    }
+    if let Some(parent) = partial.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    copy_dir(src, partial)?;
+    fs::rename(partial, dest)
+}
+
+fn copy_dir(src: &Path, dest: &Path) -> io::Result<()> {
+    fs::create_dir(dest)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = dest.join(entry.file_name());
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(src: &Path, dest: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(src)?, dest)
+}
+
+#[cfg(windows)]
+fn copy_symlink(src: &Path, dest: &Path) -> io::Result<()> { fs::copy(src, dest).map(|_| ()) }
+
+#[cfg(test)]
+mod tests {
+    use super::{super::test_helpers::install,
+                *};
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    #[test]
+    fn snapshot_copies_the_current_install() {
+        let root = TempDir::new().unwrap();
+        let backups = TempDir::new().unwrap();
+        let ident = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        let installed = install(root.path(), &ident);
+
+        let dest = snapshot(&ident, root.path(), backups.path()).unwrap();
+        assert_eq!(dest,
+                   backups.path().join("core-hab-sup-1.0.0-20200101000000"));
+        let binary = Path::new("bin").join("hab-sup");
+        assert_eq!(fs::read_to_string(dest.join(&binary)).unwrap(),
+                   fs::read_to_string(installed.installed_path().join(&binary)).unwrap());
+        assert!(!backups.path()
+                        .join(".core-hab-sup-1.0.0-20200101000000.partial")
+                        .exists());
+    }
+
+    #[test]
+    fn snapshot_reuses_an_existing_backup() {
+        let root = TempDir::new().unwrap();
+        let backups = TempDir::new().unwrap();
+        let ident = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        install(root.path(), &ident);
+
+        let dest = snapshot(&ident, root.path(), backups.path()).unwrap();
+        fs::write(dest.join("marker"), "").unwrap();
+        assert_eq!(snapshot(&ident, root.path(), backups.path()).unwrap(), dest);
+        assert!(dest.join("marker").exists());
+    }
+}
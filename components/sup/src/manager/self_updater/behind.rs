@@ -0,0 +1,139 @@
+//! Staying a fixed number of releases behind the channel.
+//!
+//! Some fleets would rather not run a release until a few newer ones
+//! have been published after it, trusting that anything badly wrong
+//! with it will have been found by then. Such an updater targets the
+//! Nth-newest release in the channel instead of the newest, and stays
+//! put while the channel has too few releases to have one. When yanked
+//! releases are skipped, they aren't counted either.
+
+use crate::{error::Error as SupError,
+            util};
+use habitat_core::{package::PackageIdent,
+                   ChannelIdent};
+use habitat_http_client::ProxyAuth;
+use log::debug;
+use std::future::Future;
+
+/// The release `behind` releases behind the newest of `tracked` in
+/// `channel`, if the channel has that many. With `skip_yanked`, only
+/// releases that haven't been yanked are counted, and the depot is
+/// asked about each release, newest first, until the target is found.
+pub(super) async fn target(update_url: &str,
+                           tracked: &PackageIdent,
+                           channel: &ChannelIdent,
+                           behind: usize,
+                           skip_yanked: bool,
+                           proxy_auth: Option<&ProxyAuth>)
+                           -> Result<Option<PackageIdent>, SupError> {
+    let releases =
+        newest_first(util::pkg::channel_releases(update_url, tracked, channel, proxy_auth).await?);
+    if skip_yanked {
+        nth_unyanked(releases, behind, |release| {
+            async move { util::pkg::release_status(update_url, &release, channel, proxy_auth).await }
+        }).await
+    } else {
+        Ok(releases.into_iter().nth(behind))
+    }
+}
+
+/// The fully qualified `releases`, each once, newest first.
+fn newest_first(mut releases: Vec<PackageIdent>) -> Vec<PackageIdent> {
+    releases.retain(PackageIdent::fully_qualified);
+    releases.sort_by(|a, b| b.cmp(a));
+    releases.dedup();
+    releases
+}
+
+/// The `n`th-newest of `releases`, counting the newest as 0, that
+/// `status` doesn't report as yanked. `releases` are newest first, and
+/// `status` is only asked about as many as it takes.
+async fn nth_unyanked<F, S>(releases: Vec<PackageIdent>,
+                            n: usize,
+                            mut status: F)
+                            -> Result<Option<PackageIdent>, SupError>
+    where F: FnMut(PackageIdent) -> S,
+          S: Future<Output = Result<(PackageIdent, bool), SupError>>
+{
+    let mut unyanked = 0;
+    for release in releases {
+        let (release, yanked) = status(release).await?;
+        if yanked {
+            debug!("Self updater not counting {}, which was yanked from the channel",
+                   release);
+        } else if unyanked == n {
+            return Ok(Some(release));
+        } else {
+            unyanked += 1;
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn ident(ident: &str) -> PackageIdent { PackageIdent::from_str(ident).unwrap() }
+
+    fn nth(releases: Vec<PackageIdent>, n: usize) -> Option<PackageIdent> {
+        newest_first(releases).into_iter().nth(n)
+    }
+
+    fn releases(count: usize) -> Vec<PackageIdent> {
+        (0..count).map(|release| {
+                      ident(&format!("core/hab-sup/1.0.{}/2020010100000{}", release, release))
+                  })
+                  .collect()
+    }
+
+    #[test]
+    fn targets_the_nth_newest_release() {
+        assert_eq!(nth(releases(5), 0),
+                   Some(ident("core/hab-sup/1.0.4/20200101000004")));
+        assert_eq!(nth(releases(5), 2),
+                   Some(ident("core/hab-sup/1.0.2/20200101000002")));
+        assert_eq!(nth(releases(5), 4),
+                   Some(ident("core/hab-sup/1.0.0/20200101000000")));
+    }
+
+    #[test]
+    fn too_few_releases_have_no_target() {
+        assert_eq!(nth(releases(0), 0), None);
+        assert_eq!(nth(releases(2), 2), None);
+        assert_eq!(nth(releases(3), 3), None);
+        assert_eq!(nth(releases(3), 2),
+                   Some(ident("core/hab-sup/1.0.0/20200101000000")));
+    }
+
+    #[test]
+    fn releases_are_counted_once_whatever_order_they_are_listed_in() {
+        let mut listed = releases(4);
+        listed.reverse();
+        listed.push(ident("core/hab-sup/1.0.3/20200101000003"));
+        listed.push(ident("core/hab-sup"));
+        assert_eq!(nth(listed, 1),
+                   Some(ident("core/hab-sup/1.0.2/20200101000002")));
+    }
+
+    #[tokio::test]
+    async fn yanked_releases_are_not_counted() {
+        let yanked = [ident("core/hab-sup/1.0.4/20200101000004"),
+                      ident("core/hab-sup/1.0.2/20200101000002")];
+        let asked = std::cell::Cell::new(0);
+        let status = |release: PackageIdent| {
+            asked.set(asked.get() + 1);
+            let is_yanked = yanked.contains(&release);
+            async move { Ok((release, is_yanked)) }
+        };
+
+        let target = nth_unyanked(newest_first(releases(5)), 1, status).await;
+        assert_eq!(target.unwrap(),
+                   Some(ident("core/hab-sup/1.0.1/20200101000001")));
+        // Nothing older than the target was asked about
+        assert_eq!(asked.get(), 4);
+        let target = nth_unyanked(newest_first(releases(5)), 3, status).await;
+        assert_eq!(target.unwrap(), None);
+    }
+}
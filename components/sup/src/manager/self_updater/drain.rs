@@ -0,0 +1,104 @@
+//! Holding restarts while connections drain.
+//!
+//! A service shutting down gracefully may spend a while letting its
+//! in-flight requests finish. Restarting the Supervisor into an update
+//! in the middle of that would cut them off, so services register each
+//! drain with the `Drains` the self updater was given, and an update
+//! that is ready to be handed over waits until none are in progress.
+
+use log::{debug,
+          info};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// The connection drains in progress. Clones share the same drains.
+#[derive(Clone, Debug)]
+pub struct Drains(Arc<watch::Sender<usize>>);
+
+impl Default for Drains {
+    fn default() -> Self { Drains(Arc::new(watch::channel(0).0)) }
+}
+
+impl Drains {
+    /// Note that `name` has started draining its connections. The drain
+    /// is in progress until the returned handle is completed or
+    /// dropped.
+    pub fn register_drain(&self, name: &str) -> Drain {
+        self.0.send_modify(|active| *active += 1);
+        debug!("Connection drain registered for {}", name);
+        Drain { drains: self.clone(),
+                name:   name.to_string(), }
+    }
+
+    /// How many drains are in progress.
+    pub fn active(&self) -> usize { *self.0.borrow() }
+
+    /// Wait until no drain is in progress.
+    pub(super) async fn wait(&self) {
+        let mut active = self.0.subscribe();
+        let count = *active.borrow();
+        if count > 0 {
+            info!("Self updater holding update until {} connection drain(s) complete",
+                  count);
+            // The sender is held by `self`, so it can't be dropped
+            active.wait_for(|active| *active == 0)
+                  .await
+                  .expect("connection drain sender dropped");
+        }
+    }
+}
+
+/// A connection drain in progress, registered with
+/// `Drains::register_drain`.
+#[derive(Debug)]
+pub struct Drain {
+    drains: Drains,
+    name:   String,
+}
+
+impl Drain {
+    /// Note that the drain has finished.
+    pub fn complete_drain(self) {}
+}
+
+impl Drop for Drain {
+    fn drop(&mut self) {
+        self.drains.0.send_modify(|active| *active -= 1);
+        debug!("Connection drain completed for {}", self.name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time as tokiotime;
+
+    #[tokio::test]
+    async fn waits_until_every_drain_completes() {
+        let drains = Drains::default();
+        let first = drains.register_drain("redis.default");
+        let second = drains.register_drain("nginx.default");
+        assert_eq!(drains.active(), 2);
+
+        let waiting = tokio::spawn({
+            let drains = drains.clone();
+            async move { drains.wait().await }
+        });
+        first.complete_drain();
+        tokiotime::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+
+        drop(second);
+        tokiotime::timeout(Duration::from_secs(5), waiting).await
+                                                           .expect("still waiting for drains")
+                                                           .unwrap();
+        assert_eq!(drains.active(), 0);
+    }
+
+    #[tokio::test]
+    async fn nothing_is_held_without_drains() {
+        tokiotime::timeout(Duration::from_secs(5), Drains::default().wait()).await
+                                                                            .unwrap();
+    }
+}
@@ -0,0 +1,77 @@
+//! Honoring a channel's publish embargo.
+//!
+//! Some channels embargo a newly published package until a given time,
+//! before which it shouldn't be consumed. The depot reports this in the
+//! package's metadata; a candidate still under embargo is declined and
+//! looked at again once the embargo lifts. Depots that don't embargo
+//! packages simply don't report one, and nothing is held back.
+
+use crate::util;
+use chrono::{DateTime,
+             Utc};
+use habitat_core::{package::PackageIdent,
+                   ChannelIdent};
+use habitat_http_client::ProxyAuth;
+use log::warn;
+use std::time::Duration;
+
+/// How long `candidate` remains under embargo in `channel` as of `now`,
+/// if it is embargoed at all.
+pub(super) async fn remaining(update_url: &str,
+                              candidate: &PackageIdent,
+                              channel: &ChannelIdent,
+                              proxy_auth: Option<&ProxyAuth>,
+                              now: DateTime<Utc>)
+                              -> Option<Duration> {
+    match util::pkg::embargo(update_url, candidate, channel, proxy_auth).await {
+        Ok(embargo) => remaining_at(candidate, embargo.as_deref(), now),
+        Err(err) => {
+            warn!("Self updater unable to check the embargo on {}, {}",
+                  candidate, err);
+            None
+        }
+    }
+}
+
+/// How long an embargo the depot reported as `until` has left to run at
+/// `now`. An embargo that can't be understood is ignored, as if the
+/// depot hadn't reported one.
+fn remaining_at(candidate: &PackageIdent,
+                until: Option<&str>,
+                now: DateTime<Utc>)
+                -> Option<Duration> {
+    let until = match DateTime::parse_from_rfc3339(until?) {
+        Ok(until) => until.with_timezone(&Utc),
+        Err(err) => {
+            warn!("Self updater ignoring the unreadable embargo on {}, {}",
+                  candidate, err);
+            return None;
+        }
+    };
+    (until - now).to_std()
+                 .ok()
+                 .filter(|remaining| !remaining.is_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn only_an_embargo_still_in_force_holds_a_candidate() {
+        let candidate = PackageIdent::from_str("core/hab-sup/1.0.1/20200102000000").unwrap();
+        let now = DateTime::parse_from_rfc3339("2020-01-02T12:00:00Z").unwrap()
+                                                                      .with_timezone(&Utc);
+        assert_eq!(remaining_at(&candidate, None, now), None);
+        assert_eq!(remaining_at(&candidate, Some("2020-01-02T13:00:00Z"), now),
+                   Some(Duration::from_secs(3600)));
+        assert_eq!(remaining_at(&candidate, Some("2020-01-02T14:00:00+01:00"), now),
+                   Some(Duration::from_secs(3600)));
+        assert_eq!(remaining_at(&candidate, Some("2020-01-02T12:00:00Z"), now),
+                   None);
+        assert_eq!(remaining_at(&candidate, Some("2020-01-01T00:00:00Z"), now),
+                   None);
+        assert_eq!(remaining_at(&candidate, Some("next tuesday"), now), None);
+    }
+}
@@ -0,0 +1,122 @@
+//! A trial run of a candidate Supervisor binary.
+//!
+//! An install can pass every structural check and still fail to
+//! start, for instance because a shared library it links against is
+//! missing or has an incompatible ABI. The fork test runs the
+//! candidate's binary with an argument that makes it exit straight
+//! away (`--version` by default) and declines the update unless it
+//! exits successfully within the timeout.
+
+use super::install_check;
+use habitat_core::package::PackageInstall;
+use std::{path::Path,
+          process::Stdio,
+          time::Duration};
+use tokio::{process::Command,
+            time as tokiotime};
+
+/// How to run a candidate Supervisor before staging it.
+#[derive(Clone, Debug)]
+pub struct ForkTest {
+    /// The arguments to run the candidate's binary with. The binary
+    /// must exit successfully for the candidate to be staged.
+    pub args:    Vec<String>,
+    /// How long the binary may run before the test fails.
+    pub timeout: Duration,
+}
+
+impl Default for ForkTest {
+    fn default() -> Self {
+        ForkTest { args:    vec!["--version".to_string()],
+                   timeout: Duration::from_secs(10), }
+    }
+}
+
+impl ForkTest {
+    /// Run the Supervisor binary in `candidate`, returning a
+    /// description of how it failed if it did.
+    pub(super) async fn run(&self,
+                            candidate: &PackageInstall,
+                            fs_root_path: &Path)
+                            -> Result<(), String> {
+        let binary = install_check::sup_binary(candidate, fs_root_path)?;
+        let child =
+            Command::new(&binary).args(&self.args)
+                                 .stdin(Stdio::null())
+                                 .stdout(Stdio::null())
+                                 .stderr(Stdio::piped())
+                                 .kill_on_drop(true)
+                                 .spawn()
+                                 .map_err(|e| {
+                                     format!("unable to run {}: {}", binary.display(), e)
+                                 })?;
+        let output = match tokiotime::timeout(self.timeout, child.wait_with_output()).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => return Err(format!("unable to wait for {}: {}", binary.display(), e)),
+            Err(_) => {
+                return Err(format!("{} {} did not exit within {}s",
+                                   binary.display(),
+                                   self.args.join(" "),
+                                   self.timeout.as_secs()))
+            }
+        };
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(format!("{} {} exited with {}: {}",
+                        binary.display(),
+                        self.args.join(" "),
+                        output.status,
+                        stderr.lines().next().unwrap_or_default()))
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{super::test_helpers::install_with,
+                *};
+    use habitat_core::package::{metadata::MetaFile,
+                                PackageIdent};
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    /// A candidate whose Supervisor binary runs `script`.
+    fn candidate(fs_root: &Path, script: &str) -> PackageInstall {
+        let ident = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        install_with(fs_root,
+                     &ident,
+                     &[MetaFile::Ident, MetaFile::Path],
+                     Some(script))
+    }
+
+    #[tokio::test]
+    async fn binary_that_exits_successfully_passes() {
+        let root = TempDir::new().unwrap();
+        let candidate = candidate(root.path(), "exit 0");
+        assert!(ForkTest::default().run(&candidate, root.path())
+                                   .await
+                                   .is_ok());
+    }
+
+    #[tokio::test]
+    async fn binary_that_fails_is_reported() {
+        let root = TempDir::new().unwrap();
+        let candidate = candidate(root.path(), "echo 'libfoo.so: not found' >&2; exit 127");
+        let err = ForkTest::default().run(&candidate, root.path())
+                                     .await
+                                     .unwrap_err();
+        assert!(err.contains("libfoo.so: not found"));
+    }
+
+    #[tokio::test]
+    async fn binary_that_hangs_times_out() {
+        let root = TempDir::new().unwrap();
+        let candidate = candidate(root.path(), "sleep 10");
+        let fork_test = ForkTest { timeout: Duration::from_millis(100),
+                                   ..Default::default() };
+        let err = fork_test.run(&candidate, root.path()).await.unwrap_err();
+        assert!(err.contains("did not exit"));
+    }
+}
@@ -0,0 +1,308 @@
+//! Host compatibility checks that a candidate Supervisor package must
+//! pass before the self updater will install it.
+//!
+//! A newer `core/hab-sup` can require a package target or runtime
+//! dependencies that this host cannot provide. Rather than downloading
+//! and installing a Supervisor that will fail to start, the self
+//! updater runs each check against the candidate as the channel
+//! describes it, and declines the update if any of them fail.
+//!
+//! The checks returned by `default_checks` are always run;
+//! environments can layer their own rules on top of them, such as
+//! `CoreDepsPresent`, or by implementing `HostCompatibilityCheck`.
+
+use habitat_core::package::{metadata::{self,
+                                       MetaFile},
+                            PackageIdent,
+                            PackageInstall,
+                            PackageTarget};
+use std::{path::PathBuf,
+          process::Command,
+          str::FromStr,
+          sync::Arc};
+
+/// What a candidate Supervisor package needs from the host. For a
+/// candidate in the channel this comes from the depot's metadata, so
+/// that it can be checked before anything is installed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub ident:  PackageIdent,
+    pub target: PackageTarget,
+    /// Its direct runtime dependencies.
+    pub deps:   Vec<PackageIdent>,
+    /// Its transitive runtime dependencies.
+    pub tdeps:  Vec<PackageIdent>,
+}
+
+impl Candidate {
+    /// The candidate the depot described as `package`, when asked for
+    /// the release for `requested`. The target is the one the depot
+    /// reports the package was built for, or `requested` from depots
+    /// that don't report it.
+    pub(super) fn from_metadata(package: habitat_api_client::Package,
+                                requested: PackageTarget)
+                                -> Self {
+        Candidate { ident:  package.ident,
+                    target: package.target.unwrap_or(requested),
+                    deps:   package.deps,
+                    tdeps:  package.tdeps, }
+    }
+
+    /// The candidate `install` already is.
+    pub(super) fn from_install(install: &PackageInstall) -> Result<Self, String> {
+        let target = metadata::read_metafile(install.installed_path(), MetaFile::Target)
+            .map_err(|e| format!("unable to read package target: {}", e))
+            .and_then(|body| PackageTarget::from_str(body.trim()).map_err(|e| e.to_string()))?;
+        let read = |e: habitat_core::Error| format!("unable to read dependencies: {}", e);
+        Ok(Candidate { ident: install.ident().clone(),
+                       target,
+                       deps: install.deps().map_err(read)?,
+                       tdeps: install.tdeps().map_err(read)? })
+    }
+}
+
+/// A rule that decides whether this host is able to run a candidate
+/// Supervisor package.
+pub trait HostCompatibilityCheck: Send + Sync {
+    /// A short name for the check, used when reporting a decline.
+    fn name(&self) -> &str;
+
+    /// Return `Ok(())` if the host can run `candidate`, or an error
+    /// describing why it cannot.
+    fn check(&self, candidate: &Candidate) -> Result<(), String>;
+}
+
+/// The checks that are run against every candidate, regardless of
+/// any additional checks that have been configured.
+pub fn default_checks() -> Vec<Arc<dyn HostCompatibilityCheck>> {
+    vec![Arc::new(TargetMatches), Arc::new(GlibcCompatible::detect())]
+}
+
+/// Ensures the candidate was built for the package target of this
+/// host.
+pub struct TargetMatches;
+
+impl HostCompatibilityCheck for TargetMatches {
+    fn name(&self) -> &str { "target" }
+
+    fn check(&self, candidate: &Candidate) -> Result<(), String> {
+        let active = PackageTarget::active_target();
+        if candidate.target == active {
+            Ok(())
+        } else {
+            Err(format!("package target {} does not match host target {}",
+                        candidate.target, active))
+        }
+    }
+}
+
+/// Ensures the host's glibc is at least as new as the `core/glibc` the
+/// candidate depends on. Hosts without glibc, and candidates that don't
+/// depend on it, pass.
+pub struct GlibcCompatible {
+    host_version: Option<String>,
+}
+
+impl GlibcCompatible {
+    /// Compare candidates against the glibc this host provides.
+    pub fn detect() -> Self { GlibcCompatible { host_version: host_glibc_version(), } }
+
+    /// Compare candidates against the given glibc version, as for a
+    /// host whose glibc can't be detected.
+    pub fn with_host_version(version: impl Into<String>) -> Self {
+        GlibcCompatible { host_version: Some(version.into()), }
+    }
+}
+
+impl HostCompatibilityCheck for GlibcCompatible {
+    fn name(&self) -> &str { "glibc" }
+
+    fn check(&self, candidate: &Candidate) -> Result<(), String> {
+        let host_version = match &self.host_version {
+            Some(host_version) => host_version,
+            None => return Ok(()),
+        };
+        let required = candidate.tdeps
+                                .iter()
+                                .find(|dep| dep.origin == "core" && dep.name == "glibc")
+                                .and_then(|glibc| glibc.version.as_deref());
+        match required {
+            Some(required) if version_parts(required) > version_parts(host_version) => {
+                Err(format!("requires glibc {}, but the host has {}",
+                            required, host_version))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Ensures every `core` package the candidate depends on at runtime is
+/// already installed on the host, for hosts that can't fetch them when
+/// the candidate is installed, such as air-gapped hosts provisioned out
+/// of band. This isn't among `default_checks`, since installing
+/// normally fetches whatever is missing.
+pub struct CoreDepsPresent {
+    fs_root: PathBuf,
+}
+
+impl CoreDepsPresent {
+    /// Look for the dependencies installed under `fs_root`.
+    pub fn under(fs_root: impl Into<PathBuf>) -> Self {
+        CoreDepsPresent { fs_root: fs_root.into(), }
+    }
+}
+
+impl HostCompatibilityCheck for CoreDepsPresent {
+    fn name(&self) -> &str { "core_deps" }
+
+    fn check(&self, candidate: &Candidate) -> Result<(), String> {
+        let missing =
+            candidate.tdeps
+                     .iter()
+                     .filter(|dep| dep.origin == "core")
+                     .filter(|dep| PackageInstall::load(dep, Some(&self.fs_root)).is_err())
+                     .map(ToString::to_string)
+                     .collect::<Vec<_>>();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("requires {}, not installed on the host", missing.join(", ")))
+        }
+    }
+}
+
+/// The version of the host's glibc, as reported by its `getconf`. The
+/// Supervisor's own process may be running against a different glibc.
+fn host_glibc_version() -> Option<String> {
+    let output = Command::new("getconf").arg("GNU_LIBC_VERSION")
+                                        .output()
+                                        .ok()
+                                        .filter(|output| output.status.success())?;
+    // For example "glibc 2.27"
+    String::from_utf8_lossy(&output.stdout).split_whitespace()
+                                           .nth(1)
+                                           .map(String::from)
+}
+
+/// The numeric parts of a dotted version, for comparing versions like
+/// `2.9` and `2.27` numerically.
+fn version_parts(version: &str) -> Vec<u64> {
+    version.split('.')
+           .map_while(|part| part.parse().ok())
+           .collect()
+}
+
+/// Run every check against `candidate`, returning the name of the
+/// first failing check and its reason.
+pub(super) fn check_host(candidate: &Candidate,
+                         checks: &[Arc<dyn HostCompatibilityCheck>])
+                         -> Result<(), (String, String)> {
+    for check in checks {
+        check.check(candidate)
+             .map_err(|reason| (check.name().to_string(), reason))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::test_helpers,
+                *};
+    use habitat_api_client::Package;
+    use tempfile::TempDir;
+
+    fn candidate(target: PackageTarget, tdeps: &[&str]) -> Candidate {
+        let tdeps = tdeps.iter()
+                         .map(|dep| PackageIdent::from_str(dep).unwrap())
+                         .collect::<Vec<_>>();
+        Candidate { ident: PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap(),
+                    target,
+                    deps: tdeps.clone(),
+                    tdeps }
+    }
+
+    #[test]
+    fn target_matches_accepts_active_target() {
+        let candidate = candidate(PackageTarget::active_target(), &[]);
+        assert!(TargetMatches.check(&candidate).is_ok());
+    }
+
+    #[test]
+    fn target_matches_rejects_foreign_target() {
+        let foreign = PackageTarget::targets().find(|t| **t != PackageTarget::active_target())
+                                              .unwrap();
+        let candidate = candidate(*foreign, &[]);
+        assert!(TargetMatches.check(&candidate).is_err());
+    }
+
+    #[test]
+    fn glibc_compatible_compares_versions_numerically() {
+        let candidate = candidate(PackageTarget::active_target(),
+                                  &["core/glibc/2.27/20200101000000",
+                                    "core/openssl/1.1.1/20200101000000"]);
+        assert!(GlibcCompatible::with_host_version("2.27").check(&candidate)
+                                                          .is_ok());
+        assert!(GlibcCompatible::with_host_version("2.35").check(&candidate)
+                                                          .is_ok());
+        let err = GlibcCompatible::with_host_version("2.9").check(&candidate)
+                                                           .unwrap_err();
+        assert!(err.contains("2.27"));
+    }
+
+    #[test]
+    fn glibc_compatible_passes_without_glibc() {
+        let candidate = candidate(PackageTarget::active_target(),
+                                  &["core/openssl/1.1.1/20200101000000"]);
+        assert!(GlibcCompatible::with_host_version("2.9").check(&candidate)
+                                                         .is_ok());
+        let glibc = candidate(PackageTarget::active_target(),
+                              &["core/glibc/2.27/20200101000000"]);
+        assert!(GlibcCompatible { host_version: None }.check(&glibc).is_ok());
+    }
+
+    #[test]
+    fn a_candidate_takes_the_target_the_depot_reports() {
+        let foreign = *PackageTarget::targets().find(|t| **t != PackageTarget::active_target())
+                                               .unwrap();
+        let package = |target| {
+            Package { ident: PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap(),
+                      checksum: String::new(),
+                      manifest: String::new(),
+                      deps: Vec::new(),
+                      tdeps: Vec::new(),
+                      exposes: Vec::new(),
+                      config: String::new(),
+                      embargo_until: None,
+                      yanked: false,
+                      target }
+        };
+
+        let reported =
+            Candidate::from_metadata(package(Some(foreign)), PackageTarget::active_target());
+        assert_eq!(reported.target, foreign);
+        assert!(TargetMatches.check(&reported).is_err());
+
+        let unreported = Candidate::from_metadata(package(None), PackageTarget::active_target());
+        assert_eq!(unreported.target, PackageTarget::active_target());
+    }
+
+    #[test]
+    fn core_deps_present_requires_each_core_dep_installed() {
+        let fs_root = TempDir::new().unwrap();
+        let glibc = PackageIdent::from_str("core/glibc/2.27/20200101000000").unwrap();
+        test_helpers::install(fs_root.path(), &glibc);
+        let check = CoreDepsPresent::under(fs_root.path());
+
+        let candidate = candidate(PackageTarget::active_target(),
+                                  &["core/glibc/2.27/20200101000000",
+                                    "acme/tools/1.0.0/20200101000000"]);
+        assert!(check.check(&candidate).is_ok());
+
+        let candidate = candidate(PackageTarget::active_target(),
+                                  &["core/glibc/2.27/20200101000000",
+                                    "core/openssl/1.1.1/20200101000000"]);
+        let err = check.check(&candidate).unwrap_err();
+        assert!(err.contains("core/openssl/1.1.1/20200101000000"), "{}", err);
+        assert!(!err.contains("glibc"), "{}", err);
+    }
+}
@@ -0,0 +1,200 @@
+//! Structural checks that an installed Supervisor package is complete
+//! enough to be started.
+//!
+//! Signatures and checksums are verified when an artifact is
+//! installed, but the resulting install can still be incomplete on
+//! disk (e.g. an interrupted extraction, or a dependency that failed
+//! to install). Restarting into such an install would fail, so the self
+//! updater declines to stage it and tries again on its next check.
+//!
+//! The artifact an install came from can be verified again too, for a
+//! Supervisor that has been running long since it was installed.
+
+use habitat_core::{crypto::{artifact,
+                            keys::KeyCache},
+                   fs,
+                   package::{metadata::MetaFile,
+                             PackageIdent,
+                             PackageInstall,
+                             PackageTarget}};
+use std::path::{Path,
+                PathBuf};
+
+/// The binary the Launcher runs out of the Supervisor package.
+pub(super) const SUP_CMD: &str = "hab-sup";
+
+/// Metadata files that every Supervisor install must have.
+const REQUIRED_METAFILES: &[MetaFile] = &[MetaFile::Ident, MetaFile::Target];
+
+/// Ensure `install` has its required metadata, its transitive
+/// dependencies and a runnable Supervisor binary, returning a
+/// description of what is missing if not.
+pub(super) fn check_installable(install: &PackageInstall,
+                                fs_root_path: &Path)
+                                -> Result<(), String> {
+    for metafile in REQUIRED_METAFILES {
+        if !install.installed_path()
+                   .join(metafile.to_string())
+                   .is_file()
+        {
+            return Err(format!("missing {} metadata file", metafile));
+        }
+    }
+    let tdeps = install.tdeps()
+                       .map_err(|e| format!("unable to read dependencies: {}", e))?;
+    let missing = tdeps.iter()
+                       .filter(|dep| PackageInstall::load(dep, Some(fs_root_path)).is_err())
+                       .map(ToString::to_string)
+                       .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(format!("missing dependencies: {}", missing.join(", ")));
+    }
+    let binary = sup_binary(install, fs_root_path)?;
+    if is_executable(&binary) {
+        Ok(())
+    } else {
+        Err(format!("{} is not executable", binary.display()))
+    }
+}
+
+/// Verify the artifact `ident` was installed from, the first found in
+/// `caches`, against its origin's public signing key in `key_cache`.
+/// This checks the artifact's checksum and the signature over it.
+pub(super) fn verify_artifact(ident: &PackageIdent,
+                              caches: &[PathBuf],
+                              key_cache: &KeyCache)
+                              -> Result<(), String> {
+    let name = ident.archive_name_with_target(PackageTarget::active_target())
+                    .map_err(|e| e.to_string())?;
+    let artifact = caches.iter()
+                         .map(|cache| cache.join(&name))
+                         .find(|artifact| artifact.is_file())
+                         .ok_or_else(|| format!("no cached {} to verify", name))?;
+    let verified = artifact::verify(&artifact, key_cache);
+    let (signer, _) =
+        verified.map_err(|e| format!("{} failed verification: {}", artifact.display(), e))?;
+    if *signer.name() != ident.origin {
+        return Err(format!("{} is signed by {}, not by origin {}",
+                           artifact.display(),
+                           signer,
+                           ident.origin));
+    }
+    Ok(())
+}
+
+/// The path of the Supervisor binary in `install`, under
+/// `fs_root_path`.
+pub(super) fn sup_binary(install: &PackageInstall, fs_root_path: &Path) -> Result<PathBuf, String> {
+    let binary = match fs::find_command_in_pkg(SUP_CMD, install, fs_root_path) {
+        Ok(Some(binary)) => binary,
+        Ok(None) => return Err(format!("no {} binary found", SUP_CMD)),
+        Err(e) => return Err(format!("unable to search for {} binary: {}", SUP_CMD, e)),
+    };
+    Ok(fs_root_path.join(binary.strip_prefix("/").unwrap_or(&binary)))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+                           .unwrap_or(false)
+}
+
+#[cfg(windows)]
+fn is_executable(path: &Path) -> bool { path.is_file() }
+
+#[cfg(test)]
+mod tests {
+    use super::{super::test_helpers::{install,
+                                      install_with},
+                *};
+    use habitat_core::{crypto::keys::generate_signing_key_pair,
+                       origin::Origin};
+    use std::{fs::{create_dir_all,
+                   write},
+              str::FromStr};
+    use tempfile::TempDir;
+
+    fn ident() -> PackageIdent {
+        PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap()
+    }
+
+    #[test]
+    fn complete_install_is_installable() {
+        let root = TempDir::new().unwrap();
+        let install = install(root.path(), &ident());
+        assert!(check_installable(&install, root.path()).is_ok());
+    }
+
+    #[test]
+    fn install_missing_metadata_is_not_installable() {
+        let root = TempDir::new().unwrap();
+        let install = install_with(root.path(),
+                                   &ident(),
+                                   &[MetaFile::Ident, MetaFile::Path],
+                                   Some("exit 0"));
+        let err = check_installable(&install, root.path()).unwrap_err();
+        assert!(err.contains("TARGET"));
+    }
+
+    #[test]
+    fn install_missing_binary_is_not_installable() {
+        let root = TempDir::new().unwrap();
+        let install = install_with(root.path(),
+                                   &ident(),
+                                   &[MetaFile::Ident, MetaFile::Target, MetaFile::Path],
+                                   None);
+        assert!(check_installable(&install, root.path()).is_err());
+    }
+
+    #[test]
+    fn install_missing_dependencies_is_not_installable() {
+        let root = TempDir::new().unwrap();
+        let install = install(root.path(), &ident());
+        let present = PackageIdent::from_str("core/present/1.0.0/20200101000000").unwrap();
+        install_with(root.path(), &present, &[MetaFile::Ident], None);
+        write(install.installed_path().join(MetaFile::TDeps.to_string()),
+              "core/present/1.0.0/20200101000000\ncore/absent/1.0.0/20200101000000").unwrap();
+
+        let err = check_installable(&install, root.path()).unwrap_err();
+        assert!(err.contains("core/absent/1.0.0/20200101000000"));
+        assert!(!err.contains("core/present"));
+    }
+
+    #[test]
+    fn only_an_artifact_signed_by_its_origin_verifies() {
+        let root = TempDir::new().unwrap();
+        let cache = root.path().join("artifacts");
+        let key_cache = KeyCache::new(root.path().join("keys"));
+        key_cache.setup().unwrap();
+        create_dir_all(&cache).unwrap();
+        let ident = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        let artifact = cache.join(ident.archive_name_with_target(PackageTarget::active_target())
+                                       .unwrap());
+        let tarball = root.path().join("hab-sup.tar.xz");
+        write(&tarball, "not really a tarball").unwrap();
+        assert!(verify_artifact(&ident, &[cache.clone()], &key_cache).unwrap_err()
+                                                                     .contains("no cached"));
+
+        let key = |origin: &str| {
+            let (public, secret) = generate_signing_key_pair(&Origin::from_str(origin).unwrap());
+            key_cache.write_key(&public).unwrap();
+            secret
+        };
+        let (core, other) = (key("core"), key("other"));
+        artifact::sign(&tarball, &artifact, &core).unwrap();
+        assert!(verify_artifact(&ident, &[cache.clone()], &key_cache).is_ok());
+
+        artifact::sign(&tarball, &artifact, &other).unwrap();
+        assert!(verify_artifact(&ident, &[cache.clone()], &key_cache).unwrap_err()
+                                                                     .contains("not by origin"));
+
+        // Tampered with after signing
+        artifact::sign(&tarball, &artifact, &core).unwrap();
+        let mut content = std::fs::read(&artifact).unwrap();
+        content.extend_from_slice(b"tampered");
+        write(&artifact, content).unwrap();
+        assert!(verify_artifact(&ident, &[cache], &key_cache).unwrap_err()
+                                                             .contains("failed verification"));
+    }
+}
@@ -0,0 +1,78 @@
+//! Bounding concurrent installs across self updaters.
+//!
+//! Updaters for different Supervisor packages can share a host, and
+//! when a release lands in all of their channels at once their
+//! downloads and installs compete for disk and CPU. Handing each of
+//! them a clone of the same `InstallLimit` makes them take turns, with
+//! at most the configured number installing at a time. An updater
+//! without one installs whenever it likes.
+
+use log::debug;
+use std::sync::Arc;
+use tokio::sync::{Semaphore,
+                  SemaphorePermit};
+
+/// A limit on how many installs the updaters sharing it run at once.
+/// Clones share the same limit.
+#[derive(Clone, Debug)]
+pub struct InstallLimit(Arc<Semaphore>);
+
+impl InstallLimit {
+    /// Allow `concurrency` installs at once. A limit of zero would never
+    /// let any install run, so it is raised to one.
+    pub fn new(concurrency: usize) -> Self {
+        InstallLimit(Arc::new(Semaphore::new(concurrency.max(1))))
+    }
+
+    /// Wait for a turn to install. The turn is over when the permit is
+    /// dropped.
+    pub(super) async fn acquire(&self) -> SemaphorePermit<'_> {
+        if self.0.available_permits() == 0 {
+            debug!("Self updater waiting for another updater's install to finish");
+        }
+        // The semaphore is never closed
+        self.0
+            .acquire()
+            .await
+            .expect("install limit semaphore closed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::atomic::{AtomicUsize,
+                             Ordering},
+              time::Duration};
+    use tokio::time as tokiotime;
+
+    #[tokio::test]
+    async fn no_more_than_the_limit_install_at_once() {
+        let limit = InstallLimit::new(2);
+        let installing = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let updaters = (0..6).map(|_| {
+                                 let limit = limit.clone();
+                                 let installing = Arc::clone(&installing);
+                                 let most = Arc::clone(&most);
+                                 tokio::spawn(async move {
+                                     let _permit = limit.acquire().await;
+                                     let now = installing.fetch_add(1, Ordering::SeqCst) + 1;
+                                     most.fetch_max(now, Ordering::SeqCst);
+                                     tokiotime::sleep(Duration::from_millis(20)).await;
+                                     installing.fetch_sub(1, Ordering::SeqCst);
+                                 })
+                             })
+                             .collect::<Vec<_>>();
+        for updater in updaters {
+            updater.await.unwrap();
+        }
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+        assert_eq!(installing.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn a_zero_limit_still_lets_installs_through() {
+        assert_eq!(InstallLimit::new(0).0.available_permits(), 1);
+    }
+}
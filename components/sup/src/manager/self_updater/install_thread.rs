@@ -0,0 +1,109 @@
+//! A dedicated thread for self-update installs.
+//!
+//! Downloading and unpacking a Supervisor package competes with the
+//! services for CPU and IO. When asked to, the self updater does that
+//! work on a thread of its own with lowered OS priority, which runs
+//! its own single-threaded runtime. Where the priority can't be
+//! lowered the thread is still used, at normal priority.
+
+use super::artifact_cache;
+use crate::{error::Error as SupError,
+            sys};
+use habitat_api_client::TransferStats;
+use habitat_common::command::package::install::InstallSource;
+use habitat_core::{package::PackageInstall,
+                   ChannelIdent};
+use habitat_http_client::ProxyAuth;
+use log::{error,
+          info,
+          warn};
+use std::{path::{Path,
+                 PathBuf},
+          sync::mpsc,
+          thread};
+use tokio::{runtime,
+            sync::oneshot};
+
+struct Request {
+    update_url:      String,
+    install_source:  InstallSource,
+    update_channel:  ChannelIdent,
+    shared_cache:    Option<PathBuf>,
+    bandwidth_limit: Option<u64>,
+    proxy_auth:      Option<ProxyAuth>,
+    reply:           oneshot::Sender<Result<(PackageInstall, TransferStats), SupError>>,
+}
+
+/// A handle to the install thread. The thread exits when this is
+/// dropped.
+pub(super) struct InstallThread {
+    requests: mpsc::Sender<Request>,
+}
+
+impl InstallThread {
+    /// Start the install thread, or return `None` if it can't be.
+    pub(super) fn start() -> Option<Self> {
+        let (requests, rx) = mpsc::channel::<Request>();
+        let spawned = thread::Builder::new().name("self-update-install".to_string())
+                                            .spawn(move || Self::run(rx));
+        match spawned {
+            Ok(_) => Some(InstallThread { requests }),
+            Err(err) => {
+                warn!("Self updater unable to start its install thread, installing at normal \
+                       priority: {}",
+                      err);
+                None
+            }
+        }
+    }
+
+    fn run(rx: mpsc::Receiver<Request>) {
+        match sys::lower_thread_priority() {
+            Ok(()) => info!("Self updater installing at low priority"),
+            Err(err) => {
+                warn!("Self updater unable to lower its install priority, installing at normal \
+                       priority: {}",
+                      err)
+            }
+        }
+        let runtime = match runtime::Builder::new_current_thread().enable_all().build() {
+            Ok(runtime) => runtime,
+            Err(err) => {
+                error!("Self updater unable to start its install runtime: {}", err);
+                return;
+            }
+        };
+        for request in rx {
+            let result = runtime.block_on(artifact_cache::install(&request.update_url,
+                                                                  &request.install_source,
+                                                                  &request.update_channel,
+                                                                  request.shared_cache.as_deref(),
+                                                                  request.bandwidth_limit,
+                                                                  request.proxy_auth.as_ref()));
+            // The self updater may have been restarted in the meantime
+            let _ = request.reply.send(result);
+        }
+    }
+
+    /// Install the newest package from `update_channel` on the install
+    /// thread, or return `None` if the thread has gone away.
+    pub(super) async fn install(&self,
+                                update_url: &str,
+                                install_source: &InstallSource,
+                                update_channel: &ChannelIdent,
+                                shared_cache: Option<&Path>,
+                                bandwidth_limit: Option<u64>,
+                                proxy_auth: Option<&ProxyAuth>)
+                                -> Option<Result<(PackageInstall, TransferStats), SupError>> {
+        let (reply, rx) = oneshot::channel();
+        let request = Request { update_url: update_url.to_string(),
+                                install_source: install_source.clone(),
+                                update_channel: update_channel.clone(),
+                                shared_cache: shared_cache.map(Path::to_path_buf),
+                                bandwidth_limit,
+                                proxy_auth: proxy_auth.cloned(),
+                                reply };
+        self.requests.send(request).ok()?;
+        rx.await.ok()
+    }
+}
@@ -0,0 +1,143 @@
+//! How far the running Supervisor has fallen behind its channel.
+//!
+//! Releases are named for the time they were built, so the gap between
+//! the running release and the newest one in the channel is a measure
+//! that can be compared across a fleet, whatever versions are involved.
+//! The number of releases in between tells how much has changed.
+
+use crate::util;
+use chrono::{DateTime,
+             NaiveDateTime,
+             Utc};
+use habitat_core::{package::PackageIdent,
+                   ChannelIdent};
+use habitat_http_client::ProxyAuth;
+use log::debug;
+use serde::{Serialize,
+            Serializer};
+use std::{collections::HashSet,
+          time::Duration};
+
+/// The format of a package release.
+const RELEASE_FORMAT: &str = "%Y%m%d%H%M%S";
+
+/// The running Supervisor compared to the newest in its channel, as of
+/// a check.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct UpdateLag {
+    pub current:         PackageIdent,
+    /// The head of the channel, whatever release is staged.
+    pub latest:          PackageIdent,
+    /// How much older the running release is than the newest one; zero
+    /// when it is up to date. `None` if either release can't be dated.
+    #[serde(rename = "age_secs", serialize_with = "as_secs")]
+    pub age:             Option<Duration>,
+    /// How many releases in the channel are newer than the running one.
+    /// `None` if the channel's releases couldn't be listed.
+    pub releases_behind: Option<usize>,
+    #[serde(serialize_with = "as_rfc3339")]
+    pub checked_at:      DateTime<Utc>,
+}
+
+impl UpdateLag {
+    pub(super) fn new(current: &PackageIdent, latest: &PackageIdent) -> Self {
+        let age = match (built_at(current), built_at(latest)) {
+            (Some(current), Some(latest)) => Some((latest - current).to_std().unwrap_or_default()),
+            _ => None,
+        };
+        let releases_behind = if current < latest { None } else { Some(0) };
+        UpdateLag { current: current.clone(),
+                    latest: latest.clone(),
+                    age,
+                    releases_behind,
+                    checked_at: Utc::now() }
+    }
+
+    /// Count the `releases` in the channel that are newer than the
+    /// running one, up to the newest.
+    fn counting(mut self, releases: &[PackageIdent]) -> Self {
+        let newer = releases.iter()
+                            .filter(|release| self.current < **release && **release <= self.latest)
+                            .collect::<HashSet<_>>();
+        self.releases_behind = Some(newer.len());
+        self
+    }
+
+    /// Whether a newer Supervisor than the running one is in the
+    /// channel.
+    pub fn is_behind(&self) -> bool { self.current < self.latest }
+}
+
+/// Measure how far `current` is behind `latest`, the newest `sup_ident`
+/// in `channel`. The channel's releases are only listed when there is
+/// something to count.
+pub(super) async fn measure(url: &str,
+                            sup_ident: &PackageIdent,
+                            channel: &ChannelIdent,
+                            current: &PackageIdent,
+                            latest: &PackageIdent,
+                            proxy_auth: Option<&ProxyAuth>)
+                            -> UpdateLag {
+    let lag = UpdateLag::new(current, latest);
+    if !lag.is_behind() {
+        return lag;
+    }
+    match util::pkg::channel_releases(url, sup_ident, channel, proxy_auth).await {
+        Ok(releases) => lag.counting(&releases),
+        Err(err) => {
+            debug!("Unable to count the releases {} is behind, {}",
+                   current, err);
+            lag
+        }
+    }
+}
+
+fn as_secs<S: Serializer>(age: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+    age.map(|age| age.as_secs()).serialize(serializer)
+}
+
+fn as_rfc3339<S: Serializer>(at: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&at.to_rfc3339())
+}
+
+fn built_at(ident: &PackageIdent) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(ident.release.as_deref()?, RELEASE_FORMAT).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn lag_is_the_age_of_the_running_release() {
+        let current = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        let latest = PackageIdent::from_str("core/hab-sup/1.1.0/20200103120000").unwrap();
+
+        let lag = UpdateLag::new(&current, &latest);
+        assert!(lag.is_behind());
+        assert_eq!(lag.age, Some(Duration::from_secs(60 * 60 * 60)));
+
+        let lag = UpdateLag::new(&latest, &latest);
+        assert!(!lag.is_behind());
+        assert_eq!(lag.age, Some(Duration::ZERO));
+        assert_eq!(lag.releases_behind, Some(0));
+    }
+
+    #[test]
+    fn releases_behind_counts_each_newer_release_once() {
+        let ident = |ident| PackageIdent::from_str(ident).unwrap();
+        let current = ident("core/hab-sup/1.0.0/20200101000000");
+        let latest = ident("core/hab-sup/1.2.0/20200103000000");
+        // As listed for the channel, newest first and with a repeat
+        let releases = [ident("core/hab-sup/1.2.0/20200103000000"),
+                        ident("core/hab-sup/1.1.0/20200102000000"),
+                        ident("core/hab-sup/1.1.0/20200102000000"),
+                        current.clone(),
+                        ident("core/hab-sup/0.9.0/20191201000000")];
+
+        let lag = UpdateLag::new(&current, &latest);
+        assert_eq!(lag.releases_behind, None);
+        assert_eq!(lag.counting(&releases).releases_behind, Some(2));
+    }
+}
@@ -0,0 +1,266 @@
+//! A plan of the next update, for review ahead of approving it.
+//!
+//! Where updates have to go through change management, an operator
+//! approving one wants to know what it entails before the Supervisor
+//! restarts into it: what it moves from and to, what it will download,
+//! how its dependencies differ and what the self updater will do along
+//! the way. The plan gathers all of that in one place.
+
+use super::SelfUpdaterOptions;
+use habitat_core::{package::{PackageIdent,
+                             PackageTarget},
+                   ChannelIdent};
+use serde::Serialize;
+use std::{fmt,
+          fs,
+          path::PathBuf};
+
+/// What the next update the self updater applies would entail.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct UpgradePlan {
+    /// The running Supervisor.
+    pub from:               PackageIdent,
+    /// The newer Supervisor that would be applied, if one has been
+    /// found.
+    pub to:                 Option<PackageIdent>,
+    pub channel:            ChannelIdent,
+    pub url:                String,
+    /// The size of `to`'s artifact where it has already been downloaded,
+    /// and otherwise the size of the last download as an estimate.
+    pub download_bytes:     Option<u64>,
+    /// How `to`'s direct dependencies differ from `from`'s. `None` when
+    /// either can't be read, as for a candidate found in check-only
+    /// mode, which is never installed.
+    pub dependency_changes: Option<Vec<DependencyChange>>,
+    /// What the self updater does on the way to restarting into `to`,
+    /// in order.
+    pub steps:              Vec<UpgradeStep>,
+}
+
+/// A difference between the dependencies of two Supervisors.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum DependencyChange {
+    Added {
+        ident: PackageIdent,
+    },
+    Removed {
+        ident: PackageIdent,
+    },
+    Changed {
+        from: PackageIdent,
+        to:   PackageIdent,
+    },
+}
+
+/// Something configured to happen before or after the restart into an
+/// update.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum UpgradeStep {
+    ForkTest,
+    Backup { dir: PathBuf },
+    Migration,
+    Approval { file: PathBuf },
+    LauncherConnection,
+    RestartLock,
+    Drains,
+    RollbackWindow { secs: u64 },
+}
+
+impl fmt::Display for DependencyChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyChange::Added { ident } => write!(f, "added {}", ident),
+            DependencyChange::Removed { ident } => write!(f, "removed {}", ident),
+            DependencyChange::Changed { from, to } => write!(f, "{} -> {}", from, to),
+        }
+    }
+}
+
+impl fmt::Display for UpgradeStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpgradeStep::ForkTest => write!(f, "run the candidate's fork test"),
+            UpgradeStep::Backup { dir } => {
+                write!(f, "back up the running Supervisor to {}", dir.display())
+            }
+            UpgradeStep::Migration => write!(f, "run the configured migration"),
+            UpgradeStep::Approval { file } => {
+                write!(f, "wait for approval in {}", file.display())
+            }
+            UpgradeStep::LauncherConnection => write!(f, "wait for the Launcher connection"),
+            UpgradeStep::RestartLock => write!(f, "take the restart lock"),
+            UpgradeStep::Drains => write!(f, "wait for connection drains"),
+            UpgradeStep::RollbackWindow { secs } => {
+                write!(f, "keep a rollback window open for {}s", secs)
+            }
+        }
+    }
+}
+
+impl fmt::Display for UpgradePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "From:         {}", self.from)?;
+        match &self.to {
+            Some(to) => writeln!(f, "To:           {}", to)?,
+            None => writeln!(f, "To:           nothing newer found")?,
+        }
+        writeln!(f, "Channel:      {} at {}", self.channel, self.url)?;
+        match self.download_bytes {
+            Some(bytes) => writeln!(f, "Download:     about {} bytes", bytes)?,
+            None => writeln!(f, "Download:     unknown")?,
+        }
+        match self.dependency_changes.as_deref() {
+            None => writeln!(f, "Dependencies: unknown")?,
+            Some([]) => writeln!(f, "Dependencies: unchanged")?,
+            Some(changes) => {
+                writeln!(f, "Dependencies:")?;
+                for change in changes {
+                    writeln!(f, "  {}", change)?;
+                }
+            }
+        }
+        if self.steps.is_empty() {
+            writeln!(f, "Steps:        none")
+        } else {
+            writeln!(f, "Steps:")?;
+            for (number, step) in self.steps.iter().enumerate() {
+                writeln!(f, "  {}. {}", number + 1, step)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// How the dependencies `to` differ from the dependencies `from`,
+/// matching them up by origin and name.
+pub(super) fn dependency_changes(from: &[PackageIdent],
+                                 to: &[PackageIdent])
+                                 -> Vec<DependencyChange> {
+    let same_package =
+        |a: &PackageIdent, b: &PackageIdent| a.origin == b.origin && a.name == b.name;
+    let mut changes = Vec::new();
+    for old in from {
+        match to.iter().find(|new| same_package(old, new)) {
+            Some(new) if new != old => {
+                changes.push(DependencyChange::Changed { from: old.clone(),
+                                                         to:   new.clone(), })
+            }
+            Some(_) => {}
+            None => changes.push(DependencyChange::Removed { ident: old.clone() }),
+        }
+    }
+    for new in to {
+        if !from.iter().any(|old| same_package(old, new)) {
+            changes.push(DependencyChange::Added { ident: new.clone() });
+        }
+    }
+    changes
+}
+
+/// The size of `ident`'s artifact in the first of `caches` that has
+/// it.
+pub(super) fn artifact_bytes(ident: &PackageIdent, caches: &[PathBuf]) -> Option<u64> {
+    let name = ident.archive_name_with_target(PackageTarget::active_target())
+                    .ok()?;
+    caches.iter()
+          .find_map(|cache| fs::metadata(cache.join(&name)).ok())
+          .map(|metadata| metadata.len())
+}
+
+/// The steps `options` configure, in the order the self updater takes
+/// them.
+pub(super) fn steps(options: &SelfUpdaterOptions) -> Vec<UpgradeStep> {
+    let mut steps = Vec::new();
+    if options.fork_test.is_some() {
+        steps.push(UpgradeStep::ForkTest);
+    }
+    if let Some(dir) = &options.backup_dir {
+        steps.push(UpgradeStep::Backup { dir: dir.clone() });
+    }
+    if let Some(file) = &options.approval_file {
+        steps.push(UpgradeStep::Approval { file: file.clone() });
+    }
+    if options.launcher_state.is_some() {
+        steps.push(UpgradeStep::LauncherConnection);
+    }
+    if options.drains.is_some() {
+        steps.push(UpgradeStep::Drains);
+    }
+    if options.restart_lock.is_some() {
+        steps.push(UpgradeStep::RestartLock);
+    }
+    if options.migration.is_some() {
+        steps.push(UpgradeStep::Migration);
+    }
+    // The window is kept in the backup directory
+    if let (Some(window), Some(_)) = (options.rollback_window, &options.backup_dir) {
+        steps.push(UpgradeStep::RollbackWindow { secs: window.as_secs(), });
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::RestartLock,
+                *};
+    use std::{str::FromStr,
+              sync::Arc,
+              time::Duration};
+
+    fn ident(ident: &str) -> PackageIdent { PackageIdent::from_str(ident).unwrap() }
+
+    /// A lock that is always free.
+    struct Free;
+
+    impl RestartLock for Free {
+        fn try_acquire(&self, _candidate: &PackageIdent) -> Result<bool, String> { Ok(true) }
+
+        fn release(&self, _candidate: &PackageIdent) {}
+    }
+
+    #[test]
+    fn dependencies_are_matched_by_origin_and_name() {
+        let from = [ident("core/glibc/2.27/20200101000000"),
+                    ident("core/openssl/1.0.2/20200101000000"),
+                    ident("core/busybox/1.0.0/20200101000000")];
+        let to = [ident("core/glibc/2.27/20200101000000"),
+                  ident("core/openssl/1.1.1/20200102000000"),
+                  ident("core/zlib/1.2.11/20200102000000")];
+
+        assert_eq!(dependency_changes(&from, &to),
+                   [DependencyChange::Changed { from: ident("core/openssl/1.0.2/20200101000000"),
+                                                to:   ident("core/openssl/1.1.1/20200102000000"), },
+                    DependencyChange::Removed { ident: ident("core/busybox/1.0.0/20200101000000"), },
+                    DependencyChange::Added { ident: ident("core/zlib/1.2.11/20200102000000"), }]);
+        assert!(dependency_changes(&from, &from).is_empty());
+    }
+
+    #[test]
+    fn steps_follow_the_options_in_order() {
+        assert!(steps(&SelfUpdaterOptions::default()).is_empty());
+
+        let options = SelfUpdaterOptions { backup_dir: Some(PathBuf::from("/hab/backup")),
+                                           approval_file: Some(PathBuf::from("/hab/approve")),
+                                           migration: Some(Arc::new(|_, _| Ok(()))),
+                                           drains: Some(Default::default()),
+                                           restart_lock: Some(Arc::new(Free)),
+                                           rollback_window: Some(Duration::from_secs(600)),
+                                           ..Default::default() };
+        assert_eq!(steps(&options),
+                   [UpgradeStep::Backup { dir: PathBuf::from("/hab/backup"), },
+                    UpgradeStep::Approval { file: PathBuf::from("/hab/approve"), },
+                    UpgradeStep::Drains,
+                    UpgradeStep::RestartLock,
+                    UpgradeStep::Migration,
+                    UpgradeStep::RollbackWindow { secs: 600 }]);
+    }
+
+    #[test]
+    fn a_rollback_window_needs_a_backup_dir() {
+        let options = SelfUpdaterOptions { rollback_window: Some(Duration::from_secs(600)),
+                                           ..Default::default() };
+        assert!(steps(&options).is_empty());
+    }
+}
@@ -0,0 +1,340 @@
+//! Whether the self updater should stage a candidate Supervisor.
+//!
+//! Everything here is a pure function of the running Supervisor, the
+//! candidate, and the state gathered at the time of the check, so that
+//! every combination of options can be tested without touching the
+//! network or the filesystem. Verifying that the candidate actually
+//! runs on this host happens afterwards, and only once it is `Stage`.
+
+use super::{quarantine::Quarantine,
+            DeclineReason};
+use habitat_core::package::PackageIdent;
+use std::time::{Duration,
+                Instant};
+
+/// The state `decide` weighs a candidate against.
+pub(super) struct Policy<'a> {
+    pub now:         Instant,
+    /// When the running Supervisor started, which the warmup is counted
+    /// from.
+    pub started:     Instant,
+    pub warmup:      Option<Duration>,
+    pub quarantine:  Option<&'a Quarantine>,
+    /// How much longer the channel embargoes the candidate, if it does.
+    pub embargo:     Option<Duration>,
+    /// The releases the Supervisor has rolled back from.
+    pub rolled_back: &'a [PackageIdent],
+}
+
+/// What to do with a candidate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Decision {
+    /// It is not newer than the running Supervisor.
+    NotNewer,
+    /// The Supervisor has rolled back from it before.
+    RolledBack,
+    /// The channel embargoes it for this much longer.
+    Embargoed(Duration),
+    /// It has to remain the newest in the channel for this much longer.
+    Quarantined(Duration),
+    /// The running Supervisor is still warming up, for this much
+    /// longer.
+    WarmingUp(Duration),
+    /// It can be verified and staged.
+    Stage,
+}
+
+impl Decision {
+    /// Why the candidate is not being staged, if it is newer but held
+    /// back.
+    pub(super) fn decline_reason(self) -> Option<DeclineReason> {
+        match self {
+            Decision::Quarantined(_) => Some(DeclineReason::Quarantined),
+            Decision::WarmingUp(_) => Some(DeclineReason::WarmingUp),
+            Decision::Embargoed(_) => Some(DeclineReason::Embargoed),
+            Decision::RolledBack => Some(DeclineReason::RolledBack),
+            Decision::NotNewer | Decision::Stage => None,
+        }
+    }
+
+    /// What holds a newer candidate back for now, if anything does.
+    pub(super) fn hold(self) -> Option<Hold> {
+        let (remaining, name, reason) = match self {
+            Decision::Embargoed(remaining) => (remaining, "embargoed", "under embargo"),
+            Decision::Quarantined(remaining) => (remaining, "quarantined", "in quarantine"),
+            Decision::WarmingUp(remaining) => (remaining, "warming_up", "warming up"),
+            Decision::NotNewer | Decision::RolledBack | Decision::Stage => return None,
+        };
+        Some(Hold { remaining,
+                    name,
+                    reason })
+    }
+}
+
+/// A candidate held back until it has waited out the embargo,
+/// quarantine or warmup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct Hold {
+    /// How much longer it is held, after which it is checked again.
+    pub remaining: Duration,
+    /// The decision, as traced for the check.
+    pub name:      &'static str,
+    pub reason:    &'static str,
+}
+
+impl Hold {
+    /// Why the candidate is held, and for how much longer.
+    pub(super) fn detail(&self) -> String {
+        format!("{} for another {}s", self.reason, self.remaining.as_secs())
+    }
+}
+
+/// Decide what to do with `candidate`, the newest Supervisor in the
+/// channel, while `current` is running.
+#[allow(clippy::neg_cmp_op_on_partial_ord)]
+pub(super) fn decide(current: &PackageIdent,
+                     candidate: &PackageIdent,
+                     policy: &Policy<'_>)
+                     -> Decision {
+    // Idents of different packages, or without a version, don't
+    // compare at all, and are never newer
+    if !(current < candidate) {
+        return Decision::NotNewer;
+    }
+    if policy.rolled_back.contains(candidate) {
+        return Decision::RolledBack;
+    }
+    if let Some(remaining) = policy.embargo.filter(|remaining| !remaining.is_zero()) {
+        return Decision::Embargoed(remaining);
+    }
+    if let Some(remaining) =
+        policy.quarantine
+              .and_then(|quarantine| quarantine.remaining(candidate, policy.now))
+    {
+        return Decision::Quarantined(remaining);
+    }
+    if let Some(remaining) =
+        policy.warmup
+              .and_then(|warmup| warmup_remaining(policy.started, warmup, policy.now))
+    {
+        return Decision::WarmingUp(remaining);
+    }
+    Decision::Stage
+}
+
+/// How much of the `warmup` after `started` is left at `now`, if any.
+pub(super) fn warmup_remaining(started: Instant,
+                               warmup: Duration,
+                               now: Instant)
+                               -> Option<Duration> {
+    (started + warmup).checked_duration_since(now)
+                      .filter(|remaining| !remaining.is_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng,
+               Rng,
+               SeedableRng};
+    use std::{cmp::Ordering,
+              str::FromStr};
+
+    fn ident(s: &str) -> PackageIdent { PackageIdent::from_str(s).unwrap() }
+
+    /// A random ident, drawn from a small enough space that equal
+    /// versions and releases come up often. Most are of the Supervisor,
+    /// but some are of another package or have no version, so as not to
+    /// compare with the rest.
+    fn random_ident(rng: &mut StdRng) -> PackageIdent {
+        let name = ["core/hab-sup",
+                    "core/hab-sup",
+                    "core/hab-sup",
+                    "acme/hab-sup",
+                    "core/hab"][rng.gen_range(0..5)];
+        if rng.gen_range(0..10) == 0 {
+            return ident(name);
+        }
+        let parts = rng.gen_range(1..=3);
+        let version = (0..parts).map(|_| rng.gen_range(0..4).to_string())
+                                .collect::<Vec<_>>()
+                                .join(".");
+        let release = format!("2020010{}00000{}", rng.gen_range(1..4), rng.gen_range(0..3));
+        ident(&format!("{}/{}/{}", name, version, release))
+    }
+
+    #[test]
+    fn only_newer_candidates_are_ever_considered() {
+        let mut rng = StdRng::seed_from_u64(0x5e1f_u64);
+        let started = Instant::now();
+        let now = started + Duration::from_secs(100);
+        for _ in 0..2_000 {
+            let current = random_ident(&mut rng);
+            let candidate = random_ident(&mut rng);
+            let mut quarantine = Quarantine::new(Duration::from_secs(rng.gen_range(1..200)));
+            if rng.gen() {
+                quarantine.observe(&candidate, started);
+            }
+            let warmup = Duration::from_secs(rng.gen_range(0..200));
+            let embargo = Duration::from_secs(rng.gen_range(0..200));
+            let policy = Policy { now,
+                                  started,
+                                  warmup: Some(warmup).filter(|_| rng.gen()),
+                                  quarantine: Some(&quarantine).filter(|_| rng.gen()),
+                                  embargo: Some(embargo).filter(|_| rng.gen()),
+                                  rolled_back: &[] };
+            let forward = decide(&current, &candidate, &policy);
+            let backward = decide(&candidate, &current, &policy);
+
+            assert_eq!(forward != Decision::NotNewer,
+                       current < candidate,
+                       "{} -> {} was {:?}",
+                       current,
+                       candidate,
+                       forward);
+            match current.partial_cmp(&candidate) {
+                // Neither is an upgrade on the other
+                Some(Ordering::Equal) | None => {
+                    assert_eq!(forward, Decision::NotNewer);
+                    assert_eq!(backward, Decision::NotNewer);
+                }
+                // Of two different releases, exactly one is an upgrade
+                Some(_) => {
+                    assert!((forward == Decision::NotNewer) != (backward == Decision::NotNewer),
+                            "{} and {} were {:?} and {:?}",
+                            current,
+                            candidate,
+                            forward,
+                            backward)
+                }
+            }
+            match forward {
+                Decision::Quarantined(remaining)
+                | Decision::WarmingUp(remaining)
+                | Decision::Embargoed(remaining) => assert!(!remaining.is_zero()),
+                Decision::Stage => {
+                    assert!(policy.embargo.filter(|e| !e.is_zero()).is_none());
+                    assert!(policy.quarantine
+                                  .and_then(|q| q.remaining(&candidate, now))
+                                  .is_none());
+                    assert!(policy.warmup
+                                  .and_then(|w| warmup_remaining(started, w, now))
+                                  .is_none());
+                }
+                Decision::NotNewer | Decision::RolledBack => (),
+            }
+        }
+    }
+
+    #[test]
+    fn every_combination_of_options() {
+        let current = ident("core/hab-sup/1.0.0/20200101000000");
+        let newer = ident("core/hab-sup/1.0.1/20200102000000");
+        let older = ident("core/hab-sup/0.9.0/20191201000000");
+        let started = Instant::now();
+        let now = started + Duration::from_secs(100);
+        let mut soaking = Quarantine::new(Duration::from_secs(60));
+        soaking.observe(&newer, now - Duration::from_secs(20));
+        let mut soaked = Quarantine::new(Duration::from_secs(60));
+        soaked.observe(&newer, now - Duration::from_secs(90));
+
+        let quarantines = [(None, None),
+                           (Some(&soaking), Some(Duration::from_secs(40))),
+                           (Some(&soaked), None)];
+        let warmups = [(None, None),
+                       (Some(Duration::from_secs(300)), Some(Duration::from_secs(200))),
+                       (Some(Duration::from_secs(50)), None)];
+        let embargoes = [(None, None),
+                         (Some(Duration::from_secs(30)), Some(Duration::from_secs(30))),
+                         (Some(Duration::ZERO), None)];
+        for (quarantine, held) in quarantines.iter().copied() {
+            for (warmup, warming_up) in warmups.iter().copied() {
+                for (embargo, embargoed) in embargoes.iter().copied() {
+                    let policy = Policy { now,
+                                          started,
+                                          warmup,
+                                          quarantine,
+                                          embargo,
+                                          rolled_back: &[] };
+                    let expected = match (embargoed, held, warming_up) {
+                        (Some(remaining), ..) => Decision::Embargoed(remaining),
+                        (None, Some(remaining), _) => Decision::Quarantined(remaining),
+                        (None, None, Some(remaining)) => Decision::WarmingUp(remaining),
+                        (None, None, None) => Decision::Stage,
+                    };
+                    assert_eq!(decide(&current, &newer, &policy), expected);
+                    assert_eq!(decide(&current, &current, &policy), Decision::NotNewer);
+                    assert_eq!(decide(&current, &older, &policy), Decision::NotNewer);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn only_held_candidates_are_declined() {
+        let remaining = Duration::from_secs(1);
+        assert_eq!(Decision::Quarantined(remaining).decline_reason(),
+                   Some(DeclineReason::Quarantined));
+        assert_eq!(Decision::WarmingUp(remaining).decline_reason(),
+                   Some(DeclineReason::WarmingUp));
+        assert_eq!(Decision::Embargoed(remaining).decline_reason(),
+                   Some(DeclineReason::Embargoed));
+        assert_eq!(Decision::RolledBack.decline_reason(),
+                   Some(DeclineReason::RolledBack));
+        assert_eq!(Decision::NotNewer.decline_reason(), None);
+        assert_eq!(Decision::Stage.decline_reason(), None);
+    }
+
+    #[test]
+    fn only_candidates_waiting_something_out_are_held() {
+        let remaining = Duration::from_secs(90);
+        let held = [Decision::Embargoed(remaining),
+                    Decision::Quarantined(remaining),
+                    Decision::WarmingUp(remaining)];
+        for decision in held.iter().copied() {
+            let hold = decision.hold().unwrap();
+            assert_eq!(hold.remaining, remaining);
+            assert!(hold.detail().ends_with(" for another 90s"),
+                    "{}",
+                    hold.detail());
+        }
+        assert_eq!(Decision::Embargoed(remaining).hold().unwrap().detail(),
+                   "under embargo for another 90s");
+        assert_eq!(Decision::NotNewer.hold(), None);
+        assert_eq!(Decision::RolledBack.hold(), None);
+        assert_eq!(Decision::Stage.hold(), None);
+    }
+
+    #[test]
+    fn an_unseen_candidate_serves_the_whole_quarantine() {
+        let current = ident("core/hab-sup/1.0.0/20200101000000");
+        let candidate = ident("core/hab-sup/1.0.1/20200102000000");
+        let quarantine = Quarantine::new(Duration::from_secs(60));
+        let now = Instant::now();
+        let policy = Policy { now,
+                              started: now,
+                              warmup: None,
+                              quarantine: Some(&quarantine),
+                              embargo: None,
+                              rolled_back: &[] };
+        assert_eq!(decide(&current, &candidate, &policy),
+                   Decision::Quarantined(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn a_release_rolled_back_from_waits_for_a_newer_one() {
+        let current = ident("core/hab-sup/1.0.0/20200101000000");
+        let rolled_back = [ident("core/hab-sup/1.0.1/20200102000000")];
+        let newer = ident("core/hab-sup/1.0.2/20200103000000");
+        let now = Instant::now();
+        let policy = Policy { now,
+                              started: now,
+                              warmup: None,
+                              quarantine: None,
+                              embargo: None,
+                              rolled_back: &rolled_back };
+        assert_eq!(decide(&current, &rolled_back[0], &policy),
+                   Decision::RolledBack);
+        assert_eq!(decide(&current, &newer, &policy), Decision::Stage);
+    }
+}
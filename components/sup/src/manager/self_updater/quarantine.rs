@@ -0,0 +1,95 @@
+//! A soak period for newly published Supervisors.
+//!
+//! A package that is published and then quickly withdrawn should never
+//! be staged. With a quarantine configured, a candidate must remain the
+//! newest Supervisor in the channel for the whole period before the
+//! self updater will stage it. If a different candidate becomes the
+//! newest in the meantime, the period starts over for that one.
+
+use habitat_core::package::PackageIdent;
+use std::time::{Duration,
+                Instant};
+
+pub(super) struct Quarantine {
+    period: Duration,
+    /// The current newest candidate, and when it was first seen.
+    newest: Option<(PackageIdent, Instant)>,
+}
+
+impl Quarantine {
+    pub(super) fn new(period: Duration) -> Self {
+        Quarantine { period,
+                     newest: None }
+    }
+
+    /// The candidate whose quarantine is still running, if any.
+    pub(super) fn quarantined(&self) -> Option<&PackageIdent> {
+        self.newest
+            .as_ref()
+            .filter(|(_, first_seen)| first_seen.elapsed() < self.period)
+            .map(|(ident, _)| ident)
+    }
+
+    /// Record that `candidate` is the newest Supervisor as of `now`.
+    pub(super) fn observe(&mut self, candidate: &PackageIdent, now: Instant) {
+        if self.first_seen(candidate).is_none() {
+            self.newest = Some((candidate.clone(), now));
+        }
+    }
+
+    /// How much longer `candidate` must stay the newest Supervisor
+    /// after `now` before it can be staged, or `None` if its
+    /// quarantine is over. A candidate that hasn't been observed yet
+    /// has the whole period ahead of it.
+    pub(super) fn remaining(&self, candidate: &PackageIdent, now: Instant) -> Option<Duration> {
+        let first_seen = self.first_seen(candidate).unwrap_or(now);
+        self.period
+            .checked_sub(now.saturating_duration_since(first_seen))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    fn first_seen(&self, candidate: &PackageIdent) -> Option<Instant> {
+        self.newest
+            .as_ref()
+            .filter(|(ident, _)| ident == candidate)
+            .map(|(_, first_seen)| *first_seen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn ident(s: &str) -> PackageIdent { PackageIdent::from_str(s).unwrap() }
+
+    #[test]
+    fn candidate_is_held_until_the_period_elapses() {
+        let mut quarantine = Quarantine::new(Duration::from_secs(60));
+        let candidate = ident("core/hab-sup/1.0.0/20200101000000");
+        let start = Instant::now();
+        quarantine.observe(&candidate, start);
+        assert_eq!(quarantine.remaining(&candidate, start),
+                   Some(Duration::from_secs(60)));
+        assert_eq!(quarantine.remaining(&candidate, start + Duration::from_secs(45)),
+                   Some(Duration::from_secs(15)));
+        assert_eq!(quarantine.remaining(&candidate, start + Duration::from_secs(60)),
+                   None);
+    }
+
+    #[test]
+    fn a_new_candidate_restarts_the_period() {
+        let mut quarantine = Quarantine::new(Duration::from_secs(60));
+        let first = ident("core/hab-sup/1.0.0/20200101000000");
+        let second = ident("core/hab-sup/1.0.1/20200102000000");
+        let start = Instant::now();
+        quarantine.observe(&first, start);
+        quarantine.observe(&second, start + Duration::from_secs(50));
+        assert_eq!(quarantine.remaining(&second, start + Duration::from_secs(50)),
+                   Some(Duration::from_secs(60)));
+        // The first candidate was withdrawn and has to soak again
+        quarantine.observe(&first, start + Duration::from_secs(70));
+        assert_eq!(quarantine.remaining(&first, start + Duration::from_secs(70)),
+                   Some(Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,88 @@
+//! Coordination of self-update restarts across a fleet.
+//!
+//! When every Supervisor in a cluster follows the same channel, they
+//! would all restart into a new release at about the same time. With a
+//! `RestartLock` configured, a Supervisor has to take the lock once an
+//! update is otherwise ready to be handed over, after any connection
+//! drains, and holds it only until the restart has been initiated, so
+//! an implementation backed by a distributed lock (a semaphore, for
+//! instance) bounds how many restart at once.
+
+use habitat_core::package::PackageIdent;
+use log::{info,
+          warn};
+use std::time::Duration;
+use tokio::time as tokiotime;
+
+/// How long to wait between attempts to take the lock.
+pub(super) const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A lock shared by the Supervisors that should not restart for an
+/// update at the same time.
+pub trait RestartLock: Send + Sync {
+    /// Try to take the lock in order to restart into `candidate`,
+    /// returning whether it was taken. This should not block.
+    fn try_acquire(&self, candidate: &PackageIdent) -> Result<bool, String>;
+
+    /// Give back the lock taken for `candidate`.
+    fn release(&self, candidate: &PackageIdent);
+}
+
+/// Wait until `lock` is taken for `candidate`, trying again every
+/// `retry` while other Supervisors hold it.
+pub(super) async fn acquire(lock: &dyn RestartLock, candidate: &PackageIdent, retry: Duration) {
+    loop {
+        match lock.try_acquire(candidate) {
+            Ok(true) => return,
+            Ok(false) => {
+                info!("Self updater waiting {}s for its turn to restart into {}",
+                      retry.as_secs(),
+                      candidate)
+            }
+            Err(err) => warn!("Self updater unable to take the restart lock, {}", err),
+        }
+        tokiotime::sleep(retry).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{str::FromStr,
+              sync::atomic::{AtomicUsize,
+                             Ordering}};
+
+    /// Refuses the lock a given number of times before granting it.
+    struct Busy {
+        refusals: AtomicUsize,
+        attempts: AtomicUsize,
+    }
+
+    impl RestartLock for Busy {
+        fn try_acquire(&self, _candidate: &PackageIdent) -> Result<bool, String> {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            match self.refusals.load(Ordering::SeqCst) {
+                0 => Ok(true),
+                n => {
+                    self.refusals.store(n - 1, Ordering::SeqCst);
+                    if n % 2 == 0 {
+                        Ok(false)
+                    } else {
+                        Err("lock service unavailable".to_string())
+                    }
+                }
+            }
+        }
+
+        fn release(&self, _candidate: &PackageIdent) {}
+    }
+
+    #[tokio::test]
+    async fn waits_until_the_lock_is_granted() {
+        let lock = Busy { refusals: AtomicUsize::new(3),
+                          attempts: AtomicUsize::new(0), };
+        let candidate = PackageIdent::from_str("core/hab-sup/1.0.1/20200102000000").unwrap();
+        acquire(&lock, &candidate, Duration::from_millis(1)).await;
+        assert_eq!(lock.attempts.load(Ordering::SeqCst), 4);
+    }
+}
@@ -0,0 +1,311 @@
+//! A window after an update during which the Supervisor can go back to
+//! the release it replaced.
+//!
+//! The window has to outlive the restart into the new Supervisor, so it
+//! is kept in a file in the backup directory, next to the snapshot of
+//! the previous release. Once it closes, both may be removed.
+//!
+//! The Launcher always starts the newest Supervisor that is installed,
+//! so rolling back moves the newer release's install out of the package
+//! cache and into the backup directory. The release rolled back from is
+//! also recorded there, so that the self updater doesn't update straight
+//! back to it while it remains the newest in the channel.
+
+use super::{backup,
+            stage::move_aside};
+use chrono::{DateTime,
+             Utc};
+use habitat_core::{fs,
+                   fs::AtomicWriter,
+                   package::{PackageIdent,
+                             PackageInstall}};
+use log::{debug,
+          warn};
+use serde::{Deserialize,
+            Serialize};
+use std::{io::Write,
+          path::{Path,
+                 PathBuf},
+          time::Duration};
+
+/// The file in `backup_dir` that holds the open window of the updater
+/// tracking `sup_ident`.
+fn window_file(backup_dir: &Path, sup_ident: &PackageIdent) -> PathBuf {
+    backup_dir.join(format!("ROLLBACK-{}-{}", sup_ident.origin, sup_ident.name))
+}
+
+/// The file in `backup_dir` that lists the releases the updater
+/// tracking `sup_ident` has rolled back from.
+fn rolled_back_file(backup_dir: &Path, sup_ident: &PackageIdent) -> PathBuf {
+    backup_dir.join(format!("ROLLED-BACK-{}-{}", sup_ident.origin, sup_ident.name))
+}
+
+/// The releases the updater tracking `sup_ident` has rolled back from,
+/// as recorded in `backup_dir`.
+pub(super) fn rolled_back(backup_dir: &Path, sup_ident: &PackageIdent) -> Vec<PackageIdent> {
+    let path = rolled_back_file(backup_dir, sup_ident);
+    let body = match std::fs::read_to_string(&path) {
+        Ok(body) => body,
+        Err(_) => return Vec::new(),
+    };
+    body.lines()
+        .filter_map(|line| {
+            match line.trim().parse() {
+                Ok(ident) => Some(ident),
+                Err(e) => {
+                    warn!("Ignoring unreadable line in {}, {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Record in `backup_dir` that the updater tracking `sup_ident` rolled
+/// back from `ident`.
+pub(super) fn record_rolled_back(backup_dir: &Path,
+                                 sup_ident: &PackageIdent,
+                                 ident: &PackageIdent)
+                                 -> Result<(), String> {
+    let mut idents = rolled_back(backup_dir, sup_ident);
+    if idents.contains(ident) {
+        return Ok(());
+    }
+    idents.push(ident.clone());
+    let body = idents.iter()
+                     .map(|ident| format!("{}\n", ident))
+                     .collect::<String>();
+    std::fs::create_dir_all(backup_dir).map_err(|e| e.to_string())?;
+    let writer =
+        AtomicWriter::new(&rolled_back_file(backup_dir, sup_ident)).map_err(|e| e.to_string())?;
+    writer.with_writer(|f| f.write_all(body.as_bytes()))
+          .map_err(|e| e.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RollbackWindow {
+    /// The Supervisor that was replaced.
+    pub previous:   PackageIdent,
+    /// The Supervisor that replaced it.
+    pub updated_to: PackageIdent,
+    /// Where `previous` was backed up, if it was.
+    pub snapshot:   Option<PathBuf>,
+    /// When the window closes, in seconds since the Unix epoch.
+    pub expires_at: i64,
+}
+
+impl RollbackWindow {
+    pub(super) fn open(previous: &PackageIdent,
+                       updated_to: &PackageIdent,
+                       snapshot: Option<PathBuf>,
+                       window: Duration,
+                       now: DateTime<Utc>)
+                       -> Self {
+        let expires_at =
+            chrono::Duration::from_std(window).ok()
+                                              .and_then(|window| now.checked_add_signed(window))
+                                              .map_or(i64::MAX, |expires_at| {
+                                                  expires_at.timestamp()
+                                              });
+        RollbackWindow { previous: previous.clone(),
+                         updated_to: updated_to.clone(),
+                         snapshot,
+                         expires_at }
+    }
+
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool { now.timestamp() < self.expires_at }
+
+    pub(super) fn save(&self, backup_dir: &Path, sup_ident: &PackageIdent) -> Result<(), String> {
+        let body = serde_json::to_vec(self).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(backup_dir).map_err(|e| e.to_string())?;
+        let writer =
+            AtomicWriter::new(&window_file(backup_dir, sup_ident)).map_err(|e| e.to_string())?;
+        writer.with_writer(|f| f.write_all(&body))
+              .map_err(|e| e.to_string())
+    }
+
+    /// The window recorded in `backup_dir` for `sup_ident`, if it is
+    /// still open. A window that has closed is removed, along with the
+    /// snapshot it kept.
+    pub(super) fn load(backup_dir: &Path,
+                       sup_ident: &PackageIdent,
+                       now: DateTime<Utc>)
+                       -> Option<Self> {
+        let path = window_file(backup_dir, sup_ident);
+        let body = std::fs::read(&path).ok()?;
+        let window = match serde_json::from_slice::<RollbackWindow>(&body) {
+            Ok(window) => window,
+            Err(e) => {
+                warn!("Ignoring unreadable rollback window {}, {}",
+                      path.display(),
+                      e);
+                Self::close(backup_dir, sup_ident);
+                return None;
+            }
+        };
+        if window.is_open(now) {
+            return Some(window);
+        }
+        debug!("Rollback window to {} has closed", window.previous);
+        if let Some(snapshot) = &window.snapshot {
+            if let Err(e) = std::fs::remove_dir_all(snapshot) {
+                debug!("Unable to remove snapshot {}, {}", snapshot.display(), e);
+            }
+        }
+        Self::close(backup_dir, sup_ident);
+        None
+    }
+
+    /// Forget the window recorded in `backup_dir` for `sup_ident`.
+    pub(super) fn close(backup_dir: &Path, sup_ident: &PackageIdent) {
+        let path = window_file(backup_dir, sup_ident);
+        if let Err(e) = std::fs::remove_file(&path) {
+            debug!("Unable to remove rollback window {}, {}", path.display(), e);
+        }
+    }
+
+    /// Make `previous` the newest installed Supervisor again, restoring
+    /// it from its snapshot if it is no longer installed, and return
+    /// its install.
+    pub(super) fn roll_back(&self,
+                            fs_root_path: &Path,
+                            backup_dir: &Path)
+                            -> Result<PackageInstall, String> {
+        if PackageInstall::load(&self.previous, Some(fs_root_path)).is_err() {
+            let snapshot = self.snapshot
+                               .as_ref()
+                               .ok_or_else(|| format!("{} is not installed", self.previous))?;
+            backup::restore(snapshot,
+                            &fs::pkg_install_path(&self.previous, Some(fs_root_path)))?;
+        }
+        let previous = match PackageInstall::load(&self.previous, Some(fs_root_path)) {
+            Ok(previous) => previous,
+            Err(e) => return Err(format!("unable to load {}: {}", self.previous, e)),
+        };
+        let updated_to = fs::pkg_install_path(&self.updated_to, Some(fs_root_path));
+        if updated_to.is_dir() {
+            let name = self.updated_to.to_string().replace('/', "-");
+            let aside = backup_dir.join(format!("{}.rolled-back", name));
+            move_aside(&updated_to, &aside).map_err(|e| {
+                                               format!("unable to move {} aside: {}",
+                                                       self.updated_to, e)
+                                           })?;
+        }
+        Ok(previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::test_helpers::install,
+                *};
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    #[test]
+    fn window_is_kept_until_it_closes() {
+        let backups = TempDir::new().unwrap();
+        let previous = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        let updated_to = PackageIdent::from_str("core/hab-sup/1.0.1/20200102000000").unwrap();
+        let snapshot = backups.path().join("core-hab-sup-1.0.0-20200101000000");
+        std::fs::create_dir_all(&snapshot).unwrap();
+        let now = Utc::now();
+        let window = RollbackWindow::open(&previous,
+                                          &updated_to,
+                                          Some(snapshot.clone()),
+                                          Duration::from_secs(3600),
+                                          now);
+        let sup_ident = PackageIdent::from_str("core/hab-sup").unwrap();
+        window.save(backups.path(), &sup_ident).unwrap();
+
+        assert_eq!(RollbackWindow::load(backups.path(), &sup_ident, now),
+                   Some(window));
+        let later = now + chrono::Duration::hours(2);
+        assert_eq!(RollbackWindow::load(backups.path(), &sup_ident, later),
+                   None);
+        assert!(!snapshot.exists());
+        assert!(!window_file(backups.path(), &sup_ident).exists());
+    }
+
+    /// Repeatedly save and reload a window for `sup_ident`, as its
+    /// updater would, checking that it is never disturbed.
+    fn keep_window(backup_dir: PathBuf,
+                   sup_ident: PackageIdent,
+                   now: DateTime<Utc>)
+                   -> RollbackWindow {
+        let previous = format!("{}/1.0.0/20200101000000", sup_ident).parse()
+                                                                    .unwrap();
+        let updated_to = format!("{}/1.0.1/20200102000000", sup_ident).parse()
+                                                                      .unwrap();
+        let window =
+            RollbackWindow::open(&previous, &updated_to, None, Duration::from_secs(3600), now);
+        for _ in 0..50 {
+            window.save(&backup_dir, &sup_ident).unwrap();
+            let loaded = RollbackWindow::load(&backup_dir, &sup_ident, now);
+            assert_eq!(loaded.as_ref(), Some(&window));
+        }
+        window
+    }
+
+    #[test]
+    fn windows_of_different_supervisors_are_kept_apart() {
+        let backups = TempDir::new().unwrap();
+        let now = Utc::now();
+        let core = PackageIdent::from_str("core/hab-sup").unwrap();
+        let acme = PackageIdent::from_str("acme/hab-sup").unwrap();
+        let threads =
+            [&core, &acme].iter()
+                          .map(|sup_ident| {
+                              let backup_dir = backups.path().to_path_buf();
+                              let sup_ident = (*sup_ident).clone();
+                              std::thread::spawn(move || keep_window(backup_dir, sup_ident, now))
+                          })
+                          .collect::<Vec<_>>();
+        let windows = threads.into_iter()
+                             .map(|thread| thread.join().unwrap())
+                             .collect::<Vec<_>>();
+
+        RollbackWindow::close(backups.path(), &core);
+        assert_eq!(RollbackWindow::load(backups.path(), &core, now), None);
+        assert_eq!(RollbackWindow::load(backups.path(), &acme, now).as_ref(),
+                   Some(&windows[1]));
+    }
+
+    #[test]
+    fn rolling_back_moves_the_newer_release_aside() {
+        let root = TempDir::new().unwrap();
+        let backups = TempDir::new().unwrap();
+        let previous = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        let updated_to = PackageIdent::from_str("core/hab-sup/1.0.1/20200102000000").unwrap();
+        install(root.path(), &previous);
+        install(root.path(), &updated_to);
+        let window = RollbackWindow::open(&previous,
+                                          &updated_to,
+                                          None,
+                                          Duration::from_secs(3600),
+                                          Utc::now());
+
+        let install = window.roll_back(root.path(), backups.path()).unwrap();
+        assert_eq!(install.ident(), &previous);
+        assert!(!fs::pkg_install_path(&updated_to, Some(root.path())).exists());
+        assert!(backups.path()
+                       .join("core-hab-sup-1.0.1-20200102000000.rolled-back")
+                       .is_dir());
+    }
+
+    #[test]
+    fn releases_rolled_back_from_are_remembered() {
+        let backups = TempDir::new().unwrap();
+        let core = PackageIdent::from_str("core/hab-sup").unwrap();
+        let acme = PackageIdent::from_str("acme/hab-sup").unwrap();
+        let first = PackageIdent::from_str("core/hab-sup/1.0.1/20200102000000").unwrap();
+        let second = PackageIdent::from_str("core/hab-sup/1.0.2/20200103000000").unwrap();
+        assert!(rolled_back(backups.path(), &core).is_empty());
+
+        record_rolled_back(backups.path(), &core, &first).unwrap();
+        record_rolled_back(backups.path(), &core, &second).unwrap();
+        record_rolled_back(backups.path(), &core, &first).unwrap();
+        assert_eq!(rolled_back(backups.path(), &core), [first, second]);
+        assert!(rolled_back(backups.path(), &acme).is_empty());
+    }
+}
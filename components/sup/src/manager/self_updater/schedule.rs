@@ -0,0 +1,284 @@
+//! Cron-style schedules for self-update checks.
+//!
+//! Expressions use the five standard fields (minute, hour, day of
+//! month, month, day of week), each of which may be `*`, a value, a
+//! range (`1-5`), a list (`1,15`), or any of those with a step
+//! (`*/15`, `0-30/10`). Days of the week run from 0 (Sunday) to 7
+//! (also Sunday). As in cron, when both the day of month and the day
+//! of week are restricted, a day matching either one matches.
+//!
+//! Times are in the Supervisor's local time zone.
+
+use super::SelfUpdateError;
+use chrono::{DateTime,
+             Datelike,
+             Local,
+             NaiveDate,
+             NaiveDateTime,
+             TimeZone,
+             Timelike};
+use std::{fmt,
+          str::FromStr,
+          time::Duration};
+
+/// How far ahead to look for a matching time. Any satisfiable
+/// expression recurs within a 28 year calendar cycle.
+const MAX_DAYS: u32 = 28 * 366;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CronSchedule {
+    expression:         String,
+    minutes:            u64,
+    hours:              u64,
+    days_of_month:      u64,
+    months:             u64,
+    days_of_week:       u64,
+    /// Whether each day field was restricted, rather than `*`.
+    day_of_month_given: bool,
+    day_of_week_given:  bool,
+}
+
+impl CronSchedule {
+    /// How long from now until the next scheduled time.
+    pub fn until_next(&self) -> Duration { self.until_next_from(Local::now()) }
+
+    /// How long from `now` until the next scheduled time in its time
+    /// zone. A time skipped as the clocks go forward doesn't happen that
+    /// day, as in cron.
+    fn until_next_from<Tz: TimeZone>(&self, now: DateTime<Tz>) -> Duration {
+        let zone = now.timezone();
+        let mut after = now.naive_local();
+        while let Some(next) = self.next_after(after) {
+            match zone.from_local_datetime(&next).earliest() {
+                Some(next) => return (next - now).to_std().unwrap_or_default(),
+                None => after = next,
+            }
+        }
+        Duration::default()
+    }
+
+    /// The first scheduled time strictly after `after`.
+    fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let after = after.date().and_hms_opt(after.hour(), after.minute(), 0)?;
+        let mut date = after.date();
+        for _ in 0..MAX_DAYS {
+            if self.matches_day(date) {
+                for hour in bits(self.hours) {
+                    for minute in bits(self.minutes) {
+                        let candidate = date.and_hms_opt(hour, minute, 0)?;
+                        if candidate > after {
+                            return Some(candidate);
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let month = is_set(self.months, date.month());
+        let day_of_month = is_set(self.days_of_month, date.day());
+        let day_of_week = is_set(self.days_of_week, date.weekday().num_days_from_sunday());
+        let day = if self.day_of_month_given && self.day_of_week_given {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        };
+        month && day
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = SelfUpdateError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| {
+            SelfUpdateError::InvalidSchedule { expression: expression.to_string(),
+                                               reason }
+        };
+        let fields = expression.split_whitespace().collect::<Vec<_>>();
+        if fields.len() != 5 {
+            return Err(invalid(format!("expected 5 fields, found {}", fields.len())));
+        }
+        let mut days_of_week = parse_field(fields[4], 0, 7).map_err(invalid)?;
+        // 7 is another name for Sunday
+        if is_set(days_of_week, 7) {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+        let schedule = CronSchedule { expression: expression.to_string(),
+                                      minutes: parse_field(fields[0], 0, 59).map_err(invalid)?,
+                                      hours: parse_field(fields[1], 0, 23).map_err(invalid)?,
+                                      days_of_month:
+                                          parse_field(fields[2], 1, 31).map_err(invalid)?,
+                                      months: parse_field(fields[3], 1, 12).map_err(invalid)?,
+                                      days_of_week,
+                                      day_of_month_given: !fields[2].starts_with('*'),
+                                      day_of_week_given: !fields[4].starts_with('*') };
+        if schedule.next_after(Local::now().naive_local()).is_none() {
+            return Err(invalid("it never matches any date".to_string()));
+        }
+        Ok(schedule)
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", self.expression) }
+}
+
+/// Parse one field into a bitmask of the values it selects.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step.parse::<u32>()
+                               .ok()
+                               .filter(|step| *step > 0)
+                               .ok_or_else(|| format!("invalid step in '{}'", part))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, min, max)?, parse_value(end, min, max)?)
+        } else {
+            let start = parse_value(range, min, max)?;
+            // A single value with a step runs to the end of the range
+            (start, if part.contains('/') { max } else { start })
+        };
+        if start > end {
+            return Err(format!("range '{}' is backwards", range));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(value: &str, min: u32, max: u32) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(value) if (min..=max).contains(&value) => Ok(value),
+        _ => Err(format!("'{}' is not a number from {} to {}", value, min, max)),
+    }
+}
+
+fn is_set(mask: u64, value: u32) -> bool { mask & (1 << value) != 0 }
+
+/// The values selected by `mask`, in ascending order.
+fn bits(mask: u64) -> impl Iterator<Item = u32> { (0..64).filter(move |v| is_set(mask, *v)) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset,
+                 LocalResult};
+
+    fn at(s: &str) -> NaiveDateTime { NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap() }
+
+    fn next(expression: &str, after: &str) -> NaiveDateTime {
+        expression.parse::<CronSchedule>()
+                  .unwrap()
+                  .next_after(at(after))
+                  .unwrap()
+    }
+
+    #[test]
+    fn daily_schedule_runs_at_the_next_matching_time() {
+        assert_eq!(next("0 2 * * *", "2020-01-01 01:30"),
+                   at("2020-01-01 02:00"));
+        assert_eq!(next("0 2 * * *", "2020-01-01 02:00"),
+                   at("2020-01-02 02:00"));
+    }
+
+    #[test]
+    fn steps_lists_and_ranges_are_supported() {
+        assert_eq!(next("*/15 * * * *", "2020-01-01 10:16"),
+                   at("2020-01-01 10:30"));
+        assert_eq!(next("0 9-17/4 * * *", "2020-01-01 13:00"),
+                   at("2020-01-01 17:00"));
+        assert_eq!(next("30 1,13 * * *", "2020-01-01 02:00"),
+                   at("2020-01-01 13:30"));
+    }
+
+    #[test]
+    fn restricted_day_fields_match_either_day() {
+        // 2020-01-01 was a Wednesday; the 10th was a Friday.
+        assert_eq!(next("0 0 10 * 5", "2020-01-01 00:00"),
+                   at("2020-01-03 00:00"));
+        assert_eq!(next("0 0 * * 7", "2020-01-01 00:00"),
+                   at("2020-01-05 00:00"));
+    }
+
+    #[test]
+    fn malformed_expressions_are_rejected() {
+        for expression in &["",
+                            "* * * *",
+                            "60 * * * *",
+                            "* 24 * * *",
+                            "*/0 * * * *",
+                            "5-1 * * * *",
+                            "a * * * *",
+                            "0 0 30 2 *"]
+        {
+            assert!(expression.parse::<CronSchedule>().is_err(),
+                    "'{}' should be invalid",
+                    expression);
+        }
+    }
+
+    /// A time zone an hour ahead of UTC from 2020-03-08 02:00 local
+    /// time, so that 02:00 to 03:00 that day never happens.
+    #[derive(Clone, Debug)]
+    struct ClocksGoForward;
+
+    impl ClocksGoForward {
+        fn offset(ahead: bool) -> FixedOffset {
+            FixedOffset::east_opt(if ahead { 3600 } else { 0 }).unwrap()
+        }
+    }
+
+    impl TimeZone for ClocksGoForward {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Self { ClocksGoForward }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            if *local < at("2020-03-08 02:00") {
+                LocalResult::Single(Self::offset(false))
+            } else if *local < at("2020-03-08 03:00") {
+                LocalResult::None
+            } else {
+                LocalResult::Single(Self::offset(true))
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            Self::offset(*utc >= at("2020-03-08 02:00"))
+        }
+    }
+
+    #[test]
+    fn a_time_skipped_by_the_clocks_going_forward_waits_a_day() {
+        let schedule = "30 2 * * *".parse::<CronSchedule>().unwrap();
+        let now = ClocksGoForward.from_utc_datetime(&at("2020-03-08 01:00"));
+        // 02:30 on the 9th, an hour ahead of UTC
+        assert_eq!(schedule.until_next_from(now),
+                   Duration::from_secs(24 * 60 * 60 + 30 * 60));
+
+        let now = ClocksGoForward.from_utc_datetime(&at("2020-03-07 01:00"));
+        assert_eq!(schedule.until_next_from(now), Duration::from_secs(90 * 60));
+    }
+}
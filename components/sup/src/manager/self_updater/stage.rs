@@ -0,0 +1,193 @@
+//! Keeping candidates the self updater hasn't committed to out of the
+//! package tree.
+//!
+//! The Launcher starts the newest Supervisor installed in the package
+//! tree, whatever the self updater made of it. A candidate that was
+//! installed and then declined, because it failed verification for
+//! instance, would be run on the Supervisor's next restart anyway if it
+//! were left there. So would one still waiting on approval or the
+//! restart lock. Both are moved aside instead, into a directory of the
+//! updater's own in the cache, and a staged candidate only goes back
+//! into the package tree once it is about to be handed over.
+
+use habitat_core::{fs,
+                   package::{PackageIdent,
+                             PackageInstall}};
+use log::debug;
+use std::{io,
+          path::{Path,
+                 PathBuf}};
+
+/// Where the most recently declined candidate is kept.
+const DECLINED: &str = "declined";
+
+/// Where a verified candidate waits to be handed over.
+const STAGED: &str = "staged";
+
+/// The directory the updater tracking `sup_ident` keeps candidates in,
+/// under `fs_root`.
+fn updater_dir(fs_root: &Path, sup_ident: &PackageIdent) -> PathBuf {
+    fs::cache_root_path(Some(fs_root)).join("self-update")
+                                      .join(format!("{}-{}", sup_ident.origin, sup_ident.name))
+}
+
+/// The name a candidate's install is kept under.
+fn dir_name(candidate: &PackageIdent) -> String { candidate.to_string().replace('/', "-") }
+
+/// Move the declined `candidate` out of the package tree under
+/// `fs_root`, so that it is never started. Only the most recently
+/// declined candidate is kept, for inspection.
+pub(super) fn discard(candidate: &PackageIdent,
+                      fs_root: &Path,
+                      sup_ident: &PackageIdent)
+                      -> Result<(), String> {
+    let installed = fs::pkg_install_path(candidate, Some(fs_root));
+    if !installed.is_dir() {
+        return Ok(());
+    }
+    let declined = updater_dir(fs_root, sup_ident).join(DECLINED);
+    let moved = replace_dir(&declined).and_then(|_| {
+                                          move_aside(&installed,
+                                                     &declined.join(dir_name(candidate)))
+                                      });
+    if let Err(err) = moved {
+        // Across filesystems it can only be removed
+        std::fs::remove_dir_all(&installed).map_err(|e| {
+                                               format!("unable to move {} aside ({}) or remove \
+                                                        it: {}",
+                                                       candidate, err, e)
+                                           })?;
+    }
+    Ok(())
+}
+
+/// Move the verified `candidate` out of the package tree under
+/// `fs_root` until it is promoted, replacing any candidate staged
+/// before it.
+pub(super) fn stage(candidate: &PackageIdent,
+                    fs_root: &Path,
+                    sup_ident: &PackageIdent)
+                    -> Result<(), String> {
+    let staged = updater_dir(fs_root, sup_ident).join(STAGED);
+    replace_dir(&staged).and_then(|_| {
+                            move_aside(&fs::pkg_install_path(candidate, Some(fs_root)),
+                                       &staged.join(dir_name(candidate)))
+                        })
+                        .map_err(|e| format!("unable to stage {}: {}", candidate, e))
+}
+
+/// Put the staged `candidate` back into the package tree under
+/// `fs_root`, for the Launcher to start, and return its install.
+pub(super) fn promote(candidate: &PackageIdent,
+                      fs_root: &Path,
+                      sup_ident: &PackageIdent)
+                      -> Result<PackageInstall, String> {
+    let staged = updater_dir(fs_root, sup_ident).join(STAGED)
+                                                .join(dir_name(candidate));
+    let installed = fs::pkg_install_path(candidate, Some(fs_root));
+    // Installed again in the meantime, it is already in place
+    if !installed.is_dir() {
+        let parent = installed.parent().unwrap_or(fs_root);
+        std::fs::create_dir_all(parent).and_then(|_| std::fs::rename(&staged, &installed))
+                                       .map_err(|e| {
+                                           format!("unable to promote {}: {}", candidate, e)
+                                       })?;
+    }
+    PackageInstall::load(candidate, Some(fs_root)).map_err(|e| {
+                                                      format!("unable to load {}: {}", candidate, e)
+                                                  })
+}
+
+/// The staged `candidate`'s install, where it waits out of the package
+/// tree under `fs_root`.
+pub(super) fn staged(candidate: &PackageIdent,
+                     fs_root: &Path,
+                     sup_ident: &PackageIdent)
+                     -> Option<PackageInstall> {
+    let staged = updater_dir(fs_root, sup_ident).join(STAGED)
+                                                .join(dir_name(candidate));
+    if !staged.is_dir() {
+        return None;
+    }
+    Some(PackageInstall::new_from_parts(candidate.clone(),
+                                        fs_root.to_path_buf(),
+                                        fs::pkg_root_path(Some(fs_root)),
+                                        staged))
+}
+
+/// Forget any candidate left staged, as by a Supervisor that restarted
+/// while its update was waiting to be handed over.
+pub(super) fn clear(fs_root: &Path, sup_ident: &PackageIdent) {
+    let staged = updater_dir(fs_root, sup_ident).join(STAGED);
+    if staged.exists() {
+        if let Err(e) = std::fs::remove_dir_all(&staged) {
+            debug!("Unable to remove staged candidates {}, {}",
+                   staged.display(),
+                   e);
+        }
+    }
+}
+
+/// Empty `dir`, creating it if needed.
+fn replace_dir(dir: &Path) -> io::Result<()> {
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    std::fs::create_dir_all(dir)
+}
+
+/// Move `src` to `dest`, replacing anything left there before.
+pub(super) fn move_aside(src: &Path, dest: &Path) -> io::Result<()> {
+    if dest.exists() {
+        std::fs::remove_dir_all(dest)?;
+    }
+    std::fs::rename(src, dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::test_helpers::install,
+                *};
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    #[test]
+    fn a_declined_candidate_is_never_started() {
+        let root = TempDir::new().unwrap();
+        let sup_ident = PackageIdent::from_str("core/hab-sup").unwrap();
+        let current = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        let candidate = PackageIdent::from_str("core/hab-sup/1.0.1/20200102000000").unwrap();
+        install(root.path(), &current);
+        install(root.path(), &candidate);
+
+        discard(&candidate, root.path(), &sup_ident).unwrap();
+        // What the Launcher starts
+        let started = PackageInstall::load_at_least(&sup_ident, Some(root.path())).unwrap();
+        assert_eq!(started.ident(), &current);
+        assert!(updater_dir(root.path(), &sup_ident).join(DECLINED)
+                                                    .join("core-hab-sup-1.0.1-20200102000000")
+                                                    .is_dir());
+    }
+
+    #[test]
+    fn a_staged_candidate_is_only_started_once_promoted() {
+        let root = TempDir::new().unwrap();
+        let sup_ident = PackageIdent::from_str("core/hab-sup").unwrap();
+        let current = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        let candidate = PackageIdent::from_str("core/hab-sup/1.0.1/20200102000000").unwrap();
+        install(root.path(), &current);
+        install(root.path(), &candidate);
+
+        stage(&candidate, root.path(), &sup_ident).unwrap();
+        let started = PackageInstall::load_at_least(&sup_ident, Some(root.path())).unwrap();
+        assert_eq!(started.ident(), &current);
+        let waiting = staged(&candidate, root.path(), &sup_ident).unwrap();
+        assert_eq!(waiting.ident(), &candidate);
+        assert!(waiting.installed_path().join("IDENT").is_file());
+
+        let promoted = promote(&candidate, root.path(), &sup_ident).unwrap();
+        assert_eq!(promoted.ident(), &candidate);
+        let started = PackageInstall::load_at_least(&sup_ident, Some(root.path())).unwrap();
+        assert_eq!(started.ident(), &candidate);
+    }
+}
@@ -0,0 +1,215 @@
+//! A human-readable summary of the self updater.
+//!
+//! Everything reported here is available piecemeal from the event
+//! stream and the logs. The HTTP gateway's `/self_update` endpoint
+//! shows the parts an operator looks at first, a line each.
+
+use super::UpdateLag;
+use chrono::{DateTime,
+             Utc};
+use habitat_core::{package::PackageIdent,
+                   ChannelIdent};
+use std::fmt::Write;
+
+/// How times are reported.
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S UTC";
+
+/// Something the self updater noted, and when.
+#[derive(Clone, Debug)]
+pub(super) struct Noted {
+    pub at:   DateTime<Utc>,
+    pub what: String,
+}
+
+impl Noted {
+    pub(super) fn now(what: String) -> Self {
+        Noted { at: Utc::now(),
+                what }
+    }
+}
+
+/// What `render` reports.
+pub(super) struct Status<'a> {
+    pub current:       &'a PackageIdent,
+    pub channel:       &'a ChannelIdent,
+    pub url:           &'a str,
+    pub check_only:    bool,
+    /// `None` once an update has been handed over.
+    pub next_check:    Option<DateTime<Utc>>,
+    pub last_check:    Option<Noted>,
+    /// As of the last successful check.
+    pub lag:           Option<UpdateLag>,
+    pub pending:       Option<PackageIdent>,
+    /// Why an update ready to be handed over is being held back.
+    pub held:          Option<String>,
+    pub recent_errors: Vec<Noted>,
+}
+
+/// Render `status` as of `now`, a line per item.
+pub(super) fn render(status: &Status, now: DateTime<Utc>) -> String {
+    let next_check = match status.next_check {
+        Some(at) if at > now => {
+            format!("{} (in {}s)",
+                    at.format(TIME_FORMAT),
+                    (at - now).num_seconds())
+        }
+        Some(_) => String::from("due now"),
+        None => String::from("none scheduled"),
+    };
+    let last_check = match &status.last_check {
+        Some(noted) => format!("{}, {}", noted.at.format(TIME_FORMAT), noted.what),
+        None => String::from("not yet checked"),
+    };
+    let behind = match &status.lag {
+        Some(lag) if lag.is_behind() => {
+            let mut behind = match lag.releases_behind {
+                Some(1) => format!("1 release, to channel head {}", lag.latest),
+                Some(releases) => format!("{} releases, to channel head {}", releases, lag.latest),
+                None => format!("to channel head {}", lag.latest),
+            };
+            if let Some(age) = lag.age {
+                write!(behind, ", {}h older", age.as_secs() / 3600).ok();
+            }
+            behind
+        }
+        Some(_) => String::from("up to date"),
+        None => String::from("unknown"),
+    };
+    let mode = if status.check_only {
+        "check only, updates are never staged"
+    } else {
+        "staging updates"
+    };
+    let pending = status.pending
+                        .as_ref()
+                        .map_or_else(|| String::from("none"), ToString::to_string);
+    let mut report = String::new();
+    // Writing to a String can't fail
+    writeln!(report, "Current:       {}", status.current).ok();
+    writeln!(report,
+             "Channel:       {} at {}",
+             status.channel, status.url).ok();
+    writeln!(report, "Mode:          {}", mode).ok();
+    writeln!(report, "Next check:    {}", next_check).ok();
+    writeln!(report, "Last check:    {}", last_check).ok();
+    writeln!(report, "Behind:        {}", behind).ok();
+    writeln!(report, "Pending:       {}", pending).ok();
+    writeln!(report,
+             "Held:          {}",
+             status.held.as_deref().unwrap_or("no")).ok();
+    if status.recent_errors.is_empty() {
+        writeln!(report, "Recent errors: none").ok();
+    } else {
+        writeln!(report, "Recent errors:").ok();
+        for error in &status.recent_errors {
+            writeln!(report, "  {} {}", error.at.format(TIME_FORMAT), error.what).ok();
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::str::FromStr;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2020, 1, 1, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn reports_a_fresh_updater() {
+        let current = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        let channel = ChannelIdent::stable();
+        let status = Status { current:       &current,
+                              channel:       &channel,
+                              url:           "https://bldr.habitat.sh",
+                              check_only:    false,
+                              next_check:    Some(at(12, 1)),
+                              last_check:    None,
+                              lag:           None,
+                              pending:       None,
+                              held:          None,
+                              recent_errors: Vec::new(), };
+
+        let report = render(&status, at(12, 0));
+        assert_eq!(report.lines().collect::<Vec<_>>(),
+                   ["Current:       core/hab-sup/1.0.0/20200101000000",
+                    "Channel:       stable at https://bldr.habitat.sh",
+                    "Mode:          staging updates",
+                    "Next check:    2020-01-01 12:01:00 UTC (in 60s)",
+                    "Last check:    not yet checked",
+                    "Behind:        unknown",
+                    "Pending:       none",
+                    "Held:          no",
+                    "Recent errors: none"]);
+    }
+
+    #[test]
+    fn reports_a_held_update_and_recent_errors() {
+        let current = PackageIdent::from_str("core/hab-sup/1.0.0/20200101000000").unwrap();
+        let pending = PackageIdent::from_str("core/hab-sup/1.1.0/20200102000000").unwrap();
+        let channel = ChannelIdent::stable();
+        let noted = |at, what: &str| {
+            Noted { at,
+                    what: what.to_string() }
+        };
+        let status = Status { current:       &current,
+                              channel:       &channel,
+                              url:           "https://bldr.habitat.sh",
+                              check_only:    false,
+                              next_check:    Some(at(11, 0)),
+                              last_check:    Some(noted(at(11, 0), &format!("staged {}", pending))),
+                              lag:           Some(UpdateLag::new(&current, &pending)),
+                              pending:       Some(pending.clone()),
+                              held:          Some(format!("awaiting approval of {}", pending)),
+                              recent_errors: vec![noted(at(10, 0), "depot unreachable"),
+                                                  noted(at(10, 30), "depot unreachable")], };
+
+        let report = render(&status, at(12, 0));
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines[3], "Next check:    due now");
+        assert_eq!(lines[4],
+                   "Last check:    2020-01-01 11:00:00 UTC, staged \
+                    core/hab-sup/1.1.0/20200102000000");
+        assert_eq!(lines[5],
+                   "Behind:        to channel head core/hab-sup/1.1.0/20200102000000, 24h older");
+        assert_eq!(lines[6], "Pending:       core/hab-sup/1.1.0/20200102000000");
+        assert_eq!(lines[7],
+                   "Held:          awaiting approval of core/hab-sup/1.1.0/20200102000000");
+        assert_eq!(&lines[8..],
+                   ["Recent errors:",
+                    "  2020-01-01 10:00:00 UTC depot unreachable",
+                    "  2020-01-01 10:30:00 UTC depot unreachable"]);
+    }
+
+    #[test]
+    fn reports_the_lag_behind_the_channel_head_not_the_pending_update() {
+        let ident = |ident| PackageIdent::from_str(ident).unwrap();
+        let current = ident("core/hab-sup/1.0.0/20200101000000");
+        // Staying a release behind the head
+        let pending = ident("core/hab-sup/1.1.0/20200102000000");
+        let head = ident("core/hab-sup/1.2.0/20200103000000");
+        let mut lag = UpdateLag::new(&current, &head);
+        lag.releases_behind = Some(2);
+        let channel = ChannelIdent::stable();
+        let status = Status { current:       &current,
+                              channel:       &channel,
+                              url:           "https://bldr.habitat.sh",
+                              check_only:    false,
+                              next_check:    None,
+                              last_check:    None,
+                              lag:           Some(lag),
+                              pending:       Some(pending),
+                              held:          None,
+                              recent_errors: Vec::new(), };
+
+        let report = render(&status, at(12, 0));
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines[5],
+                   "Behind:        2 releases, to channel head \
+                    core/hab-sup/1.2.0/20200103000000, 48h older");
+        assert_eq!(lines[6], "Pending:       core/hab-sup/1.1.0/20200102000000");
+    }
+}
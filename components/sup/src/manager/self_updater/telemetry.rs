@@ -0,0 +1,76 @@
+//! OpenTelemetry spans for self-update checks.
+//!
+//! When enabled, each check is recorded as a `self_update.check` span
+//! carrying the candidate ident and the decision made about it, with
+//! child spans for the install and verification steps. Spans go to the
+//! global tracer provider, so they are only exported if the process
+//! has configured one. When disabled no spans are created at all.
+
+use habitat_core::package::PackageIdent;
+use opentelemetry::{global::{self,
+                             BoxedSpan},
+                    trace::{Span,
+                            Status,
+                            TraceContextExt,
+                            Tracer},
+                    Context,
+                    KeyValue};
+use std::fmt;
+
+const TRACER_NAME: &str = "habitat-sup-self-updater";
+
+/// The span for one self-update check. It ends when dropped.
+pub(super) struct CheckSpan(Option<Context>);
+
+impl CheckSpan {
+    pub(super) fn start(enabled: bool) -> Self {
+        if !enabled {
+            return CheckSpan(None);
+        }
+        let span = global::tracer(TRACER_NAME).start("self_update.check");
+        CheckSpan(Some(Context::current_with_span(span)))
+    }
+
+    /// Start a child span for one step of the check.
+    pub(super) fn step(&self, name: &'static str) -> StepSpan {
+        StepSpan(self.0
+                     .as_ref()
+                     .map(|cx| global::tracer(TRACER_NAME).start_with_context(name, cx)))
+    }
+
+    pub(super) fn candidate(&self, ident: &PackageIdent) {
+        if let Some(cx) = &self.0 {
+            cx.span()
+              .set_attribute(KeyValue::new("candidate", ident.to_string()));
+        }
+    }
+
+    pub(super) fn decision(&self, decision: &'static str) {
+        if let Some(cx) = &self.0 {
+            cx.span().set_attribute(KeyValue::new("decision", decision));
+        }
+    }
+}
+
+impl Drop for CheckSpan {
+    fn drop(&mut self) {
+        if let Some(cx) = &self.0 {
+            cx.span().end();
+        }
+    }
+}
+
+/// A child span of a `CheckSpan`.
+pub(super) struct StepSpan(Option<BoxedSpan>);
+
+impl StepSpan {
+    /// End the step, marking it failed if `result` is an error.
+    pub(super) fn end<T, E: fmt::Display>(self, result: &Result<T, E>) {
+        if let Some(mut span) = self.0 {
+            if let Err(err) = result {
+                span.set_status(Status::error(err.to_string()));
+            }
+            span.end();
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Fixtures shared by the self updater's tests.
+
+use super::install_check::SUP_CMD;
+use habitat_core::{fs,
+                   package::{metadata::MetaFile,
+                             PackageIdent,
+                             PackageInstall,
+                             PackageTarget}};
+use std::path::Path;
+
+/// Install `ident` under `fs_root` the way the self updater expects to
+/// find a Supervisor, with a binary that exits successfully.
+pub(super) fn install(fs_root: &Path, ident: &PackageIdent) -> PackageInstall {
+    install_with(fs_root,
+                 ident,
+                 &[MetaFile::Ident, MetaFile::Target, MetaFile::Path],
+                 Some("exit 0"))
+}
+
+/// Install `ident` under `fs_root` with only `metafiles`, and a binary
+/// running `script` if one is given.
+pub(super) fn install_with(fs_root: &Path,
+                           ident: &PackageIdent,
+                           metafiles: &[MetaFile],
+                           script: Option<&str>)
+                           -> PackageInstall {
+    let installed_path = fs::pkg_install_path(ident, Some(fs_root));
+    let bin_path = installed_path.join("bin");
+    std::fs::create_dir_all(&bin_path).unwrap();
+    for metafile in metafiles {
+        let content = match metafile {
+            MetaFile::Ident => ident.to_string(),
+            MetaFile::Target => PackageTarget::active_target().to_string(),
+            MetaFile::Path => {
+                fs::pkg_install_path(ident, None::<&Path>).join("bin")
+                                                          .to_string_lossy()
+                                                          .into_owned()
+            }
+            _ => String::new(),
+        };
+        std::fs::write(installed_path.join(metafile.to_string()), content).unwrap();
+    }
+    if let Some(script) = script {
+        let binary = bin_path.join(SUP_CMD);
+        std::fs::write(&binary, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+    PackageInstall::new_from_parts(ident.clone(),
+                                   fs_root.to_path_buf(),
+                                   fs::pkg_root_path(Some(fs_root)),
+                                   installed_path)
+}
@@ -0,0 +1,95 @@
+//! Passing over yanked releases.
+//!
+//! A depot can yank a release from a channel after publishing it, e.g.
+//! once it turns out to be broken, before there is a fixed release to
+//! replace it. The self updater never stages a yanked release, even the
+//! newest one in the channel, and falls back to the newest release that
+//! hasn't been yanked. Depots that don't yank releases report none as
+//! yanked, so the newest release is used as usual.
+
+use crate::{error::Error as SupError,
+            util};
+use habitat_core::{package::PackageIdent,
+                   ChannelIdent};
+use habitat_http_client::ProxyAuth;
+
+/// The releases in a channel newer than the running Supervisor, with
+/// the yanked ones set aside.
+pub(super) struct Unyanked {
+    /// The newest release that hasn't been yanked. This is the
+    /// channel's newest release unless that has been yanked, and `None`
+    /// if every release newer than the running Supervisor has been.
+    pub newest: Option<PackageIdent>,
+    /// The newer releases passed over because they were yanked, newest
+    /// first.
+    pub yanked: Vec<PackageIdent>,
+}
+
+/// Find the newest release of `tracked` in `channel` that hasn't been
+/// yanked. The channel's releases are only listed if its newest one has
+/// been yanked, and then the depot is asked about each newer release,
+/// newest first, only until one hasn't been.
+pub(super) async fn newest_unyanked(update_url: &str,
+                                    tracked: &PackageIdent,
+                                    channel: &ChannelIdent,
+                                    current: &PackageIdent,
+                                    proxy_auth: Option<&ProxyAuth>)
+                                    -> Result<Unyanked, SupError> {
+    let (head, head_yanked) =
+        util::pkg::release_status(update_url, tracked, channel, proxy_auth).await?;
+    if !head_yanked {
+        return Ok(Unyanked { newest: Some(head),
+                             yanked: Vec::new(), });
+    }
+    let mut yanked = Vec::new();
+    if current < &head {
+        yanked.push(head.clone());
+    }
+    let releases = util::pkg::channel_releases(update_url, tracked, channel, proxy_auth).await?;
+    for release in newer_releases(releases, current).into_iter()
+                                                    .filter(|release| *release != head)
+    {
+        let (release, release_yanked) =
+            util::pkg::release_status(update_url, &release, channel, proxy_auth).await?;
+        if !release_yanked {
+            return Ok(Unyanked { newest: Some(release),
+                                 yanked });
+        }
+        yanked.push(release);
+    }
+    Ok(Unyanked { newest: None,
+                  yanked })
+}
+
+/// The fully qualified `releases` newer than `current`, each once,
+/// newest first.
+fn newer_releases(mut releases: Vec<PackageIdent>, current: &PackageIdent) -> Vec<PackageIdent> {
+    releases.retain(|release| release.fully_qualified() && current < release);
+    releases.sort_by(|a, b| b.cmp(a));
+    releases.dedup();
+    releases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn ident(ident: &str) -> PackageIdent { PackageIdent::from_str(ident).unwrap() }
+
+    #[test]
+    fn only_newer_releases_are_considered_each_once_newest_first() {
+        let current = ident("core/hab-sup/1.0.1/20200102000000");
+        let releases = vec![ident("core/hab-sup/1.0.0/20200101000000"),
+                            ident("core/hab-sup/1.0.3/20200104000000"),
+                            ident("core/hab-sup/1.0.1/20200102000000"),
+                            ident("core/hab-sup/1.0.2/20200103000000"),
+                            ident("core/hab-sup/1.0.2/20200105000000"),
+                            ident("core/hab-sup/1.0.3/20200104000000"),
+                            ident("core/hab-sup")];
+        assert_eq!(newer_releases(releases, &current),
+                   vec![ident("core/hab-sup/1.0.3/20200104000000"),
+                        ident("core/hab-sup/1.0.2/20200105000000"),
+                        ident("core/hab-sup/1.0.2/20200103000000")]);
+    }
+}
@@ -1 +1,37 @@
 pub mod service;
+
+use std::io;
+
+/// The nice value given to background threads.
+#[cfg(target_os = "linux")]
+const BACKGROUND_NICE: libc::c_int = 10;
+
+/// Lower the CPU and IO priority of the calling thread, for work that
+/// should not compete with services.
+#[cfg(target_os = "linux")]
+pub fn lower_thread_priority() -> io::Result<()> {
+    // See ioprio_set(2); libc doesn't define these.
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+    const IOPRIO_CLASS_BE: libc::c_int = 2;
+    const IOPRIO_BE_LOWEST: libc::c_int = 7;
+
+    // On Linux, a `who` of 0 refers to the calling thread rather than
+    // the whole process.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, BACKGROUND_NICE) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let ioprio = (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | IOPRIO_BE_LOWEST;
+    if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Other platforms can only adjust the priority of a whole process.
+#[cfg(not(target_os = "linux"))]
+pub fn lower_thread_priority() -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Other,
+                       "thread priorities cannot be adjusted on \
+                        this platform"))
+}
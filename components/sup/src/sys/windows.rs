@@ -1 +1,17 @@
 pub mod service;
+
+use std::io;
+use winapi::um::{processthreadsapi,
+                 winbase::THREAD_MODE_BACKGROUND_BEGIN};
+
+/// Lower the CPU and IO priority of the calling thread, for work that
+/// should not compete with services.
+pub fn lower_thread_priority() -> io::Result<()> {
+    let thread = unsafe { processthreadsapi::GetCurrentThread() };
+    if unsafe { processthreadsapi::SetThreadPriority(thread, THREAD_MODE_BACKGROUND_BEGIN as i32) }
+       == 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
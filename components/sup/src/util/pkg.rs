@@ -7,7 +7,9 @@ use hab::{command::pkg::{self,
                                      UninstallHookMode,
                                      UninstallSafety}},
           error::Result as HabResult};
-use habitat_api_client::BuilderAPIClient;
+use habitat_api_client::{BuilderAPIClient,
+                         Package,
+                         TransferStats};
 use habitat_common::{cli_config::CliConfig,
                      command::package::install::{self as install_cmd,
                                                  InstallHookMode,
@@ -25,6 +27,7 @@ use habitat_core::{env as henv,
                              PackageTarget},
                    ChannelIdent,
                    AUTH_TOKEN_ENVVAR};
+use habitat_http_client::ProxyAuth;
 use std::path::Path;
 
 static LOGKEY: &str = "UT";
@@ -34,6 +37,23 @@ fn get_auth_token() -> Option<String> {
                                 .or_else(|| CliConfig::cache().auth_token.clone())
 }
 
+/// A client of the depot at `url` that authenticates with any proxy
+/// using `proxy_auth`, or as configured in the environment without it.
+fn api_client(url: &str, proxy_auth: Option<&ProxyAuth>) -> Result<BuilderAPIClient> {
+    let fs_root_path = Some(Path::new(&*FS_ROOT_PATH));
+    let api_client = match proxy_auth {
+        Some(proxy_auth) => {
+            BuilderAPIClient::new_with_proxy_auth(url,
+                                                  PRODUCT,
+                                                  VERSION,
+                                                  fs_root_path,
+                                                  Some(proxy_auth))?
+        }
+        None => BuilderAPIClient::new(url, PRODUCT, VERSION, fs_root_path)?,
+    };
+    Ok(api_client)
+}
+
 /// Helper function for use in the Supervisor to handle lower-level
 /// arguments needed for installing a package.
 pub async fn install<T>(ui: &mut T,
@@ -43,24 +63,57 @@ pub async fn install<T>(ui: &mut T,
                         -> Result<PackageInstall>
     where T: UIWriter
 {
-    let fs_root_path = Path::new(&*FS_ROOT_PATH);
-    let auth_token = get_auth_token();
-    install_cmd::start(ui,
+    install_with_cache(ui,
                        url,
-                       channel,
                        install_source,
-                       PRODUCT,
-                       VERSION,
-                       fs_root_path,
-                       &fs::cache_artifact_path(None::<String>),
-                       auth_token.as_deref(),
-                       &InstallMode::default(),
-                       &LocalPackageUsage::default(),
-                       // Install hooks are run when the supervisor
-                       // loads the package in add_service so it is
-                       // repetitive to run them here
-                       InstallHookMode::Ignore).await
-                                               .map_err(Error::from)
+                       channel,
+                       &fs::cache_artifact_path(None::<String>)).await
+}
+
+/// `install`, but downloading artifacts into `artifact_cache_path`
+/// rather than the default artifact cache.
+pub async fn install_with_cache<T>(ui: &mut T,
+                                   url: &str,
+                                   install_source: &InstallSource,
+                                   channel: &ChannelIdent,
+                                   artifact_cache_path: &Path)
+                                   -> Result<PackageInstall>
+    where T: UIWriter
+{
+    let fs_root_path = Path::new(&*FS_ROOT_PATH);
+    let api_client = BuilderAPIClient::new(url, PRODUCT, VERSION, Some(fs_root_path))?;
+    install_with_client(ui,
+                        &api_client,
+                        install_source,
+                        channel,
+                        artifact_cache_path).await
+}
+
+/// `install_with_cache`, but retrieving packages through `api_client`.
+async fn install_with_client<T>(ui: &mut T,
+                                api_client: &BuilderAPIClient,
+                                install_source: &InstallSource,
+                                channel: &ChannelIdent,
+                                artifact_cache_path: &Path)
+                                -> Result<PackageInstall>
+    where T: UIWriter
+{
+    let fs_root_path = Path::new(&*FS_ROOT_PATH);
+    let auth_token = get_auth_token();
+    install_cmd::start_with_client(ui,
+                                   api_client,
+                                   channel,
+                                   install_source,
+                                   fs_root_path,
+                                   artifact_cache_path,
+                                   auth_token.as_deref(),
+                                   &InstallMode::default(),
+                                   &LocalPackageUsage::default(),
+                                   // Install hooks are run when the supervisor
+                                   // loads the package in add_service so it is
+                                   // repetitive to run them here
+                                   InstallHookMode::Ignore).await
+                                                           .map_err(Error::from)
 }
 
 // `install` but with no ui output and the benefit of thread safety
@@ -71,6 +124,26 @@ pub async fn install_no_ui(url: &str,
     install(&mut NullUi::new(), url, install_source, channel).await
 }
 
+/// `install_with_cache` with no ui output, downloading no faster than
+/// `bandwidth_limit` bytes per second, if given, and authenticating
+/// with any proxy using `proxy_auth`. Also returns the totals of what
+/// was downloaded.
+pub async fn install_no_ui_with_limit(url: &str,
+                                      install_source: &InstallSource,
+                                      channel: &ChannelIdent,
+                                      artifact_cache_path: &Path,
+                                      bandwidth_limit: Option<u64>,
+                                      proxy_auth: Option<&ProxyAuth>)
+                                      -> Result<(PackageInstall, TransferStats)> {
+    let api_client = api_client(url, proxy_auth)?.with_bandwidth_limit(bandwidth_limit);
+    let installed = install_with_client(&mut NullUi::new(),
+                                        &api_client,
+                                        install_source,
+                                        channel,
+                                        artifact_cache_path).await?;
+    Ok((installed, api_client.transfer_stats()))
+}
+
 /// Given an InstallSource, install a new package only if an existing
 /// one that can satisfy the package identifier is not already
 /// present.
@@ -113,19 +186,83 @@ pub async fn install_channel_head(url: &str,
                                   ident: impl AsRef<PackageIdent>,
                                   channel: &ChannelIdent)
                                   -> Result<PackageInstall> {
-    let fs_root_path = Path::new(&*FS_ROOT_PATH);
-    let auth_token = get_auth_token();
-    let api_client = BuilderAPIClient::new(url, PRODUCT, VERSION, Some(fs_root_path))?;
-    // Get the latest package identifier from the channel
-    let channel_latest_ident = api_client.show_package((ident.as_ref(),
-                                                        PackageTarget::active_target()),
-                                                       channel,
-                                                       auth_token.as_deref())
-                                         .await?;
+    let channel_latest_ident = channel_head(url, ident, channel, None).await?;
     // Ensure the latest package from the channel is installed
     install_no_ui(url, &channel_latest_ident.into(), channel).await
 }
 
+/// Get the identifier of the package at the head of a channel, without
+/// downloading or installing it.
+pub async fn channel_head(url: &str,
+                          ident: impl AsRef<PackageIdent>,
+                          channel: &ChannelIdent,
+                          proxy_auth: Option<&ProxyAuth>)
+                          -> Result<PackageIdent> {
+    let auth_token = get_auth_token();
+    let api_client = api_client(url, proxy_auth)?;
+    // Get the latest package identifier from the channel
+    let ident = api_client.show_package((ident.as_ref(), PackageTarget::active_target()),
+                                        channel,
+                                        auth_token.as_deref())
+                          .await?;
+    Ok(ident)
+}
+
+/// The embargo the depot has placed on `ident` in `channel`, as the raw
+/// timestamp it reported. `None` if the depot doesn't embargo packages,
+/// or this one isn't embargoed.
+pub async fn embargo(url: &str,
+                     ident: &PackageIdent,
+                     channel: &ChannelIdent,
+                     proxy_auth: Option<&ProxyAuth>)
+                     -> Result<Option<String>> {
+    let package = release_metadata(url, ident, channel, proxy_auth).await?;
+    Ok(package.embargo_until)
+}
+
+/// What the depot knows of the release of `ident` in `channel`,
+/// including its dependencies, without downloading it.
+pub async fn release_metadata(url: &str,
+                              ident: &PackageIdent,
+                              channel: &ChannelIdent,
+                              proxy_auth: Option<&ProxyAuth>)
+                              -> Result<Package> {
+    let auth_token = get_auth_token();
+    let api_client = api_client(url, proxy_auth)?;
+    let package = api_client.show_package_metadata((ident, PackageTarget::active_target()),
+                                                   channel,
+                                                   auth_token.as_deref())
+                            .await?;
+    Ok(package)
+}
+
+/// The release of `ident` in `channel` the depot serves, and whether it
+/// has been yanked. Depots that don't yank packages report none as
+/// yanked.
+pub async fn release_status(url: &str,
+                            ident: &PackageIdent,
+                            channel: &ChannelIdent,
+                            proxy_auth: Option<&ProxyAuth>)
+                            -> Result<(PackageIdent, bool)> {
+    let package = release_metadata(url, ident, channel, proxy_auth).await?;
+    Ok((package.ident, package.yanked))
+}
+
+/// Every release of `ident`'s origin and name in `channel`.
+pub async fn channel_releases(url: &str,
+                              ident: &PackageIdent,
+                              channel: &ChannelIdent,
+                              proxy_auth: Option<&ProxyAuth>)
+                              -> Result<Vec<PackageIdent>> {
+    let auth_token = get_auth_token();
+    let api_client = api_client(url, proxy_auth)?;
+    let releases = api_client.list_channel_packages((ident, PackageTarget::active_target()),
+                                                    channel,
+                                                    auth_token.as_deref())
+                             .await?;
+    Ok(releases)
+}
+
 pub async fn uninstall_all_but_latest(ident: impl AsRef<PackageIdent>,
                                       number_latest_to_keep: usize)
                                       -> HabResult<usize> {